@@ -1,6 +1,7 @@
 // https://github.com/reviewdog/reviewdog/tree/master/proto/rdf
 
 use serde::Serialize;
+use std::ops::Range;
 
 #[derive(Serialize)]
 pub struct Diagnostic<T> {
@@ -112,20 +113,44 @@ impl DiagnosticLocation {
     }
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, Clone, Debug, PartialEq, Eq)]
 pub struct DiagnosticRange {
     pub start: DiagnosticPosition,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<DiagnosticPosition>,
 }
 
-#[derive(Serialize, Default)]
+impl DiagnosticRange {
+    /// Range of byte offsets `span` in `input`. `end` is exclusive.
+    pub fn from_span(input: &str, span: Range<usize>) -> Self {
+        Self {
+            start: DiagnosticPosition::from_offset(input, span.start),
+            end: Some(DiagnosticPosition::from_offset(input, span.end)),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Clone, Debug, PartialEq, Eq)]
 pub struct DiagnosticPosition {
     pub line: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub column: Option<u32>,
 }
 
+impl DiagnosticPosition {
+    /// Position of byte `offset` in `input`.
+    ///
+    /// Both line and column start from 1, and column is counted in bytes, as rdf requires.
+    pub fn from_offset(input: &str, offset: usize) -> Self {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() as u32 + 1,
+            column: Some((before.len() - line_start) as u32 + 1),
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum DiagnosticSeverity {
@@ -167,7 +192,7 @@ impl DiagnosticCode {
     }
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, Clone, Debug)]
 pub struct DiagnosticSuggestion {
     pub range: DiagnosticRange,
     pub text: String,
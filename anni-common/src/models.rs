@@ -5,7 +5,7 @@ use std::str::FromStr;
 
 use thiserror::Error;
 
-#[derive(Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq)]
 pub struct RawTrackIdentifier<'album_id> {
     pub album_id: Cow<'album_id, str>,
     pub disc_id: NonZeroU8,
@@ -52,7 +52,7 @@ impl<'a> Display for RawTrackIdentifier<'a> {
     }
 }
 
-#[derive(Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq)]
 pub struct TrackIdentifier {
     pub inner: RawTrackIdentifier<'static>,
 }
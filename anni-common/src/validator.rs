@@ -5,7 +5,11 @@ use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 use std::str::FromStr;
 
-pub struct Validator(&'static str, fn(&str) -> ValidateResult);
+pub struct Validator(
+    &'static str,
+    fn(&str) -> ValidateResult,
+    Option<fn(&str) -> String>,
+);
 
 impl Validator {
     #[inline]
@@ -17,6 +21,12 @@ impl Validator {
     pub fn validate(&self, input: &str) -> ValidateResult {
         self.1(input)
     }
+
+    /// Fix `input` if the validator knows how to, or return `None` otherwise.
+    #[inline]
+    pub fn fix(&self, input: &str) -> Option<String> {
+        self.2.map(|fix| fix(input))
+    }
 }
 
 impl FromStr for Validator {
@@ -24,12 +34,14 @@ impl FromStr for Validator {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "number" => Ok(Self("number", number_validator)),
-            "trim" => Ok(Self("trim", trim_validator)),
-            "date" => Ok(Self("date", date_validator)),
-            "artist" => Ok(Self("artist", artist_validator)),
-            "dot" => Ok(Self("dot", middle_dot_validator)),
-            "tidle" => Ok(Self("tidle", tidal_validator)),
+            "number" => Ok(Self("number", number_validator, None)),
+            "trim" => Ok(Self("trim", trim_validator, Some(trim_replace))),
+            "date" => Ok(Self("date", date_validator, None)),
+            "artist" => Ok(Self("artist", artist_validator, None)),
+            "dot" => Ok(Self("dot", middle_dot_validator, Some(middle_dot_replace))),
+            "tidle" => Ok(Self("tidle", tidal_validator, Some(tidal_replace))),
+            "barcode" => Ok(Self("barcode", barcode_validator, Some(barcode_replace))),
+            "isrc" => Ok(Self("isrc", isrc_validator, Some(isrc_replace))),
             _ => Err(()),
         }
     }
@@ -72,6 +84,17 @@ impl ValidatorList {
             .filter(|v| !v.1.is_pass())
             .collect()
     }
+
+    /// Apply fixes of all fixable validators to `input`.
+    ///
+    /// Returns `None` if nothing was changed.
+    pub fn fix(&self, input: &str) -> Option<String> {
+        let fixed = self
+            .0
+            .iter()
+            .fold(input.to_string(), |value, v| v.fix(&value).unwrap_or(value));
+        (fixed != input).then_some(fixed)
+    }
 }
 
 pub enum ValidateResult {
@@ -133,6 +156,10 @@ pub fn trim_validator(str: &str) -> ValidateResult {
     ValidateResult::pass_or(pass, "whitespaces need to be trimmed".to_string())
 }
 
+pub fn trim_replace(input: &str) -> String {
+    input.trim().to_string()
+}
+
 pub fn date_validator(str: &str) -> ValidateResult {
     // 2021-01-01
     // 0123456789
@@ -197,10 +224,46 @@ pub fn tidal_replace(input: &str) -> String {
     input.replace('\u{301c}', "\u{ff5e}")
 }
 
+/// Validate GTIN barcodes, including EAN-8, UPC-A, EAN-13(JAN) and GTIN-14, by their check digits.
+pub fn barcode_validator(input: &str) -> ValidateResult {
+    if !input.bytes().all(|c| c.is_ascii_digit()) || ![8, 12, 13, 14].contains(&input.len()) {
+        return ValidateResult::fail("invalid barcode".to_string());
+    }
+
+    // weights are 3 and 1 alternately, counting from the rightmost digit before check digit
+    let digits: Vec<u32> = input.bytes().map(|c| (c - b'0') as u32).collect();
+    let (check, digits) = digits.split_last().unwrap();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+    let pass = (10 - sum % 10) % 10 == *check;
+    ValidateResult::pass_or(pass, "invalid barcode check digit".to_string())
+}
+
+pub fn barcode_replace(input: &str) -> String {
+    input.replace([' ', '-'], "")
+}
+
+static ISRC: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Z]{2}[A-Z0-9]{3}[0-9]{7}$").unwrap());
+
+/// Validate ISRC, which is written without hyphens, such as `JPA000000001`.
+pub fn isrc_validator(input: &str) -> ValidateResult {
+    let pass = ISRC.is_match(input);
+    ValidateResult::pass_or(pass, "invalid ISRC".to_string())
+}
+
+pub fn isrc_replace(input: &str) -> String {
+    input.replace('-', "").trim().to_ascii_uppercase()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::validator::{
-        date_validator, middle_dot_replace, middle_dot_validator, trim_validator, ValidateResult,
+        barcode_validator, date_validator, isrc_validator, middle_dot_replace,
+        middle_dot_validator, trim_validator, ValidateResult, ValidatorList,
     };
 
     #[test]
@@ -302,4 +365,47 @@ mod tests {
             "1・2・3・4・5・6・7・8・9・1・2・3・4・5・6"
         );
     }
+
+    #[test]
+    fn validator_list_fix() {
+        let validators = ValidatorList::new(&["trim", "dot", "tidle", "artist"]).unwrap();
+        assert_eq!(
+            validators.fix(" A\u{00B7}B\u{301c} ").as_deref(),
+            Some("A・B～")
+        );
+        assert_eq!(validators.fix("A・B"), None);
+    }
+
+    #[test]
+    fn test_barcode_validator() {
+        assert!(barcode_validator("4988002000005").is_pass());
+        assert!(barcode_validator("036000291452").is_pass());
+        assert!(barcode_validator("96385074").is_pass());
+        assert!(matches!(
+            barcode_validator("4988002000000"),
+            ValidateResult::Error(_)
+        ));
+        assert!(matches!(
+            barcode_validator("498800200000"),
+            ValidateResult::Error(_)
+        ));
+        assert!(matches!(
+            barcode_validator("LACA-0001"),
+            ValidateResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_isrc_validator() {
+        assert!(isrc_validator("JPA000000001").is_pass());
+        assert!(matches!(
+            isrc_validator("jp-a00-00-00001"),
+            ValidateResult::Error(_)
+        ));
+        let validators = ValidatorList::new(&["isrc"]).unwrap();
+        assert_eq!(
+            validators.fix("jp-a00-00-00001").as_deref(),
+            Some("JPA000000001")
+        );
+    }
 }
@@ -63,19 +63,23 @@ impl AnnimClient {
             year: album.release_date().year() as i32,
             month: album.release_date().month().map(|r| r as i32),
             day: album.release_date().day().map(|r| r as i32),
-            extra: None,
+            extra: mutation::add_album::external_ids_extra(album),
+            localized: mutation::add_album::localized_input(&album.localized),
             discs: discs
                 .iter()
                 .map(|disc| mutation::add_album::CreateAlbumDiscInput {
                     title: disc.title_raw(),
                     catalog: Some(disc.catalog()),
                     artist: disc.artist_raw(),
+                    localized: mutation::add_album::localized_input(&disc.raw().localized),
                     tracks: disc
                         .iter()
                         .map(|track| mutation::add_album::CreateAlbumTrackInput {
                             title: track.title(),
                             artist: track.artist(),
                             type_: track.track_type().into(),
+                            artists: mutation::add_album::artist_credits_input(track.artists()),
+                            localized: mutation::add_album::localized_input(&track.raw().localized),
                         })
                         .collect(),
                 })
@@ -1,4 +1,4 @@
-use crate::annim::query::album::{AlbumFragment, TrackTypeInput};
+use crate::annim::query::album::{AlbumFragment, ArtistKindInput, ArtistRoleInput, TrackTypeInput};
 use crate::annim::{schema, Json, Uuid};
 
 #[derive(cynic::QueryVariables, Debug)]
@@ -25,6 +25,7 @@ pub struct AddAlbumInput<'a> {
     pub month: Option<i32>,
     pub day: Option<i32>,
     pub extra: Option<Json>,
+    pub localized: Option<Vec<LocalizedTextInput<'a>>>,
     pub discs: Vec<CreateAlbumDiscInput<'a>>,
 }
 
@@ -33,6 +34,7 @@ pub struct CreateAlbumDiscInput<'a> {
     pub title: Option<&'a str>,
     pub catalog: Option<&'a str>,
     pub artist: Option<&'a str>,
+    pub localized: Option<Vec<LocalizedTextInput<'a>>>,
     pub tracks: Vec<CreateAlbumTrackInput<'a>>,
 }
 
@@ -42,6 +44,96 @@ pub struct CreateAlbumTrackInput<'a> {
     pub artist: &'a str,
     #[cynic(rename = "type")]
     pub type_: TrackTypeInput,
+    pub artists: Option<Vec<ArtistCreditInput<'a>>>,
+    pub localized: Option<Vec<LocalizedTextInput<'a>>>,
+}
+
+#[derive(cynic::InputObject, Debug)]
+pub struct LocalizedTextInput<'a> {
+    pub locale: &'a str,
+    pub title: Option<&'a str>,
+    pub artist: Option<&'a str>,
+}
+
+/// Convert localized titles and artists, or `None` if nothing is localized.
+pub(crate) fn localized_input(
+    localized: &crate::model::Localized,
+) -> Option<Vec<LocalizedTextInput<'_>>> {
+    if localized.is_empty() {
+        return None;
+    }
+
+    Some(
+        localized
+            .iter()
+            .map(|(locale, text)| LocalizedTextInput {
+                locale,
+                title: text.title.as_deref(),
+                artist: text.artist.as_deref(),
+            })
+            .collect(),
+    )
+}
+
+/// Convert external identifiers of an album, its discs and tracks to `extra` of album,
+/// or `None` if no identifier is written.
+///
+/// Identifiers are stored under `ids`, as `{ "album": {...}, "discs": [{...}], "tracks": [[{...}]] }`.
+pub(crate) fn external_ids_extra(album: &crate::model::Album) -> Option<Json> {
+    let discs = album.discs.iter().map(|disc| &disc.ids);
+    let tracks = album.discs.iter().flat_map(|disc| &disc.tracks);
+    if album.ids.is_empty()
+        && discs.clone().all(|ids| ids.is_empty())
+        && tracks.map(|track| &track.ids).all(|ids| ids.is_empty())
+    {
+        return None;
+    }
+
+    Some(serde_json::json!({
+        "ids": {
+            "album": album.ids,
+            "discs": discs.collect::<Vec<_>>(),
+            "tracks": album
+                .discs
+                .iter()
+                .map(|disc| disc.tracks.iter().map(|track| &track.ids).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+        }
+    }))
+}
+
+/// Tags of credits are not sent, as they are referenced by id in annim.
+#[derive(cynic::InputObject, Debug)]
+pub struct ArtistCreditInput<'a> {
+    pub role: ArtistRoleInput,
+    pub custom_role: Option<&'a str>,
+    pub name: &'a str,
+    pub kind: Option<ArtistKindInput>,
+    pub tag: Option<cynic::Id>,
+}
+
+impl<'a> From<&'a crate::model::ArtistCredit> for ArtistCreditInput<'a> {
+    fn from(credit: &'a crate::model::ArtistCredit) -> Self {
+        Self {
+            role: (&credit.role).into(),
+            custom_role: match &credit.role {
+                crate::model::ArtistRole::Custom(role) => Some(role),
+                _ => None,
+            },
+            name: &credit.name,
+            kind: credit.kind.map(Into::into),
+            tag: None,
+        }
+    }
+}
+
+/// Convert credits of a track, or `None` if nothing is credited.
+pub(crate) fn artist_credits_input(
+    credits: Option<&crate::model::ArtistCredits>,
+) -> Option<Vec<ArtistCreditInput<'_>>> {
+    credits
+        .filter(|credits| !credits.is_empty())
+        .map(|credits| credits.iter().map(Into::into).collect())
 }
 
 impl<'album, 'disc> From<crate::model::TrackRef<'album, 'disc>> for CreateAlbumTrackInput<'album>
@@ -53,6 +145,8 @@ where
             title: track.title(),
             artist: track.artist(),
             type_: track.track_type().into(),
+            artists: artist_credits_input(track.artists()),
+            localized: localized_input(&track.raw().localized),
         }
     }
 }
@@ -70,6 +164,8 @@ impl<'a> From<&'a crate::model::Track> for CreateAlbumTrackInput<'a> {
                 .as_ref()
                 .unwrap_or_else(|| &crate::model::TrackType::Normal)
                 .into(),
+            artists: artist_credits_input(track.artists.as_ref()),
+            localized: localized_input(&track.localized),
         }
     }
 }
@@ -80,6 +176,7 @@ impl<'a> From<&'a crate::model::Disc> for CreateAlbumDiscInput<'a> {
             title: value.title.as_deref(),
             catalog: Some(value.catalog.as_str()),
             artist: value.artist.as_deref(),
+            localized: localized_input(&value.localized),
             tracks: value.tracks.iter().map(Into::into).collect(),
         }
     }
@@ -29,6 +29,7 @@ pub struct AlbumFragment {
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub extra: Option<Json>,
+    pub localized: Vec<LocalizedTextFragment>,
     pub discs: Vec<DiscFragment>,
 }
 
@@ -40,6 +41,7 @@ pub struct DiscFragment {
     pub title: Option<String>,
     pub catalog: Option<String>,
     pub artist: Option<String>,
+    pub localized: Vec<LocalizedTextFragment>,
     pub tags: Vec<TagBase>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
@@ -55,12 +57,31 @@ pub struct TrackFragment {
     pub artist: String,
     #[cynic(rename = "type")]
     pub type_: TrackTypeInput,
-    pub artists: Option<Json>,
+    pub artists: Vec<ArtistCreditFragment>,
+    pub localized: Vec<LocalizedTextFragment>,
     pub tags: Vec<TagBase>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
 
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "LocalizedText")]
+pub struct LocalizedTextFragment {
+    pub locale: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "ArtistCredit")]
+pub struct ArtistCreditFragment {
+    pub role: ArtistRoleInput,
+    pub custom_role: Option<String>,
+    pub name: String,
+    pub kind: Option<ArtistKindInput>,
+    pub tag: Option<TagBase>,
+}
+
 #[derive(cynic::QueryFragment, Debug)]
 #[cynic(graphql_type = "Tag")]
 pub struct TagBase {
@@ -92,6 +113,27 @@ pub enum TrackTypeInput {
     Unknown,
 }
 
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+#[cynic(graphql_type = "ArtistRole")]
+pub enum ArtistRoleInput {
+    Vocal,
+    Composer,
+    Lyricist,
+    Arranger,
+    Performer,
+    Producer,
+    Engineer,
+    Illustrator,
+    Custom,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+#[cynic(graphql_type = "ArtistKind")]
+pub enum ArtistKindInput {
+    Person,
+    Group,
+}
+
 #[derive(cynic::Enum, Clone, Copy, Debug)]
 #[cynic(graphql_type = "TagType")]
 pub enum TagTypeInput {
@@ -119,3 +161,28 @@ impl From<&crate::model::TrackType> for TrackTypeInput {
         }
     }
 }
+
+impl From<&crate::model::ArtistRole> for ArtistRoleInput {
+    fn from(value: &crate::model::ArtistRole) -> Self {
+        match value {
+            crate::model::ArtistRole::Vocal => ArtistRoleInput::Vocal,
+            crate::model::ArtistRole::Composer => ArtistRoleInput::Composer,
+            crate::model::ArtistRole::Lyricist => ArtistRoleInput::Lyricist,
+            crate::model::ArtistRole::Arranger => ArtistRoleInput::Arranger,
+            crate::model::ArtistRole::Performer => ArtistRoleInput::Performer,
+            crate::model::ArtistRole::Producer => ArtistRoleInput::Producer,
+            crate::model::ArtistRole::Engineer => ArtistRoleInput::Engineer,
+            crate::model::ArtistRole::Illustrator => ArtistRoleInput::Illustrator,
+            crate::model::ArtistRole::Custom(_) => ArtistRoleInput::Custom,
+        }
+    }
+}
+
+impl From<crate::model::ArtistKind> for ArtistKindInput {
+    fn from(value: crate::model::ArtistKind) -> Self {
+        match value {
+            crate::model::ArtistKind::Person => ArtistKindInput::Person,
+            crate::model::ArtistKind::Group => ArtistKindInput::Group,
+        }
+    }
+}
@@ -7,6 +7,9 @@ pub enum Error {
         err: toml::de::Error,
     },
 
+    #[error("album schema version {version} is not supported, the latest supported version is {supported}. Please upgrade anni to read this album")]
+    UnsupportedAlbumSchema { version: u32, supported: u32 },
+
     #[error("invalid track type: {0}")]
     InvalidTrackType(String),
 
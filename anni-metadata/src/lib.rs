@@ -5,3 +5,9 @@ pub mod utils;
 
 #[cfg(feature = "annim")]
 pub mod annim;
+
+#[cfg(feature = "source")]
+pub mod source;
+
+#[cfg(feature = "schema")]
+pub mod schema;
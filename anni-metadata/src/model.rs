@@ -1,7 +1,13 @@
 mod album;
+mod credit;
 mod date;
+mod ids;
+mod localized;
 mod tag;
 
 pub use album::*;
+pub use credit::*;
 pub use date::*;
+pub use ids::*;
+pub use localized::*;
 pub use tag::*;
@@ -1,7 +1,7 @@
 use indexmap::IndexSet;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 use uuid::Uuid;
@@ -9,11 +9,27 @@ use uuid::Uuid;
 use crate::error::Error;
 use crate::utils::is_artists_empty;
 
-use super::{AnniDate, TagRef, TagString};
+use super::{AnniDate, ArtistCredit, ArtistCredits, ExternalIds, Localized, TagRef, TagString};
 
 pub const UNKNOWN_ARTIST: &'static str = "[Unknown Artist]";
 pub const VARIOUS_ARTISTS: &'static str = "Various Artists";
 
+/// Version of album schema supported by this version of `anni-metadata`.
+///
+/// Albums without `schema` are of version 1, which is not written to album files.
+/// Bump it together with a migration in `anni_repo::schema` when the format of album files changes.
+///
+/// Version 2 allows crediting several artists for a role, see [ArtistCredits].
+pub const ALBUM_SCHEMA_VERSION: u32 = 2;
+
+fn legacy_schema() -> u32 {
+    1
+}
+
+fn is_legacy_schema(schema: &u32) -> bool {
+    *schema == legacy_schema()
+}
+
 #[derive(Debug)]
 pub struct TrackIdentifier {
     pub album_id: Uuid,
@@ -22,8 +38,14 @@ pub struct TrackIdentifier {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct Album {
+    /// Version of album schema
+    #[serde(default = "legacy_schema")]
+    #[serde(skip_serializing_if = "is_legacy_schema")]
+    #[doc(hidden)]
+    pub schema: u32,
     #[serde(rename = "album")]
     #[doc(hidden)]
     pub info: AlbumInfo,
@@ -33,7 +55,11 @@ pub struct Album {
 
 impl Album {
     pub fn new(info: AlbumInfo, discs: Vec<Disc>) -> Self {
-        let mut album = Album { info, discs };
+        let mut album = Album {
+            schema: ALBUM_SCHEMA_VERSION,
+            info,
+            discs,
+        };
         album.format();
         album
     }
@@ -43,6 +69,17 @@ impl FromStr for Album {
     type Err = Error;
 
     fn from_str(toml_str: &str) -> Result<Self, Self::Err> {
+        #[derive(Deserialize)]
+        struct Schema {
+            #[serde(default = "legacy_schema")]
+            schema: u32,
+        }
+
+        // check schema first, as newer albums may have fields unknown to this version
+        if let Ok(Schema { schema }) = toml::from_str(toml_str) {
+            Album::check_schema(schema)?;
+        }
+
         let album: Album = toml::from_str(toml_str).map_err(|e| Error::TomlParseError {
             target: "Album",
             input: toml_str.to_string(),
@@ -68,6 +105,22 @@ impl DerefMut for Album {
 }
 
 impl Album {
+    /// Version of album schema. See [ALBUM_SCHEMA_VERSION].
+    pub fn schema(&self) -> u32 {
+        self.schema
+    }
+
+    /// Check whether albums of schema version `schema` can be read by this version of anni.
+    pub fn check_schema(schema: u32) -> Result<(), Error> {
+        if schema > ALBUM_SCHEMA_VERSION {
+            return Err(Error::UnsupportedAlbumSchema {
+                version: schema,
+                supported: ALBUM_SCHEMA_VERSION,
+            });
+        }
+        Ok(())
+    }
+
     pub fn album_id(&self) -> Uuid {
         self.info.album_id
     }
@@ -85,6 +138,14 @@ impl Album {
         self.info.title.as_ref()
     }
 
+    /// Album title in the first available locale of `locales`, or the original title.
+    pub fn title_in(&self, locales: &[&str]) -> &str {
+        self.info
+            .localized
+            .title(locales)
+            .unwrap_or(self.info.title.as_str())
+    }
+
     pub fn edition(&self) -> Option<&str> {
         self.info.edition.as_deref()
     }
@@ -93,6 +154,14 @@ impl Album {
         self.info.artist.as_ref()
     }
 
+    /// Album artist in the first available locale of `locales`, or the original artist.
+    pub fn artist_in(&self, locales: &[&str]) -> &str {
+        self.info
+            .localized
+            .artist(locales)
+            .unwrap_or(self.info.artist.as_str())
+    }
+
     pub fn release_date(&self) -> &AnniDate {
         &self.info.release_date
     }
@@ -133,6 +202,15 @@ impl Album {
         self.info.tags.iter().map(|t| &t.0).collect()
     }
 
+    /// Artist credits written in album, discs and tracks, without inherit.
+    pub fn credits(&self) -> impl Iterator<Item = &ArtistCredit> {
+        let discs = self.discs.iter().flat_map(|disc| {
+            let tracks = disc.tracks.iter().flat_map(|track| track.artists.iter());
+            disc.artists.iter().chain(tracks)
+        });
+        self.info.artists.iter().chain(discs).flatten()
+    }
+
     pub fn discs_len(&self) -> usize {
         self.discs.len()
     }
@@ -211,6 +289,7 @@ impl Album {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct AlbumInfo {
     /// Album ID(uuid)
@@ -229,7 +308,7 @@ pub struct AlbumInfo {
     /// Album artists
     #[serde(default)]
     #[serde(skip_serializing_if = "is_artists_empty")]
-    pub artists: Option<HashMap<String, String>>,
+    pub artists: Option<ArtistCredits>,
     /// Album release date
     #[serde(rename = "date")]
     pub release_date: AnniDate,
@@ -242,6 +321,14 @@ pub struct AlbumInfo {
     #[serde(default)]
     // TODO: use IndexSet
     pub tags: Vec<TagString>,
+    /// Localized album titles and artists
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Localized::is_empty")]
+    pub localized: Localized,
+    /// External identifiers of the album
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ExternalIds::is_empty")]
+    pub ids: ExternalIds,
 }
 
 impl Default for AlbumInfo {
@@ -251,16 +338,19 @@ impl Default for AlbumInfo {
             title: "UnknownTitle".to_string(),
             edition: None,
             artist: UNKNOWN_ARTIST.to_string(),
-            artists: HashMap::new().into(),
+            artists: ArtistCredits::new().into(),
             release_date: AnniDate::new(2021, 1, 1),
             album_type: TrackType::Normal,
             catalog: "@TEMP".to_string(),
             tags: Default::default(),
+            localized: Default::default(),
+            ids: Default::default(),
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct Disc {
     #[serde(flatten)]
@@ -290,6 +380,7 @@ impl DerefMut for Disc {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct DiscInfo {
     /// Disc title
@@ -302,7 +393,7 @@ pub struct DiscInfo {
     pub artist: Option<String>,
     /// Disc artists
     #[serde(skip_serializing_if = "is_artists_empty")]
-    pub artists: Option<HashMap<String, String>>,
+    pub artists: Option<ArtistCredits>,
     /// Disc type
     #[serde(rename = "type")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -311,6 +402,14 @@ pub struct DiscInfo {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<TagString>,
+    /// Localized disc titles and artists
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Localized::is_empty")]
+    pub localized: Localized,
+    /// External identifiers of the disc
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ExternalIds::is_empty")]
+    pub ids: ExternalIds,
 }
 
 impl DiscInfo {
@@ -318,7 +417,7 @@ impl DiscInfo {
         catalog: String,
         title: Option<String>,
         artist: Option<String>,
-        artists: Option<HashMap<String, String>>,
+        artists: Option<ArtistCredits>,
         disc_type: Option<TrackType>,
         tags: Vec<TagString>,
     ) -> Self {
@@ -329,6 +428,8 @@ impl DiscInfo {
             catalog,
             tags,
             disc_type,
+            localized: Default::default(),
+            ids: Default::default(),
         }
     }
 }
@@ -352,6 +453,19 @@ impl<'album> DiscRef<'album> {
         self.disc.title.as_deref()
     }
 
+    /// Disc title in the first available locale of `locales`.
+    ///
+    /// Localized titles are inherited from album together with the original title.
+    pub fn title_in(&self, locales: &[&str]) -> &str {
+        localize(
+            [
+                (&self.disc.localized, self.disc.title.as_deref()),
+                (&self.album.localized, Some(self.album.title.as_str())),
+            ],
+            |localized| localized.title(locales),
+        )
+    }
+
     pub fn artist(&self) -> &str {
         self.disc
             .artist
@@ -364,7 +478,18 @@ impl<'album> DiscRef<'album> {
         self.disc.artist.as_deref()
     }
 
-    pub fn artists(&self) -> Option<&HashMap<String, String>> {
+    /// Disc artist in the first available locale of `locales`.
+    pub fn artist_in(&self, locales: &[&str]) -> &str {
+        localize(
+            [
+                (&self.disc.localized, self.disc.artist.as_deref()),
+                (&self.album.localized, Some(self.album.artist.as_str())),
+            ],
+            |localized| localized.artist(locales),
+        )
+    }
+
+    pub fn artists(&self) -> Option<&ArtistCredits> {
         self.disc.artists.as_ref()
     }
 
@@ -372,6 +497,11 @@ impl<'album> DiscRef<'album> {
         self.disc.catalog.as_ref()
     }
 
+    /// External identifiers of disc, with those of album inherited.
+    pub fn ids(&self) -> ExternalIds {
+        self.disc.ids.clone().inherit(&self.album.ids)
+    }
+
     pub fn track_type(&self) -> &TrackType {
         self.disc
             .disc_type
@@ -501,6 +631,7 @@ impl<'album> DiscRefMut<'album> {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct Track {
     /// Track title
@@ -510,7 +641,7 @@ pub struct Track {
     pub artist: Option<String>,
     /// Track artists
     #[serde(skip_serializing_if = "is_artists_empty")]
-    pub artists: Option<HashMap<String, String>>,
+    pub artists: Option<ArtistCredits>,
     /// Track type
     #[serde(rename = "type")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -519,13 +650,21 @@ pub struct Track {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<TagString>,
+    /// Localized track titles and artists
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Localized::is_empty")]
+    pub localized: Localized,
+    /// External identifiers of the track
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ExternalIds::is_empty")]
+    pub ids: ExternalIds,
 }
 
 impl Track {
     pub fn new(
         title: String,
         artist: Option<String>,
-        artists: Option<HashMap<String, String>>,
+        artists: Option<ArtistCredits>,
         track_type: Option<TrackType>,
         tags: Vec<TagString>,
     ) -> Self {
@@ -535,6 +674,8 @@ impl Track {
             artists,
             track_type,
             tags,
+            localized: Default::default(),
+            ids: Default::default(),
         }
     }
 
@@ -558,6 +699,14 @@ where
         self.track.title.as_ref()
     }
 
+    /// Track title in the first available locale of `locales`, or the original title.
+    pub fn title_in(&self, locales: &[&str]) -> &'disc str {
+        self.track
+            .localized
+            .title(locales)
+            .unwrap_or(self.track.title.as_str())
+    }
+
     pub fn artist(&self) -> &'disc str {
         self.track.artist.as_deref().unwrap_or_else(|| {
             self.disc
@@ -567,7 +716,21 @@ where
         })
     }
 
-    pub fn artists(&self) -> Option<&'disc HashMap<String, String>> {
+    /// Track artist in the first available locale of `locales`.
+    ///
+    /// Localized artists are inherited from disc and album together with the original artist.
+    pub fn artist_in(&self, locales: &[&str]) -> &'disc str {
+        localize(
+            [
+                (&self.track.localized, self.track.artist.as_deref()),
+                (&self.disc.localized, self.disc.artist.as_deref()),
+                (&self.album.localized, Some(self.album.artist.as_str())),
+            ],
+            |localized| localized.artist(locales),
+        )
+    }
+
+    pub fn artists(&self) -> Option<&'disc ArtistCredits> {
         self.track
             .artists
             .as_ref()
@@ -590,6 +753,15 @@ where
         self.track.tags.iter().map(|t| &t.0)
     }
 
+    /// External identifiers of track, with those of disc and album inherited.
+    pub fn ids(&self) -> ExternalIds {
+        self.track
+            .ids
+            .clone()
+            .inherit(&self.disc.ids)
+            .inherit(&self.album.ids)
+    }
+
     pub fn raw(&self) -> &'disc Track {
         self.track
     }
@@ -635,7 +807,7 @@ where
         self.inner().artist()
     }
 
-    pub fn artists(&self) -> Option<&HashMap<String, String>> {
+    pub fn artists(&self) -> Option<&ArtistCredits> {
         self.inner().artists()
     }
 
@@ -675,7 +847,22 @@ where
     }
 }
 
+/// Find a localized value from `levels`, ordered from track to album.
+///
+/// The first level which has either a localized value or an original value is used,
+/// so that a localized value never replaces an original value of a nearer level.
+fn localize<'a, const N: usize>(
+    levels: [(&'a Localized, Option<&'a str>); N],
+    find: impl Fn(&'a Localized) -> Option<&'a str>,
+) -> &'a str {
+    levels
+        .into_iter()
+        .find_map(|(localized, original)| find(localized).or(original))
+        .unwrap_or_default()
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum TrackType {
     Normal,
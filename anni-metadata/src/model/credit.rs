@@ -0,0 +1,426 @@
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use super::TagString;
+
+/// Role of an artist in a credit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ArtistRole {
+    Vocal,
+    Composer,
+    Lyricist,
+    Arranger,
+    Performer,
+    Producer,
+    Engineer,
+    Illustrator,
+    /// Roles not listed above, kept as written in album.
+    Custom(String),
+}
+
+impl ArtistRole {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ArtistRole::Vocal => "Vocal",
+            ArtistRole::Composer => "Composer",
+            ArtistRole::Lyricist => "Lyricist",
+            ArtistRole::Arranger => "Arranger",
+            ArtistRole::Performer => "Performer",
+            ArtistRole::Producer => "Producer",
+            ArtistRole::Engineer => "Engineer",
+            ArtistRole::Illustrator => "Illustrator",
+            ArtistRole::Custom(role) => role,
+        }
+    }
+}
+
+impl AsRef<str> for ArtistRole {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for ArtistRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ArtistRole {
+    type Err = Infallible;
+
+    /// Roles are matched exactly, so that unknown spellings are kept as custom roles
+    /// and written back unchanged.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Vocal" => ArtistRole::Vocal,
+            "Composer" => ArtistRole::Composer,
+            "Lyricist" => ArtistRole::Lyricist,
+            "Arranger" => ArtistRole::Arranger,
+            "Performer" => ArtistRole::Performer,
+            "Producer" => ArtistRole::Producer,
+            "Engineer" => ArtistRole::Engineer,
+            "Illustrator" => ArtistRole::Illustrator,
+            _ => ArtistRole::Custom(s.to_string()),
+        })
+    }
+}
+
+/// Whether a credited artist is a single person or a group.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ArtistKind {
+    Person,
+    Group,
+}
+
+impl AsRef<str> for ArtistKind {
+    fn as_ref(&self) -> &str {
+        match self {
+            ArtistKind::Person => "person",
+            ArtistKind::Group => "group",
+        }
+    }
+}
+
+/// An artist credited for a role.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtistCredit {
+    pub role: ArtistRole,
+    /// Credited name of the artist
+    pub name: String,
+    /// Kind of the artist, if known
+    pub kind: Option<ArtistKind>,
+    /// Tag in repository which describes the artist
+    pub tag: Option<TagString>,
+}
+
+impl ArtistCredit {
+    pub fn new(role: ArtistRole, name: String) -> Self {
+        Self {
+            role,
+            name,
+            kind: None,
+            tag: None,
+        }
+    }
+
+    /// Whether this credit can be written in the short `Role = "Name"` form.
+    fn is_plain(&self) -> bool {
+        self.kind.is_none() && self.tag.is_none()
+    }
+}
+
+/// Artists credited on an album, a disc or a track, in the order they are written.
+///
+/// In toml, credits are a table from role to artist:
+///
+/// ```toml
+/// artists = { Vocal = "Name", Composer = { name = "Name", type = "group", tag = "group:Name" } }
+/// ```
+///
+/// A role credited to several artists is written as an array, which is available since
+/// album schema version 2:
+///
+/// ```toml
+/// artists = { Vocal = ["Name", { name = "Other", tag = "artist:Other" }] }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArtistCredits(Vec<ArtistCredit>);
+
+impl ArtistCredits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The first artist credited for `role`.
+    pub fn get(&self, role: &ArtistRole) -> Option<&ArtistCredit> {
+        self.0.iter().find(|credit| &credit.role == role)
+    }
+
+    /// All artists credited for `role`.
+    pub fn get_all<'a>(&'a self, role: &'a ArtistRole) -> impl Iterator<Item = &'a ArtistCredit> {
+        self.0.iter().filter(move |credit| &credit.role == role)
+    }
+
+    /// Add `credit`, replacing the existing credits of the same role.
+    pub fn insert(&mut self, credit: ArtistCredit) {
+        match self.0.iter().position(|c| c.role == credit.role) {
+            Some(index) => {
+                self.0.retain(|c| c.role != credit.role);
+                self.0.insert(index, credit);
+            }
+            None => self.0.push(credit),
+        }
+    }
+
+    /// Add `credit` after the existing credits, keeping those of the same role.
+    pub fn push(&mut self, credit: ArtistCredit) {
+        self.0.push(credit);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ArtistCredit> {
+        self.0.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ArtistCredit> {
+        self.0.iter_mut()
+    }
+}
+
+impl FromIterator<ArtistCredit> for ArtistCredits {
+    fn from_iter<T: IntoIterator<Item = ArtistCredit>>(iter: T) -> Self {
+        let mut credits = ArtistCredits::new();
+        for credit in iter {
+            credits.push(credit);
+        }
+        credits
+    }
+}
+
+impl IntoIterator for ArtistCredits {
+    type Item = ArtistCredit;
+    type IntoIter = std::vec::IntoIter<ArtistCredit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ArtistCredits {
+    type Item = &'a ArtistCredit;
+    type IntoIter = std::slice::Iter<'a, ArtistCredit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Value of a role in toml, which is one or several artists.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+enum CreditValue {
+    One(CreditItem),
+    Many(Vec<CreditItem>),
+}
+
+/// An artist of a credit in toml, without its role.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+enum CreditItem {
+    Name(String),
+    Detailed(DetailedCredit),
+}
+
+impl CreditItem {
+    fn into_credit(self, role: ArtistRole) -> ArtistCredit {
+        match self {
+            CreditItem::Name(name) => ArtistCredit::new(role, name),
+            CreditItem::Detailed(credit) => ArtistCredit {
+                role,
+                name: credit.name,
+                kind: credit.kind,
+                tag: credit.tag,
+            },
+        }
+    }
+}
+
+impl From<&ArtistCredit> for CreditItem {
+    fn from(credit: &ArtistCredit) -> Self {
+        if credit.is_plain() {
+            CreditItem::Name(credit.name.clone())
+        } else {
+            CreditItem::Detailed(DetailedCredit {
+                name: credit.name.clone(),
+                kind: credit.kind,
+                tag: credit.tag.clone(),
+            })
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+struct DetailedCredit {
+    name: String,
+    #[serde(rename = "type")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<ArtistKind>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<TagString>,
+}
+
+impl Serialize for ArtistCredits {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // roles are written in the order they are first credited
+        let mut roles: Vec<&ArtistRole> = Vec::new();
+        for credit in self.0.iter() {
+            if !roles.contains(&&credit.role) {
+                roles.push(&credit.role);
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(roles.len()))?;
+        for role in roles {
+            let mut items: Vec<CreditItem> = self.get_all(role).map(Into::into).collect();
+            let value = if items.len() == 1 {
+                CreditValue::One(items.remove(0))
+            } else {
+                CreditValue::Many(items)
+            };
+            map.serialize_entry(role.as_str(), &value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ArtistCredits {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CreditsVisitor;
+
+        impl<'de> Visitor<'de> for CreditsVisitor {
+            type Value = ArtistCredits;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a table from role to artist")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut credits = ArtistCredits::new();
+                while let Some((role, value)) = map.next_entry::<String, CreditValue>()? {
+                    let role = ArtistRole::from_str(&role).unwrap();
+                    match value {
+                        CreditValue::One(item) => credits.insert(item.into_credit(role)),
+                        CreditValue::Many(items) => {
+                            for item in items {
+                                credits.push(item.into_credit(role.clone()));
+                            }
+                        }
+                    }
+                }
+                Ok(credits)
+            }
+        }
+
+        deserializer.deserialize_map(CreditsVisitor)
+    }
+}
+
+/// Credits are a table from role to artist names or detailed credits.
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for ArtistCredits {
+    fn schema_name() -> String {
+        "ArtistCredits".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        <std::collections::BTreeMap<String, CreditValue>>::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TagType;
+
+    #[derive(Serialize, Deserialize)]
+    struct TestStruct {
+        artists: ArtistCredits,
+    }
+
+    #[test]
+    fn test_artist_credits_deserialize() {
+        let TestStruct { artists } = toml::from_str(
+            r#"
+artists = { "Vocal" = "Singer", "作曲" = "Writer", "Arranger" = { name = "Band", type = "group", tag = "group:Band" } }
+"#,
+        )
+        .unwrap();
+
+        let roles: Vec<_> = artists.iter().map(|c| c.role.clone()).collect();
+        assert_eq!(
+            roles,
+            vec![
+                ArtistRole::Vocal,
+                ArtistRole::Custom("作曲".to_string()),
+                ArtistRole::Arranger
+            ]
+        );
+
+        let arranger = artists.get(&ArtistRole::Arranger).unwrap();
+        assert_eq!(arranger.name, "Band");
+        assert_eq!(arranger.kind, Some(ArtistKind::Group));
+        assert_eq!(
+            arranger.tag,
+            Some(TagString::new("Band".to_string(), TagType::Group))
+        );
+    }
+
+    #[test]
+    fn test_artist_credits_round_trip() {
+        let input = r#"[artists]
+Vocal = "Singer"
+"作曲" = "Writer"
+
+[artists.Arranger]
+name = "Band"
+type = "group"
+tag = "group:Band"
+"#;
+        let credits: TestStruct = toml::from_str(input).unwrap();
+        assert_eq!(toml::to_string(&credits).unwrap(), input);
+    }
+
+    #[test]
+    fn test_artist_credits_several_artists() {
+        let input = r#"[artists]
+Vocal = ["Singer", "Other"]
+Composer = "Writer"
+Arranger = ["Band", { name = "Member", tag = "artist:Member" }]
+"#;
+        let TestStruct { artists } = toml::from_str(input).unwrap();
+        let vocals: Vec<_> = artists
+            .get_all(&ArtistRole::Vocal)
+            .map(|credit| credit.name.as_str())
+            .collect();
+        assert_eq!(vocals, vec!["Singer", "Other"]);
+        assert_eq!(artists.len(), 5);
+
+        let output = toml::to_string(&TestStruct { artists }).unwrap();
+        let TestStruct { artists: parsed } = toml::from_str(&output).unwrap();
+        assert_eq!(parsed.len(), 5);
+        assert_eq!(parsed.get(&ArtistRole::Arranger).unwrap().name, "Band");
+        assert_eq!(
+            parsed.get_all(&ArtistRole::Arranger).nth(1).unwrap().tag,
+            Some(TagString::new("Member".to_string(), TagType::Artist))
+        );
+    }
+}
@@ -82,6 +82,32 @@ impl<'de> Deserialize<'de> for AnniDate {
     }
 }
 
+/// Dates are strings in json, and toml dates or strings in toml.
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for AnniDate {
+    fn schema_name() -> String {
+        "AnniDate".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Metadata, SchemaObject, StringValidation};
+
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some("Date in format of yyyy-mm-dd, yyyy-mm or yyyy".to_string()),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(r"^(\d{2}|\d{4})(-\d{1,2}(-\d{1,2})?)?$".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl FromStr for AnniDate {
     type Err = Error;
 
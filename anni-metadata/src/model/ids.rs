@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// External identifiers of an album, a disc or a track.
+///
+/// ```toml
+/// [album.ids]
+/// barcode = "4988002000005"
+/// musicbrainz-release = "00000000-0000-0000-0000-000000000000"
+///
+/// [[discs.tracks]]
+/// title = "Track"
+/// ids = { isrc = "JPA000000001" }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExternalIds {
+    /// Barcode(EAN/UPC/JAN) of the release
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub barcode: Option<String>,
+    /// International Standard Recording Code
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
+    /// MusicBrainz release ID
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_release: Option<Uuid>,
+    /// MusicBrainz release group ID
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_release_group: Option<Uuid>,
+    /// MusicBrainz recording ID
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_recording: Option<Uuid>,
+    /// MusicBrainz track ID, which identifies a track in a release
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_track: Option<Uuid>,
+}
+
+impl ExternalIds {
+    /// Names of all identifiers, as written in metadata.
+    pub const KINDS: [&'static str; 6] = [
+        "barcode",
+        "isrc",
+        "musicbrainz-release",
+        "musicbrainz-release-group",
+        "musicbrainz-recording",
+        "musicbrainz-track",
+    ];
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Get identifier by its name in [ExternalIds::KINDS].
+    pub fn get(&self, kind: &str) -> Option<String> {
+        match kind {
+            "barcode" => self.barcode.clone(),
+            "isrc" => self.isrc.clone(),
+            "musicbrainz-release" => self.musicbrainz_release.map(|id| id.to_string()),
+            "musicbrainz-release-group" => self.musicbrainz_release_group.map(|id| id.to_string()),
+            "musicbrainz-recording" => self.musicbrainz_recording.map(|id| id.to_string()),
+            "musicbrainz-track" => self.musicbrainz_track.map(|id| id.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Set identifier by its name in [ExternalIds::KINDS].
+    ///
+    /// Returns `false` if `kind` is unknown, or `value` is not a valid MusicBrainz ID.
+    pub fn set(&mut self, kind: &str, value: String) -> bool {
+        let uuid = || Uuid::from_str(&value).ok();
+        match kind {
+            "barcode" => self.barcode = Some(value),
+            "isrc" => self.isrc = Some(value),
+            "musicbrainz-release" => self.musicbrainz_release = uuid(),
+            "musicbrainz-release-group" => self.musicbrainz_release_group = uuid(),
+            "musicbrainz-recording" => self.musicbrainz_recording = uuid(),
+            "musicbrainz-track" => self.musicbrainz_track = uuid(),
+            _ => return false,
+        }
+        self.get(kind).is_some()
+    }
+
+    /// Iterate over identifiers which are set, with their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, String)> + '_ {
+        Self::KINDS
+            .into_iter()
+            .filter_map(|kind| self.get(kind).map(|value| (kind, value)))
+    }
+
+    /// Fill identifiers which are not set with those in `parent`.
+    pub fn inherit(mut self, parent: &ExternalIds) -> Self {
+        for (kind, value) in parent.iter() {
+            if self.get(kind).is_none() {
+                self.set(kind, value);
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_ids() {
+        let ids: ExternalIds = toml::from_str(
+            r#"
+barcode = "4988002000005"
+musicbrainz-release = "f1a2b3c4-d5e6-4f70-8190-a1b2c3d4e5f6"
+"#,
+        )
+        .unwrap();
+        assert!(!ids.is_empty());
+        assert_eq!(
+            ids.iter().map(|(kind, _)| kind).collect::<Vec<_>>(),
+            vec!["barcode", "musicbrainz-release"]
+        );
+
+        let mut track = ExternalIds::default();
+        assert!(track.is_empty());
+        assert!(track.set("isrc", "JPA000000001".to_string()));
+        assert!(!track.set("musicbrainz-track", "not-a-uuid".to_string()));
+        assert!(!track.set("unknown", "value".to_string()));
+
+        let track = track.inherit(&ids);
+        assert_eq!(track.isrc.as_deref(), Some("JPA000000001"));
+        assert_eq!(track.barcode.as_deref(), Some("4988002000005"));
+        assert_eq!(track.musicbrainz_release, ids.musicbrainz_release);
+    }
+}
@@ -0,0 +1,125 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Title and artist of an album, a disc or a track in one locale.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct LocalizedText {
+    /// Localized title
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Localized artist
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+}
+
+/// Localized titles and artists, keyed by locale, in the order they are written.
+///
+/// Locales are BCP 47 language tags, such as `en`, `zh-Hans`, or `ja-Latn` for romaji:
+///
+/// ```toml
+/// [album.localized.ja-Latn]
+/// title = "Title in romaji"
+/// artist = "Artist in romaji"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct Localized(IndexMap<String, LocalizedText>);
+
+impl Localized {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get variants of exactly `locale`. Locales are compared case-insensitively.
+    pub fn get(&self, locale: &str) -> Option<&LocalizedText> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(locale))
+            .map(|(_, text)| text)
+    }
+
+    pub fn insert(&mut self, locale: String, text: LocalizedText) {
+        self.0.insert(locale, text);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &LocalizedText)> {
+        self.0.iter().map(|(locale, text)| (locale.as_str(), text))
+    }
+
+    /// Find title for the first matching locale in `locales`. See [locale_fallback].
+    pub fn title(&self, locales: &[&str]) -> Option<&str> {
+        self.find(locales, |text| text.title.as_deref())
+    }
+
+    /// Find artist for the first matching locale in `locales`. See [locale_fallback].
+    pub fn artist(&self, locales: &[&str]) -> Option<&str> {
+        self.find(locales, |text| text.artist.as_deref())
+    }
+
+    fn find<'a, F>(&'a self, locales: &[&str], field: F) -> Option<&'a str>
+    where
+        F: Fn(&'a LocalizedText) -> Option<&'a str>,
+    {
+        locales
+            .iter()
+            .flat_map(|locale| locale_fallback(locale))
+            .find_map(|locale| self.get(locale).and_then(&field))
+    }
+}
+
+impl FromIterator<(String, LocalizedText)> for Localized {
+    fn from_iter<T: IntoIterator<Item = (String, LocalizedText)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Locales to try for `locale`, from the most specific one.
+///
+/// `zh-Hans-CN` falls back to `zh-Hans`, and then `zh`.
+pub fn locale_fallback(locale: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(locale), |locale| {
+        locale.rfind('-').map(|index| &locale[..index])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_fallback() {
+        assert_eq!(
+            locale_fallback("zh-Hans-CN").collect::<Vec<_>>(),
+            vec!["zh-Hans-CN", "zh-Hans", "zh"]
+        );
+        assert_eq!(locale_fallback("en").collect::<Vec<_>>(), vec!["en"]);
+    }
+
+    #[test]
+    fn test_localized_title() {
+        let localized: Localized = toml::from_str(
+            r#"
+en = { title = "English" }
+zh-Hans = { title = "简体中文", artist = "艺术家" }
+ja-Latn = { title = "Romaji" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(localized.title(&["zh-Hans-CN"]), Some("简体中文"));
+        assert_eq!(localized.title(&["zh-hans"]), Some("简体中文"));
+        assert_eq!(localized.title(&["fr", "ja-Latn", "en"]), Some("Romaji"));
+        assert_eq!(localized.title(&["zh"]), None);
+        assert_eq!(localized.artist(&["en", "zh-Hans"]), Some("艺术家"));
+        assert_eq!(localized.artist(&[]), None);
+    }
+}
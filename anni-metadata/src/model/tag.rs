@@ -11,6 +11,7 @@ use crate::error::Error;
 
 /// Simple reference to a tag with its name and edition.
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TagRef<'a> {
     /// Tag name
     name: Cow<'a, str>,
@@ -76,6 +77,7 @@ impl<'a> TagRef<'a> {
         Tag {
             inner: self.full_clone(),
             names: Default::default(),
+            aliases: Vec::new(),
             parents,
             children: Vec::new(),
         }
@@ -165,6 +167,29 @@ impl<'de> Deserialize<'de> for TagString {
     }
 }
 
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for TagString {
+    fn schema_name() -> String {
+        "TagString".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Metadata, SchemaObject};
+
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(
+                    "Tag in format of type:name, or name if tag type is omitted".to_string(),
+                ),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl Display for TagString {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         Display::fmt(&self.0, f)
@@ -189,7 +214,9 @@ impl From<TagRef<'static>> for TagString {
     }
 }
 
+/// Tag defined in a tag file.
 #[derive(Serialize, Deserialize, Debug, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct Tag {
     #[serde(flatten)]
@@ -197,6 +224,12 @@ pub struct Tag {
     /// Tag localized name
     #[serde(default)]
     names: HashMap<String, String>,
+    /// Other names of the tag, such as alternative spellings.
+    ///
+    /// References to an alias resolve to this tag, with the same tag type.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
     /// Tag parents
     #[serde(default)]
     #[serde(rename = "included-by")]
@@ -209,6 +242,7 @@ pub struct Tag {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum TagType {
     Artist,
@@ -260,6 +294,25 @@ impl FromStr for TagType {
     }
 }
 
+impl TagType {
+    /// Whether a tag of this type can include a child tag of type `child`.
+    ///
+    /// - Categories classify other tags, but do not include artists or groups.
+    /// - Categories can only be included by other categories.
+    /// - Artists do not include groups or organizations.
+    ///
+    /// Relations with [TagType::Unknown] are not checked.
+    pub fn can_include(&self, child: &TagType) -> bool {
+        match (self, child) {
+            (TagType::Unknown, _) | (_, TagType::Unknown) => true,
+            (TagType::Category, child) => !matches!(child, TagType::Artist | TagType::Group),
+            (_, TagType::Category) => false,
+            (TagType::Artist, TagType::Group | TagType::Organization) => false,
+            _ => true,
+        }
+    }
+}
+
 impl Display for TagType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.as_ref())
@@ -297,6 +350,17 @@ impl Tag {
         self.inner.tag_type()
     }
 
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    /// Aliases as references with the type of this tag.
+    pub fn alias_refs(&self) -> impl Iterator<Item = TagRef<'_>> {
+        self.aliases
+            .iter()
+            .map(|alias| TagRef::new(alias.as_str(), self.tag_type().clone()))
+    }
+
     pub fn parents<'me, 'tag>(&'me self) -> impl Iterator<Item = &'me TagRef<'tag>>
     where
         'tag: 'me,
@@ -311,6 +375,11 @@ impl Tag {
         self.children.iter().map(|i| &i.0)
     }
 
+    /// Parents and children of this tag, for rewriting references in place.
+    pub fn relations_mut(&mut self) -> impl Iterator<Item = &mut TagString> {
+        self.parents.iter_mut().chain(self.children.iter_mut())
+    }
+
     pub fn get_owned_ref(&self) -> TagRef<'static> {
         TagRef {
             name: self.inner.name.clone(),
@@ -325,7 +394,9 @@ impl AsRef<TagRef<'static>> for Tag {
     }
 }
 
+/// Tags defined in a tag file.
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Tags {
     tag: Vec<Tag>,
 }
@@ -338,7 +409,7 @@ impl Tags {
 
 #[cfg(test)]
 mod tests {
-    use crate::model::{TagRef, TagString, TagType};
+    use crate::model::{Tag, TagRef, TagString, TagType, Tags};
 
     #[test]
     fn test_tag_string_serialize() {
@@ -378,4 +449,35 @@ tags = [
         assert_eq!(tags[3].name, "implicit:tag-type with :");
         assert_eq!(tags[3].tag_type, TagType::Unknown);
     }
+
+    #[test]
+    fn test_tag_aliases() {
+        let tags = toml::from_str::<Tags>(
+            r#"
+[[tag]]
+name = "Name"
+type = "artist"
+aliases = ["Alternative Name"]
+"#,
+        )
+        .unwrap()
+        .into_inner();
+
+        let tag: &Tag = &tags[0];
+        assert_eq!(tag.aliases(), ["Alternative Name"]);
+        assert_eq!(
+            tag.alias_refs().collect::<Vec<_>>(),
+            vec![TagRef::new("Alternative Name", TagType::Artist)]
+        );
+    }
+
+    #[test]
+    fn test_tag_type_can_include() {
+        assert!(TagType::Group.can_include(&TagType::Artist));
+        assert!(!TagType::Category.can_include(&TagType::Artist));
+        assert!(TagType::Category.can_include(&TagType::Category));
+        assert!(!TagType::Group.can_include(&TagType::Category));
+        assert!(!TagType::Artist.can_include(&TagType::Group));
+        assert!(TagType::Unknown.can_include(&TagType::Artist));
+    }
 }
@@ -0,0 +1,18 @@
+//! [JSON Schema](https://json-schema.org) of album and tag files, generated from models.
+//!
+//! Schemas describe the structure of toml files, which is also used when albums are exported as json.
+//! Dates are described as strings, so that they match both toml dates and json strings.
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::model::{Album, Tags};
+
+/// JSON Schema of album files.
+pub fn album_schema() -> RootSchema {
+    schema_for!(Album)
+}
+
+/// JSON Schema of tag files.
+pub fn tag_schema() -> RootSchema {
+    schema_for!(Tags)
+}
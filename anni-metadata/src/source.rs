@@ -0,0 +1,160 @@
+//! Remote sources of album metadata, used to draft albums before adding them to repository.
+//!
+//! Requests are sent with [HttpFetch], so that sources can be tested against recorded responses.
+use std::fmt::Display;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+
+use crate::model::{AnniDate, Track, TrackType};
+
+mod discogs;
+mod itunes;
+mod musicbrainz;
+
+pub use discogs::DiscogsSource;
+pub use itunes::ITunesSource;
+pub use musicbrainz::MusicBrainzSource;
+
+/// An album found by [MetadataSource::search].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchCandidate {
+    /// Identifier of album, which can be passed to [MetadataSource::fetch]
+    pub id: String,
+    pub title: String,
+    pub artist: Option<String>,
+    pub catalog: Option<String>,
+    pub release_date: Option<String>,
+}
+
+impl Display for SearchCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(catalog) = &self.catalog {
+            write!(f, "[{catalog}] ")?;
+        }
+        write!(f, "{}", self.title)?;
+        if let Some(artist) = &self.artist {
+            write!(f, " / {artist}")?;
+        }
+        if let Some(release_date) = &self.release_date {
+            write!(f, " ({release_date})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A source of album metadata.
+#[async_trait]
+pub trait MetadataSource: Send + Sync {
+    /// Name of the source, such as `discogs`.
+    fn name(&self) -> &'static str;
+
+    /// Fetch album with identifier `id` in this source.
+    ///
+    /// The returned album is a draft. Its catalog may be empty, and fields which the source
+    /// does not provide are left as default.
+    async fn fetch(&self, id: &str) -> anyhow::Result<crate::model::Album>;
+
+    /// Search albums matching `query`.
+    async fn search(&self, query: &str) -> anyhow::Result<Vec<SearchCandidate>>;
+}
+
+/// Client used by sources to send HTTP GET requests.
+#[async_trait]
+pub trait HttpFetch: Send + Sync {
+    /// Get body of `url`. Responses with non-successful status are errors.
+    async fn get(&self, url: &str) -> anyhow::Result<String>;
+}
+
+/// [HttpFetch] which sends requests with `reqwest`.
+pub struct ReqwestFetch(reqwest::Client);
+
+impl Default for ReqwestFetch {
+    fn default() -> Self {
+        // MusicBrainz and Discogs reject requests without user agent
+        let client = reqwest::Client::builder()
+            .user_agent(concat!(
+                "anni-metadata/",
+                env!("CARGO_PKG_VERSION"),
+                " ( https://github.com/ProjectAnni/anni )"
+            ))
+            .build()
+            .unwrap();
+        Self(client)
+    }
+}
+
+#[async_trait]
+impl HttpFetch for ReqwestFetch {
+    async fn get(&self, url: &str) -> anyhow::Result<String> {
+        let response = self.0.get(url).send().await?.error_for_status()?;
+        Ok(response.text().await?)
+    }
+}
+
+/// Build url from `base`, `path` and query `params`.
+fn build_url(base: &str, path: &str, params: &[(&str, &str)]) -> anyhow::Result<String> {
+    let url = format!("{}/{}", base.trim_end_matches('/'), path);
+    Ok(reqwest::Url::parse_with_params(&url, params)?.to_string())
+}
+
+/// Parse dates like `2020-12-16`, `2020-12-16T08:00:00Z` or `2020-00-00`, where `00` means unknown.
+fn parse_date(date: &str) -> AnniDate {
+    let date = date.split('T').next().unwrap_or_default();
+    let mut date = date;
+    while let Some(rest) = date.strip_suffix("-00") {
+        date = rest;
+    }
+    AnniDate::from_str(date).unwrap_or(AnniDate::UNKNOWN)
+}
+
+/// Join names of artists in the format used in repository.
+fn join_artists<'a, I>(names: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let artist = names.into_iter().collect::<Vec<_>>().join("、");
+    (!artist.is_empty()).then_some(artist)
+}
+
+fn draft_track(title: String, artist: Option<String>) -> Track {
+    let track_type = TrackType::guess(&title);
+    Track::new(title, artist, None, track_type, Default::default())
+}
+
+/// Group tracks by disc number, keeping the order discs first appear.
+fn group_discs<T>(tracks: impl IntoIterator<Item = (u32, T)>) -> Vec<Vec<T>> {
+    let mut discs: Vec<(u32, Vec<T>)> = Vec::new();
+    for (disc_number, track) in tracks {
+        match discs.iter_mut().find(|(number, _)| *number == disc_number) {
+            Some((_, disc)) => disc.push(track),
+            None => discs.push((disc_number, vec![track])),
+        }
+    }
+    discs.into_iter().map(|(_, disc)| disc).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2020-12-16"), AnniDate::new(2020, 12, 16));
+        assert_eq!(
+            parse_date("2020-12-16T08:00:00Z"),
+            AnniDate::new(2020, 12, 16)
+        );
+        assert_eq!(
+            parse_date("2020-00-00"),
+            AnniDate::from_str("2020").unwrap()
+        );
+        assert_eq!(parse_date(""), AnniDate::UNKNOWN);
+    }
+
+    #[test]
+    fn test_group_discs() {
+        let discs = group_discs([(1, "a"), (2, "b"), (1, "c")]);
+        assert_eq!(discs, vec![vec!["a", "c"], vec!["b"]]);
+    }
+}
@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{
+    build_url, draft_track, group_discs, join_artists, parse_date, HttpFetch, MetadataSource,
+    ReqwestFetch, SearchCandidate,
+};
+use crate::model::{Album, AlbumInfo, AnniDate, Disc, DiscInfo, ExternalIds, UNKNOWN_ARTIST};
+
+/// Releases on [Discogs](https://www.discogs.com), identified by release id.
+///
+/// Searching requires a personal access token.
+pub struct DiscogsSource {
+    fetch: Box<dyn HttpFetch>,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl DiscogsSource {
+    pub const BASE_URL: &'static str = "https://api.discogs.com";
+
+    pub fn new(token: Option<String>) -> Self {
+        Self::with_fetch(ReqwestFetch::default(), Self::BASE_URL, token)
+    }
+
+    pub fn with_fetch<F>(fetch: F, base_url: &str, token: Option<String>) -> Self
+    where
+        F: HttpFetch + 'static,
+    {
+        Self {
+            fetch: Box::new(fetch),
+            base_url: base_url.to_string(),
+            token,
+        }
+    }
+
+    fn url(&self, path: &str, params: &[(&str, &str)]) -> anyhow::Result<String> {
+        let mut params = params.to_vec();
+        if let Some(token) = &self.token {
+            params.push(("token", token));
+        }
+        build_url(&self.base_url, path, &params)
+    }
+}
+
+#[async_trait]
+impl MetadataSource for DiscogsSource {
+    fn name(&self) -> &'static str {
+        "discogs"
+    }
+
+    async fn fetch(&self, id: &str) -> anyhow::Result<Album> {
+        let url = self.url(&format!("releases/{id}"), &[])?;
+        let release: Release = serde_json::from_str(&self.fetch.get(&url).await?)?;
+        Ok(release.into())
+    }
+
+    async fn search(&self, query: &str) -> anyhow::Result<Vec<SearchCandidate>> {
+        if self.token.is_none() {
+            anyhow::bail!("searching on Discogs requires a personal access token");
+        }
+
+        let url = self.url("database/search", &[("q", query), ("type", "release")])?;
+        let result: SearchResult = serde_json::from_str(&self.fetch.get(&url).await?)?;
+        Ok(result
+            .results
+            .into_iter()
+            .map(|result| {
+                // title of search result is `{artist} - {title}`
+                let (artist, title) = match result.title.split_once(" - ") {
+                    Some((artist, title)) => (Some(artist.to_string()), title.to_string()),
+                    None => (None, result.title),
+                };
+                SearchCandidate {
+                    id: result.id.to_string(),
+                    title,
+                    artist,
+                    catalog: result.catno.filter(|catno| is_catalog(catno)),
+                    release_date: result.year,
+                }
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    title: String,
+    #[serde(default)]
+    artists: Vec<Artist>,
+    released: Option<String>,
+    #[serde(default)]
+    labels: Vec<Label>,
+    #[serde(default)]
+    identifiers: Vec<Identifier>,
+    #[serde(default)]
+    tracklist: Vec<TrackItem>,
+}
+
+#[derive(Deserialize)]
+struct Artist {
+    name: String,
+    /// Artist name variation, which is the name credited on this release
+    #[serde(default)]
+    anv: String,
+}
+
+impl Artist {
+    fn name(&self) -> &str {
+        if !self.anv.is_empty() {
+            return &self.anv;
+        }
+        // artists with the same name are suffixed with a number, like `Name (2)`
+        match self.name.rsplit_once(" (") {
+            Some((name, suffix))
+                if suffix
+                    .strip_suffix(')')
+                    .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit())) =>
+            {
+                name
+            }
+            _ => &self.name,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Label {
+    catno: String,
+}
+
+#[derive(Deserialize)]
+struct Identifier {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct TrackItem {
+    position: String,
+    /// `track`, `heading` or `index`
+    #[serde(rename = "type_")]
+    kind: String,
+    title: String,
+    #[serde(default)]
+    artists: Vec<Artist>,
+}
+
+impl TrackItem {
+    /// Disc number from positions like `1-01` or `CD2-3`. Other positions are on the first disc.
+    fn disc_number(&self) -> u32 {
+        self.position
+            .split_once(['-', '.'])
+            .and_then(|(disc, _)| {
+                disc.trim_start_matches(|c: char| !c.is_ascii_digit())
+                    .parse()
+                    .ok()
+            })
+            .unwrap_or(1)
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    results: Vec<SearchResultItem>,
+}
+
+#[derive(Deserialize)]
+struct SearchResultItem {
+    id: u64,
+    title: String,
+    catno: Option<String>,
+    year: Option<String>,
+}
+
+fn is_catalog(catno: &str) -> bool {
+    !catno.is_empty() && !catno.eq_ignore_ascii_case("none")
+}
+
+impl From<Release> for Album {
+    fn from(release: Release) -> Self {
+        let catalog = release
+            .labels
+            .iter()
+            .map(|label| label.catno.as_str())
+            .find(|catno| is_catalog(catno))
+            .unwrap_or_default()
+            .to_string();
+        let barcode = release
+            .identifiers
+            .iter()
+            .find(|identifier| identifier.kind == "Barcode")
+            .map(|identifier| identifier.value.replace(|c: char| !c.is_ascii_digit(), ""));
+
+        let tracks = release
+            .tracklist
+            .iter()
+            // headings are not tracks, and index tracks are kept as a single track
+            .filter(|track| track.kind != "heading")
+            .map(|track| {
+                let artist = join_artists(track.artists.iter().map(Artist::name));
+                (
+                    track.disc_number(),
+                    draft_track(track.title.clone(), artist),
+                )
+            });
+        let discs = group_discs(tracks)
+            .into_iter()
+            .map(|tracks| {
+                let disc = DiscInfo::new(catalog.clone(), None, None, None, None, Vec::new());
+                Disc::new(disc, tracks)
+            })
+            .collect();
+
+        Album::new(
+            AlbumInfo {
+                title: release.title,
+                artist: join_artists(release.artists.iter().map(Artist::name))
+                    .unwrap_or_else(|| UNKNOWN_ARTIST.to_string()),
+                release_date: release
+                    .released
+                    .as_deref()
+                    .map(parse_date)
+                    .unwrap_or(AnniDate::UNKNOWN),
+                catalog,
+                ids: ExternalIds {
+                    barcode: barcode.filter(|barcode| !barcode.is_empty()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            discs,
+        )
+    }
+}
@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{
+    build_url, draft_track, group_discs, parse_date, HttpFetch, MetadataSource, ReqwestFetch,
+    SearchCandidate,
+};
+use crate::model::{Album, AlbumInfo, Disc, DiscInfo};
+
+/// Albums on iTunes Store, identified by collection id, fetched with the
+/// [iTunes Search API](https://performance-partners.apple.com/search-api).
+///
+/// iTunes does not provide catalog of albums.
+pub struct ITunesSource {
+    fetch: Box<dyn HttpFetch>,
+    base_url: String,
+    /// Two-letter country code of the store to search, such as `jp`
+    country: Option<String>,
+}
+
+impl ITunesSource {
+    pub const BASE_URL: &'static str = "https://itunes.apple.com";
+
+    pub fn new(country: Option<String>) -> Self {
+        Self::with_fetch(ReqwestFetch::default(), Self::BASE_URL, country)
+    }
+
+    pub fn with_fetch<F>(fetch: F, base_url: &str, country: Option<String>) -> Self
+    where
+        F: HttpFetch + 'static,
+    {
+        Self {
+            fetch: Box::new(fetch),
+            base_url: base_url.to_string(),
+            country,
+        }
+    }
+
+    async fn request(&self, path: &str, params: &[(&str, &str)]) -> anyhow::Result<Vec<Item>> {
+        let mut params = params.to_vec();
+        if let Some(country) = &self.country {
+            params.push(("country", country));
+        }
+        let url = build_url(&self.base_url, path, &params)?;
+        let response: Response = serde_json::from_str(&self.fetch.get(&url).await?)?;
+        Ok(response.results)
+    }
+}
+
+#[async_trait]
+impl MetadataSource for ITunesSource {
+    fn name(&self) -> &'static str {
+        "itunes"
+    }
+
+    async fn fetch(&self, id: &str) -> anyhow::Result<Album> {
+        let items = self
+            .request("lookup", &[("id", id), ("entity", "song")])
+            .await?;
+        let mut collection = None;
+        let mut songs = Vec::new();
+        for item in items {
+            match item {
+                Item::Collection(item) => collection = Some(item),
+                Item::Track(item) => songs.push(item),
+                Item::Other => {}
+            }
+        }
+        let Some(collection) = collection else {
+            anyhow::bail!("album {id} was not found on iTunes");
+        };
+
+        songs.sort_by_key(|song| (song.disc_number, song.track_number));
+        let discs = group_discs(songs.into_iter().map(|song| {
+            let artist = (song.artist_name != collection.artist_name).then_some(song.artist_name);
+            (song.disc_number, draft_track(song.track_name, artist))
+        }))
+        .into_iter()
+        .map(|tracks| {
+            let disc = DiscInfo::new(String::new(), None, None, None, None, Vec::new());
+            Disc::new(disc, tracks)
+        })
+        .collect();
+
+        Ok(Album::new(
+            AlbumInfo {
+                title: collection.collection_name,
+                artist: collection.artist_name,
+                release_date: parse_date(&collection.release_date),
+                catalog: String::new(),
+                ..Default::default()
+            },
+            discs,
+        ))
+    }
+
+    async fn search(&self, query: &str) -> anyhow::Result<Vec<SearchCandidate>> {
+        let items = self
+            .request("search", &[("term", query), ("entity", "album")])
+            .await?;
+        Ok(items
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Collection(collection) => Some(SearchCandidate {
+                    id: collection.collection_id.to_string(),
+                    title: collection.collection_name,
+                    artist: Some(collection.artist_name),
+                    catalog: None,
+                    release_date: collection
+                        .release_date
+                        .split('T')
+                        .next()
+                        .map(str::to_string),
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    results: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "wrapperType", rename_all = "camelCase")]
+enum Item {
+    Collection(Collection),
+    Track(Song),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Collection {
+    collection_id: u64,
+    collection_name: String,
+    artist_name: String,
+    release_date: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Song {
+    track_name: String,
+    artist_name: String,
+    disc_number: u32,
+    track_number: u32,
+}
@@ -0,0 +1,218 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::{
+    build_url, draft_track, join_artists, parse_date, HttpFetch, MetadataSource, ReqwestFetch,
+    SearchCandidate,
+};
+use crate::model::{Album, AlbumInfo, AnniDate, Disc, DiscInfo, ExternalIds, UNKNOWN_ARTIST};
+
+/// Releases on [MusicBrainz](https://musicbrainz.org), identified by release MBID.
+///
+/// Fetched albums use the release MBID as album id.
+pub struct MusicBrainzSource {
+    fetch: Box<dyn HttpFetch>,
+    base_url: String,
+}
+
+impl MusicBrainzSource {
+    pub const BASE_URL: &'static str = "https://musicbrainz.org/ws/2";
+
+    pub fn new(base_url: &str) -> Self {
+        Self::with_fetch(ReqwestFetch::default(), base_url)
+    }
+
+    pub fn with_fetch<F>(fetch: F, base_url: &str) -> Self
+    where
+        F: HttpFetch + 'static,
+    {
+        Self {
+            fetch: Box::new(fetch),
+            base_url: base_url.to_string(),
+        }
+    }
+}
+
+impl Default for MusicBrainzSource {
+    fn default() -> Self {
+        Self::new(Self::BASE_URL)
+    }
+}
+
+#[async_trait]
+impl MetadataSource for MusicBrainzSource {
+    fn name(&self) -> &'static str {
+        "musicbrainz"
+    }
+
+    async fn fetch(&self, id: &str) -> anyhow::Result<Album> {
+        let url = build_url(
+            &self.base_url,
+            &format!("release/{id}"),
+            &[
+                (
+                    "inc",
+                    "recordings+artist-credits+release-groups+isrcs+labels",
+                ),
+                ("fmt", "json"),
+            ],
+        )?;
+        let release: Release = serde_json::from_str(&self.fetch.get(&url).await?)?;
+        Ok(release.into())
+    }
+
+    async fn search(&self, query: &str) -> anyhow::Result<Vec<SearchCandidate>> {
+        let url = build_url(
+            &self.base_url,
+            "release",
+            &[("query", query), ("fmt", "json")],
+        )?;
+        let result: SearchResult = serde_json::from_str(&self.fetch.get(&url).await?)?;
+        Ok(result
+            .releases
+            .into_iter()
+            .map(|release| SearchCandidate {
+                id: release.id.to_string(),
+                artist: artist_name(&release.artist_credit),
+                catalog: release.catalog().map(str::to_string),
+                title: release.title,
+                release_date: release.date,
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Release {
+    id: Uuid,
+    title: String,
+    date: Option<String>,
+    barcode: Option<String>,
+    #[serde(default)]
+    artist_credit: Vec<ArtistCredit>,
+    release_group: Option<ReleaseGroup>,
+    #[serde(default)]
+    label_info: Vec<LabelInfo>,
+    #[serde(default)]
+    media: Vec<Medium>,
+}
+
+impl Release {
+    fn catalog(&self) -> Option<&str> {
+        self.label_info
+            .iter()
+            .filter_map(|info| info.catalog_number.as_deref())
+            .find(|catalog| !catalog.is_empty() && *catalog != "[none]")
+    }
+}
+
+#[derive(Deserialize)]
+struct ArtistCredit {
+    /// Credited name of artist
+    name: String,
+}
+
+fn artist_name(credits: &[ArtistCredit]) -> Option<String> {
+    join_artists(credits.iter().map(|credit| credit.name.as_str()))
+}
+
+#[derive(Deserialize)]
+struct ReleaseGroup {
+    id: Uuid,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct LabelInfo {
+    catalog_number: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Medium {
+    title: Option<String>,
+    #[serde(default)]
+    tracks: Vec<MediumTrack>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MediumTrack {
+    id: Uuid,
+    title: String,
+    #[serde(default)]
+    artist_credit: Vec<ArtistCredit>,
+    recording: Recording,
+}
+
+#[derive(Deserialize)]
+struct Recording {
+    id: Uuid,
+    #[serde(default)]
+    isrcs: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    releases: Vec<Release>,
+}
+
+impl From<Release> for Album {
+    fn from(release: Release) -> Self {
+        let catalog = release.catalog().unwrap_or_default().to_string();
+        let artist = artist_name(&release.artist_credit);
+
+        let discs = release
+            .media
+            .into_iter()
+            .map(|medium| {
+                let disc = DiscInfo::new(
+                    catalog.clone(),
+                    medium.title.filter(|title| !title.is_empty()),
+                    None,
+                    None,
+                    None,
+                    Vec::new(),
+                );
+                let tracks = medium
+                    .tracks
+                    .into_iter()
+                    .map(|track| {
+                        let mut draft = draft_track(track.title, artist_name(&track.artist_credit));
+                        draft.ids = ExternalIds {
+                            isrc: track.recording.isrcs.into_iter().next(),
+                            musicbrainz_recording: Some(track.recording.id),
+                            musicbrainz_track: Some(track.id),
+                            ..Default::default()
+                        };
+                        draft
+                    })
+                    .collect();
+                Disc::new(disc, tracks)
+            })
+            .collect();
+
+        Album::new(
+            AlbumInfo {
+                album_id: release.id,
+                title: release.title,
+                artist: artist.unwrap_or_else(|| UNKNOWN_ARTIST.to_string()),
+                release_date: release
+                    .date
+                    .as_deref()
+                    .map(parse_date)
+                    .unwrap_or(AnniDate::UNKNOWN),
+                catalog,
+                ids: ExternalIds {
+                    barcode: release.barcode.filter(|barcode| !barcode.is_empty()),
+                    musicbrainz_release: Some(release.id),
+                    musicbrainz_release_group: release.release_group.map(|group| group.id),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            discs,
+        )
+    }
+}
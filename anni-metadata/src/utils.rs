@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use crate::model::ArtistCredits;
 
 // https://github.com/serde-rs/serde/issues/1425#issuecomment-439729881
 pub fn non_empty_str<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
@@ -7,7 +7,7 @@ pub fn non_empty_str<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<St
     Ok(o.filter(|s| !s.is_empty()))
 }
 
-pub fn is_artists_empty(artists: &Option<HashMap<String, String>>) -> bool {
+pub fn is_artists_empty(artists: &Option<ArtistCredits>) -> bool {
     match artists {
         Some(artists) => artists.is_empty(),
         None => true,
@@ -0,0 +1,163 @@
+#![cfg(feature = "source")]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anni_metadata::model::{AnniDate, TrackType};
+use anni_metadata::source::{
+    DiscogsSource, HttpFetch, ITunesSource, MetadataSource, MusicBrainzSource,
+};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Responds with recorded fixtures in `tests/fixtures/source`.
+#[derive(Default)]
+struct FixtureFetch(HashMap<String, &'static str>);
+
+impl FixtureFetch {
+    fn with(mut self, url: &str, fixture: &'static str) -> Self {
+        self.0.insert(url.to_string(), fixture);
+        self
+    }
+}
+
+#[async_trait]
+impl HttpFetch for FixtureFetch {
+    async fn get(&self, url: &str) -> anyhow::Result<String> {
+        let fixture = self
+            .0
+            .get(url)
+            .ok_or_else(|| anyhow::anyhow!("unexpected request: {url}"))?;
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/source")
+            .join(fixture);
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
+
+const BASE_URL: &str = "http://localhost";
+
+#[tokio::test]
+async fn test_discogs() {
+    let fetch = FixtureFetch::default()
+        .with(
+            "http://localhost/releases/1234567?token=t",
+            "discogs-release.json",
+        )
+        .with(
+            "http://localhost/database/search?q=TEST-0001&type=release&token=t",
+            "discogs-search.json",
+        );
+    let source = DiscogsSource::with_fetch(fetch, BASE_URL, Some("t".to_string()));
+
+    let album = source.fetch("1234567").await.unwrap();
+    assert_eq!(album.title_raw(), "Test Album");
+    assert_eq!(album.artist(), "Artist A、B");
+    assert_eq!(album.catalog(), "TEST-0001");
+    assert_eq!(album.release_date(), &AnniDate::new(2020, 12, 16));
+    assert_eq!(album.ids.barcode.as_deref(), Some("4988002000005"));
+
+    let discs = album.iter().collect::<Vec<_>>();
+    assert_eq!(discs.len(), 2);
+    assert_eq!(discs[0].catalog(), "TEST-0001");
+    let tracks = discs[0].iter().collect::<Vec<_>>();
+    assert_eq!(tracks.len(), 2);
+    assert_eq!(tracks[0].artist(), "Artist A、B");
+    assert_eq!(tracks[1].artist(), "Artist C");
+    assert_eq!(tracks[1].track_type(), &TrackType::Instrumental);
+    assert_eq!(discs[1].track_type(), &TrackType::Drama);
+
+    let candidates = source.search("TEST-0001").await.unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(
+        candidates[0].to_string(),
+        "[TEST-0001] Test Album / Artist A (2020)"
+    );
+    assert_eq!(candidates[1].id, "7654321");
+    assert_eq!(candidates[1].catalog, None);
+
+    let source = DiscogsSource::with_fetch(FixtureFetch::default(), BASE_URL, None);
+    assert!(source.search("TEST-0001").await.is_err());
+}
+
+#[tokio::test]
+async fn test_itunes() {
+    let fetch = FixtureFetch::default()
+        .with(
+            "http://localhost/lookup?id=1545000000&entity=song&country=jp",
+            "itunes-lookup.json",
+        )
+        .with(
+            "http://localhost/search?term=Test+Album&entity=album&country=jp",
+            "itunes-search.json",
+        );
+    let source = ITunesSource::with_fetch(fetch, BASE_URL, Some("jp".to_string()));
+
+    let album = source.fetch("1545000000").await.unwrap();
+    assert_eq!(album.title_raw(), "Test Album");
+    assert_eq!(album.artist(), "Artist A");
+    assert_eq!(album.catalog(), "");
+    assert_eq!(album.release_date(), &AnniDate::new(2020, 12, 16));
+
+    let discs = album.iter().collect::<Vec<_>>();
+    assert_eq!(discs.len(), 2);
+    let titles = discs[0]
+        .iter()
+        .map(|track| track.title())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec!["Track 1", "Track 2 (Instrumental)"]);
+    assert_eq!(discs[0].iter().nth(1).unwrap().artist(), "Artist C");
+    assert_eq!(discs[1].iter().next().unwrap().title(), "Drama Track");
+
+    let candidates = source.search("Test Album").await.unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].id, "1545000000");
+    assert_eq!(
+        candidates[0].to_string(),
+        "Test Album / Artist A (2020-12-16)"
+    );
+}
+
+#[tokio::test]
+async fn test_musicbrainz() {
+    let fetch = FixtureFetch::default()
+        .with(
+            "http://localhost/release/f1a2b3c4-d5e6-4f70-8190-a1b2c3d4e5f6?inc=recordings%2Bartist-credits%2Brelease-groups%2Bisrcs%2Blabels&fmt=json",
+            "musicbrainz-release.json",
+        )
+        .with(
+            "http://localhost/release?query=catno%3ATEST-0001&fmt=json",
+            "musicbrainz-search.json",
+        );
+    let source = MusicBrainzSource::with_fetch(fetch, BASE_URL);
+    let release_id = Uuid::from_str("f1a2b3c4-d5e6-4f70-8190-a1b2c3d4e5f6").unwrap();
+
+    let album = source.fetch(&release_id.to_string()).await.unwrap();
+    assert_eq!(album.album_id(), release_id);
+    assert_eq!(album.title_raw(), "Test Album");
+    assert_eq!(album.artist(), "Artist A、Artist B");
+    assert_eq!(album.catalog(), "TEST-0001");
+    assert_eq!(album.ids.barcode.as_deref(), Some("4988002000005"));
+    assert_eq!(album.ids.musicbrainz_release, Some(release_id));
+    assert!(album.ids.musicbrainz_release_group.is_some());
+
+    let discs = album.iter().collect::<Vec<_>>();
+    assert_eq!(discs.len(), 2);
+    assert_eq!(discs[0].title_raw(), None);
+    assert_eq!(discs[1].title_raw(), Some("Bonus CD"));
+    let tracks = discs[0].iter().collect::<Vec<_>>();
+    assert_eq!(tracks[0].artist(), "Artist A、Artist B");
+    assert_eq!(tracks[1].artist(), "Artist C");
+    let ids = tracks[0].ids();
+    assert_eq!(ids.isrc.as_deref(), Some("JPA000000001"));
+    assert!(ids.musicbrainz_recording.is_some());
+    assert!(ids.musicbrainz_track.is_some());
+    assert_eq!(tracks[1].ids().isrc, None);
+
+    let candidates = source.search("catno:TEST-0001").await.unwrap();
+    assert_eq!(
+        candidates[0].to_string(),
+        "[TEST-0001] Test Album / Artist A、Artist B (2020-12-16)"
+    );
+}
@@ -16,12 +16,16 @@
 
 use std::{
     path::Path,
-    sync::{atomic::AtomicBool, Arc, RwLock, RwLockReadGuard},
+    sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard},
+    time::Duration,
 };
 
 use crossbeam::channel::unbounded;
 
-use crate::types::*;
+use crate::{
+    dsp::{DspChain, DspPreset},
+    types::*,
+};
 
 /// Creates a getter and setter for an AtomicBool.
 macro_rules! getset_atomic_bool {
@@ -49,6 +53,9 @@ macro_rules! getset_rwlock {
     };
 }
 
+/// Length of the ramp applied when playing, pausing or seeking.
+const DEFAULT_FADE_DURATION: Duration = Duration::from_millis(50);
+
 type EventHandler = (Sender<InternalPlayerEvent>, Receiver<InternalPlayerEvent>);
 
 #[derive(Clone)]
@@ -61,8 +68,17 @@ pub struct Controls {
     is_normalizing: Arc<AtomicBool>,
     is_file_preloaded: Arc<AtomicBool>,
     volume: Arc<RwLock<f32>>,
+    /// Length of the fade-in/out ramps on play, pause and seek.
+    fade_duration: Arc<RwLock<Duration>>,
+    /// Length of the crossfade between the current and the preloaded track.
+    /// Crossfade is disabled if it's zero.
+    crossfade_duration: Arc<RwLock<Duration>>,
     seek_ts: Arc<RwLock<Option<u64>>>,
     progress: Arc<RwLock<ProgressState>>,
+    /// DSP stages applied before writing to the output.
+    dsp_chain: Arc<Mutex<DspChain>>,
+    /// The preset `dsp_chain` was built from, if any.
+    dsp_preset: Arc<RwLock<Option<DspPreset>>>,
 
     player_event_sender: Arc<std::sync::mpsc::Sender<PlayerEvent>>,
 }
@@ -77,11 +93,15 @@ impl Controls {
             is_normalizing: Arc::new(AtomicBool::new(false)),
             is_file_preloaded: Arc::new(AtomicBool::new(false)),
             volume: Arc::new(RwLock::new(1.0)),
+            fade_duration: Arc::new(RwLock::new(DEFAULT_FADE_DURATION)),
+            crossfade_duration: Arc::new(RwLock::new(Duration::ZERO)),
             seek_ts: Arc::new(RwLock::new(None)),
             progress: Arc::new(RwLock::new(ProgressState {
                 position: 0,
                 duration: 0,
             })),
+            dsp_chain: Arc::new(Mutex::new(DspChain::new())),
+            dsp_preset: Arc::new(RwLock::new(None)),
 
             player_event_sender: Arc::new(player_event_sender),
         }
@@ -187,6 +207,28 @@ impl Controls {
         });
     }
 
+    /// Returns the DSP chain, which can be modified at runtime.
+    pub fn dsp_chain(&self) -> MutexGuard<'_, DspChain> {
+        self.dsp_chain.lock().unwrap()
+    }
+
+    /// Replaces the DSP chain with a custom one.
+    pub fn set_dsp_chain(&self, chain: DspChain) {
+        *self.dsp_chain() = chain;
+        *self.dsp_preset.write().unwrap() = None;
+    }
+
+    /// Replaces the DSP chain with the one built from `preset`.
+    pub fn set_dsp_preset(&self, preset: DspPreset) {
+        *self.dsp_chain() = preset.to_chain();
+        *self.dsp_preset.write().unwrap() = Some(preset);
+    }
+
+    /// Returns the preset applied by `set_dsp_preset`, if the chain was not replaced since.
+    pub fn dsp_preset(&self) -> Option<DspPreset> {
+        self.dsp_preset.read().unwrap().clone()
+    }
+
     pub(crate) fn preload_played(&self) {
         self.set_is_file_preloaded(false);
         self.send_player_event(PlayerEvent::PreloadPlayed);
@@ -198,5 +240,7 @@ impl Controls {
     getset_atomic_bool!(is_normalizing, set_is_normalizing);
     getset_atomic_bool!(is_file_preloaded, set_is_file_preloaded);
     getset_rwlock!(volume, set_volume, f32);
+    getset_rwlock!(fade_duration, set_fade_duration, Duration);
+    getset_rwlock!(crossfade_duration, set_crossfade_duration, Duration);
     getset_rwlock!(seek_ts, set_seek_ts, Option<u64>);
 }
@@ -14,13 +14,20 @@
 // You should have received a copy of the GNU Lesser General Public License along with this program.
 // If not, see <https://www.gnu.org/licenses/>.
 
-use std::sync::{atomic::AtomicBool, Arc};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     Device, Stream, StreamConfig,
 };
+use crossbeam::channel::{bounded, Receiver};
 use symphonia::core::audio::{AudioBufferRef, SampleBuffer, SignalSpec};
 
 use crate::types::InternalPlayerEvent;
@@ -28,7 +35,11 @@ use crate::utils::blocking_rb::*;
 
 use super::{
     controls::*,
-    dsp::{normalizer::Normalizer, resampler::Resampler},
+    dsp::{
+        fader::{duration_to_frames, Fader},
+        normalizer::Normalizer,
+        resampler::Resampler,
+    },
 };
 
 /// The default output volume is way too high.
@@ -36,6 +47,19 @@ use super::{
 /// will help to reduce it.
 const BASE_VOLUME: f32 = 0.8;
 
+/// The latest gain ramp requested for the output stream.
+///
+/// The output callback must not block, so it owns the [`Fader`] and picks up
+/// new requests from these atomics instead of sharing the fader behind a lock.
+#[derive(Default)]
+struct FadeRequest {
+    /// Target gain, stored as the bits of an `f32`.
+    target: AtomicU32,
+    frames: AtomicUsize,
+    /// Incremented with every request.
+    generation: AtomicUsize,
+}
+
 pub struct CpalOutputStream {
     pub stream: Stream,
     pub ring_buffer_reader: BlockingRb<f32, Consumer>,
@@ -44,6 +68,9 @@ pub struct CpalOutputStream {
     pub config: StreamConfig,
     //
     controls: Controls,
+    fade: Arc<FadeRequest>,
+    /// Generations of the ramps the output callback has finished.
+    fade_done: Receiver<usize>,
 }
 
 impl CpalOutputStream {
@@ -62,10 +89,17 @@ impl CpalOutputStream {
         let ring_buffer_writer = rb.0;
         let ring_buffer_reader = rb.1;
 
+        let fade = Arc::new(FadeRequest::default());
+        let (fade_done_tx, fade_done) = bounded(1);
+
         let stream = device.build_output_stream(
             &config,
             {
                 let controls = controls.clone();
+                let fade = fade.clone();
+                let mut fader = Fader::new(channels);
+                let mut applied = 0;
+                let mut reported = 0;
                 move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
                     // let buffering = buffer_signal.load(std::sync::atomic::Ordering::SeqCst);
 
@@ -88,17 +122,36 @@ impl CpalOutputStream {
                     //     return;
                     // }
 
+                    let generation = fade.generation.load(Ordering::Acquire);
+                    if generation != applied {
+                        applied = generation;
+                        fader.ramp_to(
+                            f32::from_bits(fade.target.load(Ordering::Relaxed)),
+                            fade.frames.load(Ordering::Relaxed),
+                        );
+                    }
+
                     // Set the volume.
                     // TODO: allow user to not normalize the volume.
                     if let Some(written) = ring_buffer_reader.read(data) {
                         data[0..written]
                             .iter_mut()
                             .for_each(|s| *s *= BASE_VOLUME * *controls.volume());
+                        fader.process(&mut data[0..written]);
 
                         data[written..].fill(0.);
                     } else {
                         data.fill(0.);
                     }
+
+                    // Retried on the next call if the previous completion
+                    // has not been received yet.
+                    if reported != applied
+                        && !fader.is_ramping()
+                        && fade_done_tx.try_send(applied).is_ok()
+                    {
+                        reported = applied;
+                    }
                 }
             },
             {
@@ -131,6 +184,8 @@ impl CpalOutputStream {
             ring_buffer_writer: rb_clone.0,
             ring_buffer_reader: rb_clone.1,
             controls,
+            fade,
+            fade_done,
         })
     }
 
@@ -163,6 +218,38 @@ impl CpalOutputStream {
         }
     }
 
+    /// Ramps the output gain back to unity in `duration`.
+    pub fn fade_in(&self, duration: Duration) {
+        self.ramp_to(1.0, duration);
+    }
+
+    /// Ramps the output gain to zero in `duration`.
+    ///
+    /// Blocks until the ramp has been played, or until twice the `duration`
+    /// has passed in case no samples are being consumed.
+    pub fn fade_out(&self, duration: Duration) {
+        // Drop completions of earlier ramps.
+        while self.fade_done.try_recv().is_ok() {}
+
+        let generation = self.ramp_to(0.0, duration);
+        let deadline = Instant::now() + duration * 2;
+        while let Ok(done) = self.fade_done.recv_deadline(deadline) {
+            if done == generation {
+                break;
+            }
+        }
+    }
+
+    /// Requests a ramp of the output gain to `target` in `duration`.
+    ///
+    /// Returns the generation of the request.
+    fn ramp_to(&self, target: f32, duration: Duration) -> usize {
+        let frames = duration_to_frames(duration, self.config.sample_rate.0);
+        self.fade.target.store(target.to_bits(), Ordering::Relaxed);
+        self.fade.frames.store(frames, Ordering::Relaxed);
+        self.fade.generation.fetch_add(1, Ordering::Release) + 1
+    }
+
     fn get_config(
         #[cfg_attr(target_os = "windows", allow(unused))] spec: SignalSpec,
     ) -> anyhow::Result<(Device, StreamConfig)> {
@@ -212,6 +299,8 @@ pub struct CpalOutput {
     resampler: Option<Resampler<f32>>,
     normalizer: Normalizer,
     controls: Controls,
+    /// Samples waiting to be modified by `write_with`.
+    buffer: Vec<f32>,
 }
 
 impl CpalOutput {
@@ -249,13 +338,45 @@ impl CpalOutput {
             resampler,
             normalizer: Normalizer::new(spec.channels.count(), sample_rate),
             controls: controls,
+            buffer: Vec::new(),
         }
     }
 
     /// Write the `AudioBufferRef` to the buffers.
     pub fn write(&mut self, decoded: AudioBufferRef) {
+        self.write_with(decoded, |_| {});
+    }
+
+    /// Write the `AudioBufferRef` to the buffers, allowing `f` to modify the
+    /// interleaved output samples before they go through the DSP chain.
+    pub fn write_with<F>(&mut self, decoded: AudioBufferRef, f: F)
+    where
+        F: FnOnce(&mut [f32]),
+    {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.extend_from_slice(self.render(decoded));
+        f(&mut buffer);
+        self.controls.dsp_chain().process(
+            &mut buffer,
+            self.spec.channels.count(),
+            self.sample_rate,
+        );
+
+        let mut samples = &buffer[..];
+        while let Some(written) = self.ring_buffer_writer.write(samples) {
+            samples = &samples[written..];
+        }
+
+        self.buffer = buffer;
+    }
+
+    /// Resamples and normalizes the `AudioBufferRef` without writing it.
+    ///
+    /// Returns the interleaved output samples.
+    pub fn render(&mut self, decoded: AudioBufferRef) -> &[f32] {
         if decoded.frames() == 0 {
-            return;
+            return &[];
         }
 
         let need_resample = decoded.spec().rate != self.sample_rate;
@@ -278,9 +399,7 @@ impl CpalOutput {
             }
         }
 
-        while let Some(written) = self.ring_buffer_writer.write(samples) {
-            samples = &samples[written..];
-        }
+        samples
     }
 
     /// Clean up after playback is done.
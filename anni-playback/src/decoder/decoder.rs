@@ -20,11 +20,12 @@ use std::{
     time::Duration,
 };
 
+use anni_common::models::TrackIdentifier;
 use anyhow::{anyhow, Context};
 use once_cell::sync::Lazy;
 use symphonia::{
     core::{
-        audio::{AsAudioBufferRef, AudioBuffer},
+        audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef},
         formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
         io::MediaSourceStream,
         meta::MetadataOptions,
@@ -42,6 +43,7 @@ use super::opus::OpusDecoder;
 use crate::{
     controls::*,
     cpal_output::{CpalOutput, CpalOutputStream},
+    dsp::fader::{duration_to_frames, Crossfader},
     sources::AnniSource,
     types::*,
 };
@@ -70,6 +72,8 @@ pub struct Decoder {
     preload_playback: Option<Playback>,
     /// The `JoinHandle` for the thread that preloads a file.
     preload_thread: Option<JoinHandle<anyhow::Result<Playback>>>,
+    /// The outgoing track while crossfading into `playback`.
+    crossfade: Option<Crossfade>,
     spec: SignalSpec,
 }
 
@@ -87,6 +91,7 @@ impl Decoder {
             playback: None,
             preload_playback: None,
             preload_thread: None,
+            crossfade: None,
             spec,
         }
     }
@@ -166,6 +171,7 @@ impl Decoder {
                         duration: playback.duration,
                     });
                     self.cpal_output = None;
+                    self.crossfade = None;
                     self.playback = Some(playback);
                 }
                 InternalPlayerEvent::Play => {
@@ -173,18 +179,23 @@ impl Decoder {
 
                     if self.cpal_output.is_some() {
                         self.cpal_output_stream.play();
+                        self.cpal_output_stream
+                            .fade_in(*self.controls.fade_duration());
                     }
                 }
                 InternalPlayerEvent::Pause => {
                     self.state = DecoderState::Paused;
 
                     if self.cpal_output.is_some() {
+                        self.cpal_output_stream
+                            .fade_out(*self.controls.fade_duration());
                         self.cpal_output_stream.pause();
                     }
                 }
                 InternalPlayerEvent::Stop => {
                     self.state = DecoderState::Idle;
                     self.cpal_output = None;
+                    self.crossfade = None;
                     self.playback = None;
                 }
                 // When the device is changed/disconnected,
@@ -242,12 +253,23 @@ impl Decoder {
             }
 
             let buffer_ref = preload.as_audio_buffer_ref();
-            self.cpal_output.as_mut().unwrap().write(buffer_ref);
+            Self::write(
+                self.cpal_output.as_mut().unwrap(),
+                &mut self.crossfade,
+                buffer_ref,
+            );
 
             return Ok(PlaybackState::Playing);
         }
 
         if let Some(seek_ts) = *self.controls.seek_ts() {
+            // Fade out before jumping to avoid clicks.
+            if self.cpal_output.is_some() {
+                self.cpal_output_stream
+                    .fade_out(*self.controls.fade_duration());
+            }
+            self.crossfade = None;
+
             let seek_to = SeekTo::Time {
                 time: Time {
                     seconds: seek_ts / 1000,
@@ -262,10 +284,13 @@ impl Decoder {
         if self.controls.seek_ts().is_some() {
             self.controls.set_seek_ts(None);
             playback.decoder.reset();
+            self.controls.dsp_chain().reset();
             // Clear the ring buffer which prevents the writer
             // from blocking.
             if self.cpal_output.is_some() {
                 self.cpal_output_stream.ring_buffer_reader.skip_all();
+                self.cpal_output_stream
+                    .fade_in(*self.controls.fade_duration());
             }
             return Ok(PlaybackState::Playing);
         }
@@ -323,11 +348,92 @@ impl Decoder {
                 ));
         }
 
-        self.cpal_output.as_mut().unwrap().write(decoded);
+        Self::write(
+            self.cpal_output.as_mut().unwrap(),
+            &mut self.crossfade,
+            decoded,
+        );
+
+        if self.should_crossfade(position) {
+            self.start_crossfade();
+        }
 
         Ok(PlaybackState::Playing)
     }
 
+    /// Writes decoded samples to `output`, mixing in the outgoing track
+    /// if a crossfade is in progress.
+    fn write(output: &mut CpalOutput, crossfade: &mut Option<Crossfade>, decoded: AudioBufferRef) {
+        match crossfade {
+            Some(fading) => {
+                output.write_with(decoded, |samples| fading.mix(samples));
+
+                if fading.is_finished() {
+                    log::debug!("crossfade finished");
+                    *crossfade = None;
+                }
+            }
+            None => output.write(decoded),
+        }
+    }
+
+    /// Returns true if the current track has reached the point where it
+    /// should crossfade into the preloaded one.
+    ///
+    /// Crossfade is disabled while looping, and between consecutive tracks
+    /// of an album, which are played gapless.
+    fn should_crossfade(&self, position: u64) -> bool {
+        let crossfade_duration = self.controls.crossfade_duration().as_millis() as u64;
+        let playback = match &self.playback {
+            Some(playback) => playback,
+            None => return false,
+        };
+
+        crossfade_duration > 0
+            && playback.duration > 0
+            && self.crossfade.is_none()
+            && !self.controls.is_looping()
+            && self
+                .preload_playback
+                .as_ref()
+                .is_some_and(|p| p.preload.is_some() && !playback.is_gapless_with(p))
+            && position + crossfade_duration >= playback.duration
+    }
+
+    /// Switches to the preloaded track while keeping the current one
+    /// as the outgoing side of a crossfade.
+    fn start_crossfade(&mut self) {
+        let (Some(incoming), Some(outgoing_output)) =
+            (self.preload_playback.take(), self.cpal_output.take())
+        else {
+            return;
+        };
+        let preload = incoming.preload.as_ref().unwrap();
+        let output = self.cpal_output_stream.create_output(
+            incoming.buffer_signal.clone(),
+            *preload.spec(),
+            preload.capacity() as u64,
+        );
+
+        log::debug!("starting crossfade");
+
+        let frames = duration_to_frames(
+            *self.controls.crossfade_duration(),
+            self.cpal_output_stream.config.sample_rate.0,
+        );
+
+        let outgoing = self.playback.replace(incoming).unwrap();
+        self.cpal_output = Some(output);
+        self.crossfade = Some(Crossfade {
+            playback: outgoing,
+            output: outgoing_output,
+            mixer: Crossfader::new(self.spec.channels.count(), frames),
+            exhausted: false,
+        });
+
+        self.controls.preload_played();
+    }
+
     /// Called when the file is finished playing.
     ///
     /// Flushes `cpal_output` and sends a `Done` message to Dart.
@@ -341,6 +447,7 @@ impl Decoder {
         } else {
             self.cpal_output_stream.ring_buffer_reader.skip_all();
         }
+        self.crossfade = None;
 
         // If there is a preloaded file, then swap it with the current playback.
         if let Some(playback) = self.preload_playback.take() {
@@ -362,6 +469,7 @@ impl Decoder {
         buffer_signal: Arc<AtomicBool>,
     ) -> anyhow::Result<Playback> {
         let duration_hint = source.duration_hint();
+        let identifier = source.track().cloned();
         let mss = MediaSourceStream::new(source.into(), Default::default());
         let format_options = FormatOptions {
             enable_gapless: true,
@@ -410,6 +518,7 @@ impl Decoder {
             duration,
             buffer_signal,
             preload: None,
+            identifier,
         })
     }
 
@@ -483,6 +592,39 @@ impl DecoderState {
     }
 }
 
+/// The outgoing track of a crossfade, decoded on demand to be mixed into
+/// the incoming one.
+struct Crossfade {
+    playback: Playback,
+    output: CpalOutput,
+    mixer: Crossfader,
+    /// Set when the outgoing track has no more packets.
+    exhausted: bool,
+}
+
+impl Crossfade {
+    /// Mixes the outgoing track into `samples`, decoding more packets if needed.
+    fn mix(&mut self, samples: &mut [f32]) {
+        while !self.exhausted && self.mixer.buffered() < samples.len() {
+            match self.playback.reader.next_packet() {
+                Ok(packet) if packet.track_id() == self.playback.track_id => {
+                    match self.playback.decoder.decode(&packet) {
+                        Ok(decoded) => self.mixer.push_outgoing(self.output.render(decoded)),
+                        Err(e) => log::warn!("Decode error on crossfade: {e}"),
+                    }
+                }
+                _ => self.exhausted = true,
+            }
+        }
+
+        self.mixer.mix(samples);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.mixer.is_finished() || (self.exhausted && self.mixer.buffered() == 0)
+    }
+}
+
 /// Holds the items related to playback.
 ///
 /// Ex: The Symphonia decoder, timebase, duration.
@@ -495,4 +637,35 @@ struct Playback {
     buffer_signal: Arc<AtomicBool>,
     /// A buffer of already decoded samples.
     preload: Option<AudioBuffer<f32>>,
+    /// The track being played, if the source knows it.
+    identifier: Option<TrackIdentifier>,
+}
+
+impl Playback {
+    /// Returns true if `next` is the track following this one on the same album,
+    /// encoded in the same format, so that it should be played without a gap.
+    fn is_gapless_with(&self, next: &Playback) -> bool {
+        let (Some(current), Some(next_track)) = (&self.identifier, &next.identifier) else {
+            return false;
+        };
+        let (current, next_track) = (&current.inner, &next_track.inner);
+
+        let disc_id = current.disc_id.get();
+        let track_id = current.track_id.get();
+        let consecutive = current.album_id == next_track.album_id
+            && if next_track.disc_id.get() == disc_id {
+                track_id.checked_add(1) == Some(next_track.track_id.get())
+            } else {
+                // first track of the next disc
+                disc_id.checked_add(1) == Some(next_track.disc_id.get())
+                    && next_track.track_id.get() == 1
+            };
+
+        let params = self.decoder.codec_params();
+        let next_params = next.decoder.codec_params();
+        consecutive
+            && params.codec == next_params.codec
+            && params.sample_rate == next_params.sample_rate
+            && params.channels == next_params.channels
+    }
 }
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind, Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use anni_common::models::TrackIdentifier;
+use anni_provider::providers::TypedPriorityProvider;
+use crossbeam::channel::{Receiver, Sender};
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::sources::{
+    cached_http::{
+        cache::{CacheKey, CacheStore, CacheWriter},
+        provider::{AudioQuality, ProviderProxy},
+    },
+    provider::PlaybackProvider,
+};
+
+const BUF_SIZE: usize = 1024 * 64; // 64k
+/// Minimum number of bytes between two [`DownloadEvent::Progress`] events of a track.
+const PROGRESS_INTERVAL: u64 = 1024 * 1024; // 1M
+/// Interval of checking whether a track being written by playback is finished.
+const WAIT_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("No available annil")]
+    NoAvailableAnnil,
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Request Error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Io Error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug)]
+pub enum DownloadEvent {
+    /// Track is added to the queue
+    Queued(TrackIdentifier),
+    /// Started downloading a track
+    Started(TrackIdentifier),
+    /// Download progress updated. `downloaded` includes bytes of a resumed partial download.
+    Progress {
+        track: TrackIdentifier,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    /// Track is fully cached, or had been cached before
+    Completed(TrackIdentifier),
+    /// Failed to download a track from all providers
+    Failed {
+        track: TrackIdentifier,
+        error: DownloadError,
+    },
+}
+
+struct DownloadTask {
+    track: TrackIdentifier,
+    quality: AudioQuality,
+    opus: bool,
+    /// Value of `DownloadManager::generation` when the task was queued.
+    generation: usize,
+}
+
+impl DownloadTask {
+    fn key(&self) -> CacheKey<'static> {
+        CacheKey::new(self.track.inner.clone(), self.quality, self.opus)
+    }
+}
+
+/// Queued or running tasks, with the generation they were queued in.
+type ActiveTasks = Arc<Mutex<HashMap<CacheKey<'static>, usize>>>;
+
+/// Downloads tracks into a [`CacheStore`] in the background for offline listening.
+///
+/// Tracks are fetched by a fixed number of worker threads, trying each remote provider in
+/// priority order. Local providers are skipped, as they are available offline already.
+/// An interrupted download is resumed from the partial cache file with an HTTP range
+/// request, either by another provider or by a later download of the same track.
+pub struct DownloadManager {
+    queue: Sender<DownloadTask>,
+    cache_store: CacheStore,
+    event_sender: mpsc::Sender<DownloadEvent>,
+    /// Bumped to cancel tasks queued earlier.
+    generation: Arc<AtomicUsize>,
+    active: ActiveTasks,
+}
+
+impl DownloadManager {
+    /// Creates a manager running `concurrency` downloads at most at the same time.
+    pub fn new(
+        provider: Arc<RwLock<TypedPriorityProvider<PlaybackProvider>>>,
+        cache_store: CacheStore,
+        concurrency: usize,
+    ) -> (Self, mpsc::Receiver<DownloadEvent>) {
+        let (queue, tasks) = crate::create_unbound_channel();
+        let (event_sender, events) = mpsc::channel();
+        let generation = Arc::new(AtomicUsize::new(0));
+        let active = ActiveTasks::default();
+
+        for i in 0..concurrency.max(1) {
+            let worker = Worker {
+                provider: provider.clone(),
+                cache_store: cache_store.clone(),
+                event_sender: event_sender.clone(),
+                generation: generation.clone(),
+                active: active.clone(),
+            };
+            let tasks = tasks.clone();
+
+            thread::Builder::new()
+                .name(format!("anni-playback-download-{i}"))
+                .spawn(move || worker.run(tasks))
+                .unwrap();
+        }
+
+        (
+            Self {
+                queue,
+                cache_store,
+                event_sender,
+                generation,
+                active,
+            },
+            events,
+        )
+    }
+
+    /// Adds a track to the download queue.
+    ///
+    /// Does nothing if the track is already queued or being downloaded at the same quality.
+    pub fn enqueue(&self, track: TrackIdentifier, quality: AudioQuality, opus: bool) {
+        let task = DownloadTask {
+            track,
+            quality,
+            opus,
+            generation: self.generation.load(Ordering::Acquire),
+        };
+
+        {
+            let mut active = self.active.lock().unwrap();
+            // tasks of previous generations are cancelled, so they do not count
+            if active.get(&task.key()) == Some(&task.generation) {
+                log::debug!("{} is already queued", task.track);
+                return;
+            }
+            active.insert(task.key(), task.generation);
+        }
+
+        let _ = self
+            .event_sender
+            .send(DownloadEvent::Queued(task.track.clone()));
+        let _ = self.queue.send(task);
+    }
+
+    /// Pins an album for offline use, and queues `tracks` of it.
+    ///
+    /// Cache of pinned albums is exempt from eviction.
+    pub fn pin_album(
+        &self,
+        album_id: &str,
+        tracks: Vec<TrackIdentifier>,
+        quality: AudioQuality,
+        opus: bool,
+    ) -> io::Result<()> {
+        self.cache_store.pin(album_id)?;
+
+        for track in tracks {
+            self.enqueue(track, quality, opus);
+        }
+
+        Ok(())
+    }
+
+    /// Unpins an album. Its cache can be evicted afterwards.
+    pub fn unpin_album(&self, album_id: &str) -> io::Result<()> {
+        self.cache_store.unpin(album_id)
+    }
+
+    /// Number of tracks waiting in the queue, excluding running downloads.
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Cancels queued and running downloads.
+    ///
+    /// Partial downloads are kept and resumed when the track is queued again.
+    pub fn cancel_all(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl Drop for DownloadManager {
+    fn drop(&mut self) {
+        // workers exit once the queue is closed and drained
+        self.cancel_all();
+    }
+}
+
+struct Worker {
+    provider: Arc<RwLock<TypedPriorityProvider<PlaybackProvider>>>,
+    cache_store: CacheStore,
+    event_sender: mpsc::Sender<DownloadEvent>,
+    generation: Arc<AtomicUsize>,
+    active: ActiveTasks,
+}
+
+impl Worker {
+    fn run(self, tasks: Receiver<DownloadTask>) {
+        for task in tasks {
+            let result = self.download(&task);
+
+            {
+                let mut active = self.active.lock().unwrap();
+                // the track may have been queued again after cancellation
+                let key = task.key();
+                if active.get(&key) == Some(&task.generation) {
+                    active.remove(&key);
+                }
+            }
+
+            let event = match result {
+                Ok(()) => DownloadEvent::Completed(task.track),
+                Err(error) => {
+                    log::warn!("failed to download {}: {error}", task.track);
+                    DownloadEvent::Failed {
+                        track: task.track,
+                        error,
+                    }
+                }
+            };
+            let _ = self.event_sender.send(event);
+        }
+    }
+
+    fn is_cancelled(&self, task: &DownloadTask) -> bool {
+        task.generation != self.generation.load(Ordering::Acquire)
+    }
+
+    fn download(&self, task: &DownloadTask) -> Result<(), DownloadError> {
+        if self.is_cancelled(task) {
+            return Err(DownloadError::Cancelled);
+        }
+
+        let key = CacheKey::new(task.track.inner.copied(), task.quality, task.opus);
+        let mut cache = loop {
+            if self.cache_store.is_cached(key.copied())? {
+                return Ok(());
+            }
+
+            match self.cache_store.open_partial(key.copied())? {
+                Ok(cache) => break cache,
+                Err(progress) => {
+                    // the track is being written by playback, wait for it instead of
+                    // writing to the same file
+                    log::debug!("{} is being cached, waiting", task.track);
+                    while progress.is_writing() {
+                        if self.is_cancelled(task) {
+                            return Err(DownloadError::Cancelled);
+                        }
+                        thread::sleep(WAIT_INTERVAL);
+                    }
+                }
+            }
+        };
+
+        let _ = self
+            .event_sender
+            .send(DownloadEvent::Started(task.track.clone()));
+
+        // clone the providers so that they can be modified during the download
+        let providers: Vec<ProviderProxy> = self
+            .provider
+            .read()
+            .unwrap()
+            .providers()
+            .filter_map(PlaybackProvider::as_remote)
+            .cloned()
+            .collect();
+
+        let mut error = DownloadError::NoAvailableAnnil;
+        for provider in providers {
+            match self.fetch(&provider, task, key.copied(), &mut cache) {
+                Ok(()) => {
+                    if let Err(e) = self.cache_store.evict() {
+                        log::warn!("failed to evict cache: {e}");
+                    }
+                    return Ok(());
+                }
+                Err(DownloadError::Cancelled) => return Err(DownloadError::Cancelled),
+                Err(e) => {
+                    log::warn!("failed to download {} from provider: {e}", task.track);
+                    error = e;
+                }
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Downloads the rest of `cache` from `provider`.
+    fn fetch(
+        &self,
+        provider: &ProviderProxy,
+        task: &DownloadTask,
+        key: CacheKey,
+        cache: &mut CacheWriter,
+    ) -> Result<(), DownloadError> {
+        let offset = cache.file_len()?;
+        let response =
+            provider.get_range(task.track.inner.copied(), task.quality, task.opus, offset)?;
+
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // the partial file is not a prefix of the audio, start over
+            log::warn!("partial cache of {} is invalid", task.track);
+            cache.clear()?;
+            return self.fetch(provider, task, key, cache);
+        }
+
+        let mut response = response.error_for_status()?;
+        let mut downloaded = if response.status() == StatusCode::PARTIAL_CONTENT {
+            log::debug!("resuming {} from {offset}", task.track);
+            offset
+        } else {
+            // range is not supported, e.g. for transcoded audio
+            cache.clear()?;
+            0
+        };
+
+        let total = response.content_length().map(|len| downloaded + len);
+        if let Some(total) = total {
+            self.cache_store.store_info(key, "content-length", total)?;
+        }
+
+        let mut buf = [0; BUF_SIZE];
+        let mut reported = downloaded;
+        loop {
+            if self.is_cancelled(task) {
+                return Err(DownloadError::Cancelled);
+            }
+
+            match response.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    cache.write_all(&buf[..n])?;
+                    downloaded += n as u64;
+
+                    if downloaded - reported >= PROGRESS_INTERVAL {
+                        reported = downloaded;
+                        self.report(task, downloaded, total);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        cache.flush()?;
+
+        if total.is_some_and(|total| total != downloaded) {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        self.report(task, downloaded, total);
+
+        Ok(())
+    }
+
+    fn report(&self, task: &DownloadTask, downloaded: u64, total: Option<u64>) {
+        let _ = self.event_sender.send(DownloadEvent::Progress {
+            track: task.track.clone(),
+            downloaded,
+            total,
+        });
+    }
+}
@@ -0,0 +1,147 @@
+// This file is a part of simple_audio
+// Copyright (c) 2022-2023 Erikas Taroza <erikastaroza@gmail.com>
+//
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License as
+// published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along with this program.
+// If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    equalizer::{EqBand, Equalizer, Preamp},
+    limiter::{Limiter, LimiterOptions},
+};
+
+/// A stage of the [`DspChain`].
+pub trait DspStage: Send {
+    /// Processes interleaved samples in place.
+    fn process(&mut self, samples: &mut [f32], channels: usize, sample_rate: u32);
+
+    /// Clears any internal state, e.g. filter history after seeking.
+    fn reset(&mut self) {}
+}
+
+/// An ordered list of [`DspStage`]s applied to the decoded audio before it is
+/// written to the output.
+#[derive(Default)]
+pub struct DspChain {
+    stages: Vec<Box<dyn DspStage>>,
+}
+
+impl DspChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, stage: Box<dyn DspStage>) {
+        self.stages.push(stage);
+    }
+
+    pub fn insert(&mut self, index: usize, stage: Box<dyn DspStage>) {
+        self.stages.insert(index, stage);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Box<dyn DspStage> {
+        self.stages.remove(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.stages.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn process(&mut self, samples: &mut [f32], channels: usize, sample_rate: u32) {
+        for stage in self.stages.iter_mut() {
+            stage.process(samples, channels, sample_rate);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+}
+
+/// A serializable configuration of the built-in stages:
+/// preamp, parametric equalizer and limiter, in that order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DspPreset {
+    /// Gain applied before the equalizer, in dB.
+    #[serde(default)]
+    pub preamp: f32,
+    #[serde(default)]
+    pub bands: Vec<EqBand>,
+    #[serde(default)]
+    pub limiter: Option<LimiterOptions>,
+}
+
+impl DspPreset {
+    /// Builds a [`DspChain`] from this preset.
+    ///
+    /// Stages that would not change the signal are skipped.
+    pub fn to_chain(&self) -> DspChain {
+        let mut chain = DspChain::new();
+
+        if self.preamp != 0.0 {
+            chain.push(Box::new(Preamp::new(self.preamp)));
+        }
+        if !self.bands.is_empty() {
+            chain.push(Box::new(Equalizer::new(self.bands.clone())));
+        }
+        if let Some(limiter) = self.limiter {
+            chain.push(Box::new(Limiter::new(limiter)));
+        }
+
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::equalizer::FilterType;
+
+    #[test]
+    fn preset_roundtrip() {
+        let preset: DspPreset = serde_json::from_str(
+            r#"{
+                "preamp": -3.0,
+                "bands": [
+                    { "type": "low_shelf", "frequency": 100.0, "gain": 4.0 },
+                    { "type": "peaking", "frequency": 1000.0, "gain": -2.0, "q": 1.4 }
+                ],
+                "limiter": { "threshold": -1.0 }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(preset.bands[0].kind, FilterType::LowShelf);
+        assert_eq!(preset.bands[1].q, 1.4);
+        assert_eq!(preset.to_chain().len(), 3);
+
+        let json = serde_json::to_string(&preset).unwrap();
+        assert_eq!(serde_json::from_str::<DspPreset>(&json).unwrap(), preset);
+    }
+
+    #[test]
+    fn empty_preset() {
+        assert!(DspPreset::default().to_chain().is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+// This file is a part of simple_audio
+// Copyright (c) 2022-2023 Erikas Taroza <erikastaroza@gmail.com>
+//
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License as
+// published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along with this program.
+// If not, see <https://www.gnu.org/licenses/>.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::chain::DspStage;
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+fn default_q() -> f32 {
+    std::f32::consts::FRAC_1_SQRT_2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterType {
+    Peaking,
+    LowShelf,
+    HighShelf,
+    LowPass,
+    HighPass,
+}
+
+/// A band of the parametric equalizer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EqBand {
+    #[serde(rename = "type")]
+    pub kind: FilterType,
+    /// Center or corner frequency, in Hz.
+    pub frequency: f32,
+    /// Gain in dB. Ignored by low-pass and high-pass filters.
+    #[serde(default)]
+    pub gain: f32,
+    #[serde(default = "default_q")]
+    pub q: f32,
+}
+
+/// Normalized biquad coefficients, from the Audio EQ Cookbook by Robert Bristow-Johnson.
+#[derive(Debug, Clone, Copy)]
+struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Coefficients {
+    fn new(band: &EqBand, sample_rate: u32) -> Self {
+        let fs = sample_rate as f64;
+        // Keep the frequency below nyquist.
+        let f0 = (band.frequency as f64).clamp(1.0, fs * 0.49);
+        let a = 10.0_f64.powf(band.gain as f64 / 40.0);
+        let w0 = 2.0 * PI * f0 / fs;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * band.q.max(0.01) as f64);
+
+        let (b0, b1, b2, a0, a1, a2) = match band.kind {
+            FilterType::Peaking => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            FilterType::LowShelf => {
+                let sqrt_a = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a),
+                    (a + 1.0) + (a - 1.0) * cos + sqrt_a,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - sqrt_a,
+                )
+            }
+            FilterType::HighShelf => {
+                let sqrt_a = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a),
+                    (a + 1.0) - (a - 1.0) * cos + sqrt_a,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - sqrt_a,
+                )
+            }
+            FilterType::LowPass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            FilterType::HighPass => (
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+        };
+
+        Coefficients {
+            b0: (b0 / a0) as f32,
+            b1: (b1 / a0) as f32,
+            b2: (b2 / a0) as f32,
+            a1: (a1 / a0) as f32,
+            a2: (a2 / a0) as f32,
+        }
+    }
+}
+
+/// A biquad filter in transposed direct form II, with a separate state per channel.
+struct Biquad {
+    coefficients: Coefficients,
+    state: Vec<[f32; 2]>,
+}
+
+impl Biquad {
+    fn new(band: &EqBand, channels: usize, sample_rate: u32) -> Self {
+        Biquad {
+            coefficients: Coefficients::new(band, sample_rate),
+            state: vec![[0.0; 2]; channels],
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f32) -> f32 {
+        let Coefficients { b0, b1, b2, a1, a2 } = self.coefficients;
+        let z = &mut self.state[channel];
+
+        let y = b0 * x + z[0];
+        z[0] = b1 * x - a1 * y + z[1];
+        z[1] = b2 * x - a2 * y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.state.fill([0.0; 2]);
+    }
+}
+
+/// A multi-band parametric equalizer made of cascaded biquad filters.
+pub struct Equalizer {
+    bands: Vec<EqBand>,
+    filters: Vec<Biquad>,
+    channels: usize,
+    sample_rate: u32,
+}
+
+impl Equalizer {
+    pub fn new(bands: Vec<EqBand>) -> Self {
+        Equalizer {
+            bands,
+            filters: Vec::new(),
+            channels: 0,
+            sample_rate: 0,
+        }
+    }
+
+    pub fn bands(&self) -> &[EqBand] {
+        &self.bands
+    }
+
+    /// Replaces the bands. Filter states are kept if the number of bands is unchanged.
+    pub fn set_bands(&mut self, bands: Vec<EqBand>) {
+        if bands.len() == self.bands.len() && self.sample_rate != 0 {
+            for (filter, band) in self.filters.iter_mut().zip(bands.iter()) {
+                filter.coefficients = Coefficients::new(band, self.sample_rate);
+            }
+        } else {
+            // rebuild on next `process`
+            self.sample_rate = 0;
+        }
+        self.bands = bands;
+    }
+
+    fn prepare(&mut self, channels: usize, sample_rate: u32) {
+        self.filters = self
+            .bands
+            .iter()
+            .map(|band| Biquad::new(band, channels, sample_rate))
+            .collect();
+        self.channels = channels;
+        self.sample_rate = sample_rate;
+    }
+}
+
+impl DspStage for Equalizer {
+    fn process(&mut self, samples: &mut [f32], channels: usize, sample_rate: u32) {
+        if channels != self.channels || sample_rate != self.sample_rate {
+            self.prepare(channels, sample_rate);
+        }
+
+        for frame in samples.chunks_exact_mut(channels) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = self
+                    .filters
+                    .iter_mut()
+                    .fold(*sample, |x, filter| filter.process(channel, x));
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.filters.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+/// A constant gain, in dB.
+pub struct Preamp {
+    gain: f32,
+}
+
+impl Preamp {
+    pub fn new(gain: f32) -> Self {
+        Preamp {
+            gain: db_to_gain(gain),
+        }
+    }
+}
+
+impl DspStage for Preamp {
+    fn process(&mut self, samples: &mut [f32], _channels: usize, _sample_rate: u32) {
+        samples.iter_mut().for_each(|s| *s *= self.gain);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, sample_rate: u32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |max, s| s.abs().max(max))
+    }
+
+    #[test]
+    fn flat_band_is_transparent() {
+        let mut eq = Equalizer::new(vec![EqBand {
+            kind: FilterType::Peaking,
+            frequency: 1000.0,
+            gain: 0.0,
+            q: 1.0,
+        }]);
+
+        let input = sine(440.0, 48000, 4800);
+        let mut output = input.clone();
+        eq.process(&mut output, 1, 48000);
+
+        for (a, b) in input.iter().zip(output.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn low_pass_attenuates() {
+        let mut eq = Equalizer::new(vec![EqBand {
+            kind: FilterType::LowPass,
+            frequency: 500.0,
+            gain: 0.0,
+            q: default_q(),
+        }]);
+
+        let mut samples = sine(8000.0, 48000, 4800);
+        eq.process(&mut samples, 1, 48000);
+
+        // skip the transient at the beginning
+        assert!(peak(&samples[480..]) < 0.05);
+    }
+
+    #[test]
+    fn peaking_boost() {
+        let mut eq = Equalizer::new(vec![EqBand {
+            kind: FilterType::Peaking,
+            frequency: 1000.0,
+            gain: 6.0,
+            q: 1.0,
+        }]);
+
+        let mut samples = sine(1000.0, 48000, 4800);
+        eq.process(&mut samples, 1, 48000);
+
+        let expected = db_to_gain(6.0);
+        assert!((peak(&samples[480..]) - expected).abs() < 0.05);
+    }
+}
@@ -0,0 +1,187 @@
+// This file is a part of simple_audio
+// Copyright (c) 2022-2023 Erikas Taroza <erikastaroza@gmail.com>
+//
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License as
+// published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along with this program.
+// If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::VecDeque, f32::consts::FRAC_PI_2, time::Duration};
+
+/// Converts a `duration` to the number of frames at `sample_rate`.
+pub fn duration_to_frames(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_millis() * sample_rate as u128 / 1000) as usize
+}
+
+/// A linear gain ramp over interleaved samples.
+///
+/// Used to fade the output in and out on play, pause and seek.
+pub struct Fader {
+    channels: usize,
+    gain: f32,
+    target: f32,
+    /// Gain change per frame.
+    step: f32,
+}
+
+impl Fader {
+    pub fn new(channels: usize) -> Self {
+        Fader {
+            channels,
+            gain: 1.0,
+            target: 1.0,
+            step: 0.0,
+        }
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Returns true if the gain has not reached its target yet.
+    pub fn is_ramping(&self) -> bool {
+        self.gain != self.target
+    }
+
+    /// Ramps the gain from its current value to `target` in `frames` frames.
+    ///
+    /// The gain is set immediately if `frames` is 0.
+    pub fn ramp_to(&mut self, target: f32, frames: usize) {
+        self.target = target;
+
+        if frames == 0 {
+            self.gain = target;
+            self.step = 0.0;
+        } else {
+            self.step = (target - self.gain) / frames as f32;
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if !self.is_ramping() {
+            if self.gain != 1.0 {
+                samples.iter_mut().for_each(|s| *s *= self.gain);
+            }
+            return;
+        }
+
+        for frame in samples.chunks_mut(self.channels) {
+            frame.iter_mut().for_each(|s| *s *= self.gain);
+
+            self.gain += self.step;
+            if (self.step > 0.0 && self.gain >= self.target)
+                || (self.step < 0.0 && self.gain <= self.target)
+            {
+                self.gain = self.target;
+                self.step = 0.0;
+            }
+        }
+    }
+}
+
+/// Mixes the tail of an outgoing track into the head of an incoming one.
+///
+/// Samples of the outgoing track are queued with [`Crossfader::push_outgoing`] and
+/// mixed into the incoming samples with equal-power curves.
+pub struct Crossfader {
+    channels: usize,
+    /// Length of the crossfade in frames.
+    frames: usize,
+    position: usize,
+    outgoing: VecDeque<f32>,
+}
+
+impl Crossfader {
+    pub fn new(channels: usize, frames: usize) -> Self {
+        Crossfader {
+            channels,
+            frames,
+            position: 0,
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    /// Number of queued outgoing samples.
+    pub fn buffered(&self) -> usize {
+        self.outgoing.len()
+    }
+
+    pub fn push_outgoing(&mut self, samples: &[f32]) {
+        self.outgoing.extend(samples);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.frames
+    }
+
+    /// Mixes queued outgoing samples into `incoming`.
+    ///
+    /// Missing outgoing samples are treated as silence.
+    pub fn mix(&mut self, incoming: &mut [f32]) {
+        for frame in incoming.chunks_mut(self.channels) {
+            let (gain_in, gain_out) = if self.position < self.frames {
+                let t = self.position as f32 / self.frames as f32 * FRAC_PI_2;
+                (t.sin(), t.cos())
+            } else {
+                (1.0, 0.0)
+            };
+
+            for sample in frame {
+                let outgoing = self.outgoing.pop_front().unwrap_or(0.0);
+                *sample = *sample * gain_in + outgoing * gain_out;
+            }
+
+            self.position += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fader_ramp() {
+        let mut fader = Fader::new(2);
+        fader.ramp_to(0.0, 4);
+        assert!(fader.is_ramping());
+
+        let mut samples = [1.0; 10];
+        fader.process(&mut samples);
+        assert_eq!(
+            samples,
+            [1.0, 1.0, 0.75, 0.75, 0.5, 0.5, 0.25, 0.25, 0.0, 0.0]
+        );
+        assert!(!fader.is_ramping());
+        assert_eq!(fader.gain(), 0.0);
+
+        fader.ramp_to(1.0, 0);
+        assert!(!fader.is_ramping());
+        assert_eq!(fader.gain(), 1.0);
+    }
+
+    #[test]
+    fn crossfader_mix() {
+        let mut crossfader = Crossfader::new(1, 4);
+        crossfader.push_outgoing(&[1.0; 2]);
+
+        let mut samples = [0.0; 6];
+        crossfader.mix(&mut samples);
+
+        // outgoing starts at full gain
+        assert_eq!(samples[0], 1.0);
+        assert!(samples[1] < 1.0 && samples[1] > 0.0);
+        // outgoing samples ran out
+        assert_eq!(&samples[2..], &[0.0; 4]);
+        assert!(crossfader.is_finished());
+        assert_eq!(crossfader.buffered(), 0);
+    }
+}
@@ -0,0 +1,124 @@
+// This file is a part of simple_audio
+// Copyright (c) 2022-2023 Erikas Taroza <erikastaroza@gmail.com>
+//
+// This program is free software: you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public License as
+// published by the Free Software Foundation, either version 3 of
+// the License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along with this program.
+// If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use super::chain::DspStage;
+
+fn default_threshold() -> f32 {
+    -1.0
+}
+
+fn default_release() -> f32 {
+    100.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LimiterOptions {
+    /// Maximum output level, in dBFS.
+    #[serde(default = "default_threshold")]
+    pub threshold: f32,
+    /// Time for the gain to recover after a peak, in milliseconds.
+    #[serde(default = "default_release")]
+    pub release: f32,
+}
+
+impl Default for LimiterOptions {
+    fn default() -> Self {
+        LimiterOptions {
+            threshold: default_threshold(),
+            release: default_release(),
+        }
+    }
+}
+
+/// A peak limiter with instant attack, keeping the output below the threshold
+/// after boosting with the preamp or equalizer.
+pub struct Limiter {
+    options: LimiterOptions,
+    threshold: f32,
+    /// Per-frame release coefficient for `sample_rate`.
+    release: f32,
+    sample_rate: u32,
+    gain: f32,
+}
+
+impl Limiter {
+    pub fn new(options: LimiterOptions) -> Self {
+        Limiter {
+            options,
+            threshold: 10.0_f32.powf(options.threshold / 20.0),
+            release: 0.0,
+            sample_rate: 0,
+            gain: 1.0,
+        }
+    }
+
+    pub fn options(&self) -> LimiterOptions {
+        self.options
+    }
+}
+
+impl DspStage for Limiter {
+    fn process(&mut self, samples: &mut [f32], channels: usize, sample_rate: u32) {
+        if sample_rate != self.sample_rate {
+            let release_frames = self.options.release.max(1.0) / 1000.0 * sample_rate as f32;
+            self.release = (-1.0 / release_frames).exp();
+            self.sample_rate = sample_rate;
+        }
+
+        for frame in samples.chunks_exact_mut(channels) {
+            let peak = frame.iter().fold(0.0_f32, |max, s| s.abs().max(max));
+            let target = if peak > self.threshold {
+                self.threshold / peak
+            } else {
+                1.0
+            };
+
+            self.gain = if target < self.gain {
+                target
+            } else {
+                target + (self.gain - target) * self.release
+            };
+
+            frame.iter_mut().for_each(|s| *s *= self.gain);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_peaks() {
+        let mut limiter = Limiter::new(LimiterOptions {
+            threshold: -6.0,
+            release: 10.0,
+        });
+        let threshold = 10.0_f32.powf(-6.0 / 20.0);
+
+        let mut samples = vec![0.1, -0.1, 1.5, -1.5, 0.9, 0.9, 0.1, 0.1];
+        limiter.process(&mut samples, 2, 48000);
+
+        assert_eq!(&samples[0..2], &[0.1, -0.1]);
+        assert!(samples.iter().all(|s| s.abs() <= threshold + 1e-6));
+    }
+}
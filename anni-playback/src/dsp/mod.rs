@@ -14,5 +14,13 @@
 // You should have received a copy of the GNU Lesser General Public License along with this program.
 // If not, see <https://www.gnu.org/licenses/>.
 
+pub mod chain;
+pub mod equalizer;
+pub mod fader;
+pub mod limiter;
 pub mod normalizer;
 pub mod resampler;
+
+pub use chain::{DspChain, DspPreset, DspStage};
+pub use equalizer::{EqBand, Equalizer, FilterType, Preamp};
+pub use limiter::{Limiter, LimiterOptions};
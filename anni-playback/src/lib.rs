@@ -22,7 +22,14 @@ mod utils;
 
 pub use controls::Controls;
 pub use decoder::*;
+pub use dsp::{
+    DspChain, DspPreset, DspStage, EqBand, Equalizer, FilterType, Limiter, LimiterOptions, Preamp,
+};
+pub mod download;
+#[cfg(all(feature = "mpris", target_os = "linux"))]
+pub mod mpris;
 pub mod player;
+pub mod scrobble;
 pub mod sources;
 pub mod types;
 
@@ -0,0 +1,504 @@
+//! MPRIS2 integration over D-Bus.
+//!
+//! [`MprisServer`] exposes [`Controls`] as `org.mpris.MediaPlayer2` so desktop media keys and
+//! now-playing widgets work out of the box. Track metadata is supplied by the caller with
+//! [`MprisServer::set_metadata`], and [`PlayerEvent`]s should be forwarded to
+//! [`MprisServer::handle_event`].
+//!
+//! See <https://specifications.freedesktop.org/mpris-spec/latest/> for the specification.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use zbus::{
+    blocking::{connection::Builder, Connection},
+    fdo, interface,
+    object_server::SignalContext,
+    zvariant::{ObjectPath, OwnedValue, Value},
+};
+
+use crate::{types::PlayerEvent, Controls};
+
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// Position changes larger than this are reported as seeks, in milliseconds.
+const SEEK_THRESHOLD: u64 = 2000;
+
+/// Metadata of the current track, supplied by the caller.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackMetadata {
+    /// A unique id of the track, e.g. the display form of `TrackIdentifier`.
+    pub track_id: String,
+    pub title: Option<String>,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub album_artists: Vec<String>,
+    pub disc_number: Option<u32>,
+    pub track_number: Option<u32>,
+    pub art_url: Option<String>,
+    /// Length of the track in milliseconds. Falls back to the duration reported by the decoder.
+    pub length: Option<u64>,
+}
+
+impl TrackMetadata {
+    /// Object path used as `mpris:trackid`.
+    fn object_path(&self) -> String {
+        let id: String = self
+            .track_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("/org/projectanni/track/_{id}")
+    }
+}
+
+pub struct MprisOptions {
+    /// Suffix of the bus name, `org.mpris.MediaPlayer2.{name}`.
+    pub name: String,
+    /// Human readable name of the player.
+    pub identity: String,
+    /// Address of the bus to connect to. Defaults to the session bus.
+    pub address: Option<String>,
+}
+
+#[derive(Default)]
+struct MprisState {
+    metadata: Option<TrackMetadata>,
+    /// Last position reported by `PlayerEvent::Progress`, in milliseconds.
+    position: u64,
+    /// Last duration reported by `PlayerEvent::Progress`, in milliseconds.
+    duration: u64,
+}
+
+type SharedState = Arc<Mutex<MprisState>>;
+
+fn lock(state: &SharedState) -> MutexGuard<'_, MprisState> {
+    state.lock().unwrap()
+}
+
+fn to_owned_value<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+    // only fd values can fail here
+    value.into().try_to_owned().unwrap()
+}
+
+fn playback_status(controls: &Controls) -> &'static str {
+    if controls.is_playing() {
+        "Playing"
+    } else if controls.is_stopped() {
+        "Stopped"
+    } else {
+        "Paused"
+    }
+}
+
+fn loop_status(controls: &Controls) -> &'static str {
+    if controls.is_looping() {
+        "Track"
+    } else {
+        "None"
+    }
+}
+
+fn metadata(state: &MprisState) -> HashMap<String, OwnedValue> {
+    let mut map = HashMap::new();
+
+    let Some(metadata) = &state.metadata else {
+        map.insert(
+            "mpris:trackid".to_owned(),
+            to_owned_value(ObjectPath::from_static_str_unchecked(NO_TRACK)),
+        );
+        return map;
+    };
+
+    let path = metadata.object_path();
+    map.insert(
+        "mpris:trackid".to_owned(),
+        to_owned_value(ObjectPath::try_from(path.as_str()).unwrap()),
+    );
+
+    let length = metadata.length.unwrap_or(state.duration);
+    if length > 0 {
+        map.insert(
+            "mpris:length".to_owned(),
+            to_owned_value(length as i64 * 1000),
+        );
+    }
+    if let Some(title) = &metadata.title {
+        map.insert("xesam:title".to_owned(), to_owned_value(title.as_str()));
+    }
+    if !metadata.artists.is_empty() {
+        map.insert("xesam:artist".to_owned(), to_owned_value(&metadata.artists));
+    }
+    if let Some(album) = &metadata.album {
+        map.insert("xesam:album".to_owned(), to_owned_value(album.as_str()));
+    }
+    if !metadata.album_artists.is_empty() {
+        map.insert(
+            "xesam:albumArtist".to_owned(),
+            to_owned_value(&metadata.album_artists),
+        );
+    }
+    if let Some(disc_number) = metadata.disc_number {
+        map.insert(
+            "xesam:discNumber".to_owned(),
+            to_owned_value(disc_number as i32),
+        );
+    }
+    if let Some(track_number) = metadata.track_number {
+        map.insert(
+            "xesam:trackNumber".to_owned(),
+            to_owned_value(track_number as i32),
+        );
+    }
+    if let Some(art_url) = &metadata.art_url {
+        map.insert("mpris:artUrl".to_owned(), to_owned_value(art_url.as_str()));
+    }
+
+    map
+}
+
+/// `org.mpris.MediaPlayer2`
+struct Root {
+    identity: String,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        self.identity.clone()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// `org.mpris.MediaPlayer2.Player`
+struct Player {
+    controls: Controls,
+    state: SharedState,
+}
+
+impl Player {
+    async fn seek_to(&self, position: u64, ctxt: &SignalContext<'_>) -> fdo::Result<()> {
+        let duration = self.controls.progress().duration;
+        let position = if duration > 0 {
+            position.min(duration)
+        } else {
+            position
+        };
+
+        self.controls.seek(position);
+        // avoid reporting the seek twice when the progress is updated
+        lock(&self.state).position = position;
+
+        Player::seeked(ctxt, position as i64 * 1000).await?;
+        Ok(())
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        if self.controls.is_file_preloaded() {
+            self.controls.play_preloaded();
+        }
+    }
+
+    fn previous(&self) {}
+
+    fn pause(&self) {
+        self.controls.pause();
+    }
+
+    fn play_pause(&self) {
+        if self.controls.is_playing() {
+            self.controls.pause();
+        } else {
+            self.controls.play();
+        }
+    }
+
+    fn stop(&self) {
+        self.controls.stop();
+    }
+
+    fn play(&self) {
+        self.controls.play();
+    }
+
+    /// Seeks forward by `offset` microseconds. A negative value seeks backwards.
+    async fn seek(
+        &self,
+        offset: i64,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        let position = self.controls.progress().position as i64 + offset / 1000;
+        self.seek_to(position.max(0) as u64, &ctxt).await
+    }
+
+    /// Seeks to `position` microseconds if `track_id` is the current track.
+    async fn set_position(
+        &self,
+        track_id: ObjectPath<'_>,
+        position: i64,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        let is_current = lock(&self.state)
+            .metadata
+            .as_ref()
+            .is_some_and(|m| m.object_path() == track_id.as_str());
+        if !is_current || position < 0 {
+            return Ok(());
+        }
+
+        self.seek_to(position as u64 / 1000, &ctxt).await
+    }
+
+    fn open_uri(&self, _uri: String) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "OpenUri is not supported".to_owned(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(ctxt: &SignalContext<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        playback_status(&self.controls).to_owned()
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> String {
+        loop_status(&self.controls).to_owned()
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, value: String) -> fdo::Result<()> {
+        match value.as_str() {
+            "None" => self.controls.set_is_looping(false),
+            "Track" => self.controls.set_is_looping(true),
+            _ => {
+                return Err(fdo::Error::NotSupported(format!(
+                    "LoopStatus {value} is not supported"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn set_rate(&mut self, _value: f64) {}
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        metadata(&lock(&self.state))
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        *self.controls.volume() as f64
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, value: f64) {
+        self.controls.set_volume(value.max(0.0) as f32);
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.controls.progress().position as i64 * 1000
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.controls.is_file_preloaded()
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        lock(&self.state).metadata.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Exposes the player on D-Bus as an MPRIS2 media player.
+///
+/// The server runs until it is dropped.
+pub struct MprisServer {
+    connection: Connection,
+    controls: Controls,
+    state: SharedState,
+}
+
+impl MprisServer {
+    pub fn new(controls: Controls, options: MprisOptions) -> zbus::Result<Self> {
+        let MprisOptions {
+            name,
+            identity,
+            address,
+        } = options;
+
+        let state = SharedState::default();
+        let builder = match address {
+            Some(address) => Builder::address(address.as_str())?,
+            None => Builder::session()?,
+        };
+        let connection = builder
+            .name(format!("org.mpris.MediaPlayer2.{name}"))?
+            .serve_at(OBJECT_PATH, Root { identity })?
+            .serve_at(
+                OBJECT_PATH,
+                Player {
+                    controls: controls.clone(),
+                    state: state.clone(),
+                },
+            )?
+            .build()?;
+
+        Ok(Self {
+            connection,
+            controls,
+            state,
+        })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Sets the metadata of the current track. `None` means no track is loaded.
+    pub fn set_metadata(&self, metadata: Option<TrackMetadata>) -> zbus::Result<()> {
+        let metadata = {
+            let mut state = lock(&self.state);
+            state.metadata = metadata;
+            Value::from(self::metadata(&state))
+        };
+
+        self.properties_changed(&[
+            ("Metadata", metadata),
+            ("CanPlay", Value::from(lock(&self.state).metadata.is_some())),
+        ])
+    }
+
+    /// Updates the exposed state from an event emitted by the player.
+    pub fn handle_event(&self, event: &PlayerEvent) -> zbus::Result<()> {
+        match event {
+            PlayerEvent::Play | PlayerEvent::Pause | PlayerEvent::Stop => {
+                self.properties_changed(&[(
+                    "PlaybackStatus",
+                    Value::from(playback_status(&self.controls)),
+                )])
+            }
+            PlayerEvent::PreloadPlayed => self.properties_changed(&[(
+                "CanGoNext",
+                Value::from(self.controls.is_file_preloaded()),
+            )]),
+            PlayerEvent::Progress(progress) => {
+                let (seeked, duration_changed) = {
+                    let mut state = lock(&self.state);
+                    let seeked = progress.position.abs_diff(state.position) > SEEK_THRESHOLD;
+                    let duration_changed = progress.duration != state.duration;
+
+                    state.position = progress.position;
+                    state.duration = progress.duration;
+                    (seeked, duration_changed)
+                };
+
+                if seeked {
+                    self.connection.emit_signal(
+                        None::<()>,
+                        OBJECT_PATH,
+                        PLAYER_INTERFACE,
+                        "Seeked",
+                        &(progress.position as i64 * 1000),
+                    )?;
+                }
+                if duration_changed {
+                    let metadata = Value::from(metadata(&lock(&self.state)));
+                    self.properties_changed(&[("Metadata", metadata)])?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn properties_changed(&self, changed: &[(&str, Value<'_>)]) -> zbus::Result<()> {
+        let changed: HashMap<&str, &Value<'_>> =
+            changed.iter().map(|(name, value)| (*name, value)).collect();
+
+        self.connection.emit_signal(
+            None::<()>,
+            OBJECT_PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            &(PLAYER_INTERFACE, changed, Vec::<&str>::new()),
+        )
+    }
+}
@@ -1,6 +1,6 @@
 pub use anni_provider::providers::TypedPriorityProvider;
 
-pub use crate::sources::cached_http::provider::AudioQuality;
+pub use crate::sources::{cached_http::provider::AudioQuality, provider::PlaybackProvider};
 
 use crossbeam::channel::Sender;
 use reqwest::blocking::Client;
@@ -16,10 +16,18 @@ use std::{
 };
 
 use anni_common::models::TrackIdentifier;
+use anni_provider::AnniProvider;
 
 use crate::{
-    sources::cached_http::{
-        cache::CacheStore, provider::ProviderProxy, CachedAnnilSource, OpenTrackError,
+    download::{DownloadEvent, DownloadManager},
+    sources::{
+        cached_http::{
+            cache::{CacheKey, CacheStore},
+            provider::ProviderProxy,
+            CachedAnnilSource, OpenTrackError,
+        },
+        provider::ProviderSource,
+        AnniSource,
     },
     types::PlayerEvent,
     Controls, Decoder,
@@ -29,25 +37,33 @@ pub struct AnniPlayer {
     pub controls: Controls,
     pub client: Client,
     pub thread_killer: Sender<bool>,
-    provider: RwLock<TypedPriorityProvider<ProviderProxy>>,
+    provider: Arc<RwLock<TypedPriorityProvider<PlaybackProvider>>>,
     cache_store: CacheStore, // root of cache
 }
 
 pub struct AnniPlayerOptions {
     pub sample_rate: u32,
     pub cache_path: PathBuf,
+    /// Maximum size of the cache in bytes. The cache is unbounded if `None`.
+    pub cache_quota: Option<u64>,
 }
 
 impl AnniPlayer {
     pub fn new(
-        provider: TypedPriorityProvider<ProviderProxy>,
+        provider: TypedPriorityProvider<PlaybackProvider>,
         options: AnniPlayerOptions,
     ) -> (Self, Receiver<PlayerEvent>) {
         let AnniPlayerOptions {
             sample_rate,
             cache_path,
+            cache_quota,
         } = options;
 
+        let mut cache_store = CacheStore::new(cache_path);
+        if let Some(quota) = cache_quota {
+            cache_store = cache_store.with_quota(quota);
+        }
+
         let (controls, receiver, killer) = {
             let (sender, receiver) = mpsc::channel();
             let controls = Controls::new(sender);
@@ -73,8 +89,8 @@ impl AnniPlayer {
                 controls,
                 client: Client::new(),
                 thread_killer: killer,
-                provider: RwLock::new(provider),
-                cache_store: CacheStore::new(cache_path),
+                provider: Arc::new(RwLock::new(provider)),
+                cache_store,
             },
             receiver,
         )
@@ -83,7 +99,24 @@ impl AnniPlayer {
     pub fn add_provider(&self, url: String, auth: String, priority: i32) {
         let mut provider = self.provider.write().unwrap();
 
-        provider.insert(ProviderProxy::new(url, auth, self.client.clone()), priority);
+        provider.insert(
+            ProviderProxy::new(url, auth, self.client.clone()).into(),
+            priority,
+        );
+    }
+
+    /// Adds a provider, e.g. a [`ProviderProxy`] with a token provider to refresh its credentials.
+    pub fn insert_provider(&self, provider: impl Into<PlaybackProvider>, priority: i32) {
+        let mut providers = self.provider.write().unwrap();
+
+        providers.insert(provider.into(), priority);
+    }
+
+    /// Adds an [`AnniProvider`], e.g. a local workspace, which is read directly instead of through annil.
+    pub fn add_local_provider(&self, local: Arc<dyn AnniProvider + Send + Sync>, priority: i32) {
+        let mut provider = self.provider.write().unwrap();
+
+        provider.insert(PlaybackProvider::Local(local), priority);
     }
 
     pub fn clear_provider(&self) {
@@ -102,24 +135,80 @@ impl AnniPlayer {
 
         self.controls.pause();
 
-        let provider = self.provider.read().unwrap();
+        let buffer_signal = Arc::new(AtomicBool::new(true));
+        let source = self.open_source(track, quality, opus, buffer_signal.clone())?;
+
+        self.controls.open(source, buffer_signal, false);
+
+        Ok(())
+    }
+
+    /// Preloads `track` to be played after the current one.
+    ///
+    /// If it's the next track of the same album, the two tracks are played gapless.
+    pub fn preload(
+        &self,
+        track: TrackIdentifier,
+        quality: AudioQuality,
+        opus: bool,
+    ) -> Result<(), OpenTrackError> {
+        log::info!("preloading track: {track}");
 
         let buffer_signal = Arc::new(AtomicBool::new(true));
-        let source = CachedAnnilSource::new(
-            track,
-            quality,
-            &self.cache_store,
-            self.client.clone(),
-            &provider,
-            buffer_signal.clone(),
-            opus,
-        )?;
-
-        self.controls.open(Box::new(source), buffer_signal, false);
+        let source = self.open_source(track, quality, opus, buffer_signal.clone())?;
+
+        self.controls.open(source, buffer_signal, true);
 
         Ok(())
     }
 
+    /// Opens `track` from cache if it is fully cached, or from the first available provider.
+    fn open_source(
+        &self,
+        track: TrackIdentifier,
+        quality: AudioQuality,
+        opus: bool,
+        buffer_signal: Arc<AtomicBool>,
+    ) -> Result<Box<dyn AnniSource>, OpenTrackError> {
+        let open_cached = |providers: &[&ProviderProxy]| {
+            CachedAnnilSource::new(
+                track.clone(),
+                quality,
+                &self.cache_store,
+                providers.iter().copied(),
+                buffer_signal.clone(),
+                opus,
+            )
+            .map(|source| Box::new(source) as Box<dyn AnniSource>)
+        };
+
+        let key = CacheKey::new(track.inner.copied(), quality, opus);
+        if self.cache_store.is_cached(key)? {
+            return open_cached(&[]);
+        }
+
+        let provider = self.provider.read().unwrap();
+        for provider in provider.providers() {
+            let source = match provider {
+                PlaybackProvider::Remote(remote) => open_cached(&[remote]),
+                PlaybackProvider::Local(local) => ProviderSource::new(local.clone(), track.clone())
+                    .map(|source| Box::new(source) as Box<dyn AnniSource>)
+                    .map_err(|e| {
+                        log::warn!("failed to open {track} from local provider: {e}");
+                        OpenTrackError::NoAvailableAnnil
+                    }),
+            };
+
+            match source {
+                Ok(source) => return Ok(source),
+                Err(OpenTrackError::NoAvailableAnnil) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(OpenTrackError::NoAvailableAnnil)
+    }
+
     pub fn open_and_play(
         &self,
         track: TrackIdentifier,
@@ -155,4 +244,17 @@ impl AnniPlayer {
     pub fn seek(&self, position: u64) {
         self.controls.seek(position);
     }
+
+    pub fn cache_store(&self) -> &CacheStore {
+        &self.cache_store
+    }
+
+    /// Creates a [`DownloadManager`] that fetches tracks into the cache of this player,
+    /// using the same remote providers.
+    pub fn download_manager(
+        &self,
+        concurrency: usize,
+    ) -> (DownloadManager, Receiver<DownloadEvent>) {
+        DownloadManager::new(self.provider.clone(), self.cache_store.clone(), concurrency)
+    }
 }
@@ -0,0 +1,106 @@
+use reqwest::{blocking::Client, header::AUTHORIZATION};
+use serde_json::{json, Value};
+
+use super::{Listen, ListenTrack, ScrobbleError, Scrobbler};
+
+const LISTENBRAINZ_API: &str = "https://api.listenbrainz.org";
+
+/// Submits listens to ListenBrainz, or any server implementing its API.
+pub struct ListenBrainz {
+    url: String,
+    token: String,
+    client: Client,
+}
+
+impl ListenBrainz {
+    pub fn new(token: String, client: Client) -> Self {
+        Self {
+            url: LISTENBRAINZ_API.to_owned(),
+            token,
+            client,
+        }
+    }
+
+    /// Uses another server compatible with the ListenBrainz API.
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = url;
+        self
+    }
+
+    fn post(&self, listen_type: &str, payload: Vec<Value>) -> Result<(), ScrobbleError> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/1/submit-listens",
+                self.url.trim_end_matches('/')
+            ))
+            .header(AUTHORIZATION, format!("Token {}", self.token))
+            .json(&json!({
+                "listen_type": listen_type,
+                "payload": payload,
+            }))
+            .send()?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(ScrobbleError::Rejected {
+                status: status.as_u16(),
+                message: response.text().unwrap_or_default(),
+            })
+        }
+    }
+}
+
+fn track_metadata(track: &ListenTrack, duration: Option<u64>) -> Value {
+    let mut additional_info = json!({
+        "media_player": "anni-playback",
+        "submission_client": "anni-playback",
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(track_number) = track.track_number {
+        additional_info["tracknumber"] = track_number.into();
+    }
+    if let Some(duration) = duration.filter(|d| *d > 0) {
+        additional_info["duration_ms"] = duration.into();
+    }
+
+    let mut metadata = json!({
+        "artist_name": track.artist,
+        "track_name": track.title,
+        "additional_info": additional_info,
+    });
+    if let Some(album) = &track.album {
+        metadata["release_name"] = album.as_str().into();
+    }
+    metadata
+}
+
+impl Scrobbler for ListenBrainz {
+    fn now_playing(&self, track: &ListenTrack) -> Result<(), ScrobbleError> {
+        self.post(
+            "playing_now",
+            vec![json!({ "track_metadata": track_metadata(track, None) })],
+        )
+    }
+
+    fn submit(&self, listens: &[Listen]) -> Result<(), ScrobbleError> {
+        let listen_type = if listens.len() == 1 {
+            "single"
+        } else {
+            "import"
+        };
+        let payload = listens
+            .iter()
+            .map(|listen| {
+                json!({
+                    "listened_at": listen.listened_at,
+                    "track_metadata": track_metadata(&listen.track, Some(listen.duration)),
+                })
+            })
+            .collect();
+
+        self.post(listen_type, payload)
+    }
+}
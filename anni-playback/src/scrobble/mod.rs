@@ -0,0 +1,132 @@
+mod listenbrainz;
+mod session;
+
+pub use listenbrainz::ListenBrainz;
+pub use session::{Listen, ListenEvent, ListenOptions, ListenTrack, ListenTracker};
+
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use thiserror::Error;
+
+/// Maximum number of listens submitted in one request.
+const MAX_BATCH: usize = 100;
+
+#[derive(Debug, Error)]
+pub enum ScrobbleError {
+    #[error("Request Error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Rejected by server ({status}): {message}")]
+    Rejected { status: u16, message: String },
+    #[error("Io Error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A service that listens are submitted to.
+pub trait Scrobbler: Send {
+    /// Reports the track that started playing. Failures are not retried.
+    fn now_playing(&self, track: &ListenTrack) -> Result<(), ScrobbleError> {
+        let _ = track;
+        Ok(())
+    }
+
+    /// Submits completed listens, in the order they were played.
+    fn submit(&self, listens: &[Listen]) -> Result<(), ScrobbleError>;
+}
+
+/// Queues listens for a [`Scrobbler`], keeping them on disk until they are submitted,
+/// so that listens are not lost while offline.
+pub struct ScrobbleQueue<S> {
+    scrobbler: S,
+    path: PathBuf,
+    pending: Vec<Listen>,
+}
+
+impl<S: Scrobbler> ScrobbleQueue<S> {
+    /// Creates a queue persisted to `path`, loading listens left from previous sessions.
+    pub fn new(scrobbler: S, path: PathBuf) -> io::Result<Self> {
+        let pending = match File::open(&path) {
+            Ok(f) => serde_json::from_reader(f)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            scrobbler,
+            path,
+            pending,
+        })
+    }
+
+    pub fn scrobbler(&self) -> &S {
+        &self.scrobbler
+    }
+
+    /// Listens waiting to be submitted.
+    pub fn pending(&self) -> &[Listen] {
+        &self.pending
+    }
+
+    /// Reports started tracks and queues completed listens. Skipped listens are ignored.
+    pub fn handle_event(&mut self, event: &ListenEvent) {
+        match event {
+            ListenEvent::Started(track) => {
+                if let Err(e) = self.scrobbler.now_playing(track) {
+                    log::warn!("failed to report now playing: {e}");
+                }
+            }
+            ListenEvent::Completed(listen) => {
+                if let Err(e) = self.push(listen.clone()) {
+                    log::warn!("failed to submit listen: {e}");
+                }
+            }
+            ListenEvent::Skipped(_) => {}
+        }
+    }
+
+    /// Queues a listen and tries to submit all pending listens.
+    pub fn push(&mut self, listen: Listen) -> Result<(), ScrobbleError> {
+        self.pending.push(listen);
+        self.save()?;
+        self.flush()
+    }
+
+    /// Submits pending listens. Listens are kept in the queue if the submission failed,
+    /// except for those rejected as invalid, which would never be accepted.
+    pub fn flush(&mut self) -> Result<(), ScrobbleError> {
+        while !self.pending.is_empty() {
+            let batch = self.pending.len().min(MAX_BATCH);
+
+            match self.scrobbler.submit(&self.pending[..batch]) {
+                Ok(()) => {}
+                Err(ScrobbleError::Rejected {
+                    status: 400,
+                    message,
+                }) => {
+                    log::warn!("dropping {batch} listens rejected by server: {message}");
+                }
+                Err(e) => return Err(e),
+            }
+
+            self.pending.drain(..batch);
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // write to a temporary file first, so that the queue is not corrupted on crash
+        let mut tmp = self.path.clone();
+        tmp.set_extension("tmp");
+        serde_json::to_writer(File::create(&tmp)?, &self.pending)?;
+        fs::rename(tmp, &self.path)
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    sync::mpsc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::PlayerEvent;
+
+/// Progress jumps larger than this are treated as seeks, in milliseconds.
+const SEEK_THRESHOLD: u64 = 2000;
+
+/// Information of a track submitted to scrobblers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListenTrack {
+    /// Identifier of the track, in `album_id/disc_id/track_id` format.
+    pub track_id: String,
+    pub title: String,
+    pub artist: String,
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub track_number: Option<u32>,
+}
+
+/// A finished listen session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Listen {
+    pub track: ListenTrack,
+    /// Unix timestamp when the track started playing, in seconds.
+    pub listened_at: u64,
+    /// Time actually listened, excluding pauses and seeks, in milliseconds.
+    pub listened: u64,
+    /// Duration of the track in milliseconds, or 0 if unknown.
+    pub duration: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum ListenEvent {
+    /// A track started playing
+    Started(ListenTrack),
+    /// Listened long enough to count as a listen
+    Completed(Listen),
+    /// Ended before reaching the completion threshold
+    Skipped(Listen),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ListenOptions {
+    /// Fraction of the track duration to listen to for a completed listen.
+    pub completion_ratio: f64,
+    /// Upper bound of the completion threshold, in milliseconds.
+    ///
+    /// Also used as the threshold if the duration is unknown.
+    pub max_threshold: u64,
+}
+
+impl Default for ListenOptions {
+    /// Half of the track or 4 minutes, whichever is shorter, as recommended by ListenBrainz and Last.fm.
+    fn default() -> Self {
+        ListenOptions {
+            completion_ratio: 0.5,
+            max_threshold: 240_000,
+        }
+    }
+}
+
+impl ListenOptions {
+    fn threshold(&self, duration: u64) -> u64 {
+        if duration == 0 {
+            self.max_threshold
+        } else {
+            ((duration as f64 * self.completion_ratio) as u64).min(self.max_threshold)
+        }
+    }
+}
+
+struct ListenSession {
+    track: ListenTrack,
+    started_at: u64,
+    listened: u64,
+    position: u64,
+    duration: u64,
+}
+
+/// Tracks listen sessions from [`PlayerEvent`]s.
+///
+/// Call [`ListenTracker::start`] when a track is opened, or after [`PlayerEvent::PreloadPlayed`],
+/// and forward all player events to [`ListenTracker::handle_event`].
+pub struct ListenTracker {
+    options: ListenOptions,
+    session: Option<ListenSession>,
+    sender: mpsc::Sender<ListenEvent>,
+}
+
+impl ListenTracker {
+    pub fn new(options: ListenOptions) -> (Self, mpsc::Receiver<ListenEvent>) {
+        let (sender, receiver) = mpsc::channel();
+
+        (
+            Self {
+                options,
+                session: None,
+                sender,
+            },
+            receiver,
+        )
+    }
+
+    /// Starts a listen session of `track`, finishing the current one.
+    pub fn start(&mut self, track: ListenTrack) {
+        self.finish();
+
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.send(ListenEvent::Started(track.clone()));
+        self.session = Some(ListenSession {
+            track,
+            started_at,
+            listened: 0,
+            position: 0,
+            duration: 0,
+        });
+    }
+
+    /// Finishes the current listen session, emitting [`ListenEvent::Completed`] or [`ListenEvent::Skipped`].
+    pub fn finish(&mut self) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+
+        let listen = Listen {
+            track: session.track,
+            listened_at: session.started_at,
+            listened: session.listened,
+            duration: session.duration,
+        };
+
+        if listen.listened >= self.options.threshold(listen.duration) {
+            self.send(ListenEvent::Completed(listen));
+        } else {
+            self.send(ListenEvent::Skipped(listen));
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::Progress(progress) => {
+                let Some(session) = self.session.as_mut() else {
+                    return;
+                };
+
+                if progress.duration > 0 {
+                    session.duration = progress.duration;
+                }
+                // progress only moves while playing, so pauses are excluded naturally
+                if progress.position >= session.position
+                    && progress.position - session.position <= SEEK_THRESHOLD
+                {
+                    session.listened += progress.position - session.position;
+                }
+                session.position = progress.position;
+            }
+            // the preloaded track replaces the current one
+            PlayerEvent::Stop | PlayerEvent::PreloadPlayed => self.finish(),
+            PlayerEvent::Play | PlayerEvent::Pause => {}
+        }
+    }
+
+    fn send(&self, event: ListenEvent) {
+        let _ = self.sender.send(event);
+    }
+}
@@ -1,8 +1,13 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{self, File},
-    io::{self, ErrorKind},
+    io::{self, ErrorKind, Write},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::SystemTime,
 };
 
 use crate::CODEC_REGISTRY;
@@ -20,56 +25,293 @@ use anni_common::models::RawTrackIdentifier;
 use symphonia_core::io::MediaSource;
 use thiserror::Error;
 
+use super::provider::AudioQuality;
+
+const PINNED_FILE: &str = "pinned.json";
+
+/// Identifies a cache entry: a track at a specific quality and codec.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct CacheKey<'a> {
+    pub track: RawTrackIdentifier<'a>,
+    pub quality: AudioQuality,
+    pub opus: bool,
+}
+
+impl<'a> CacheKey<'a> {
+    pub fn new(track: RawTrackIdentifier<'a>, quality: AudioQuality, opus: bool) -> Self {
+        Self {
+            track,
+            quality,
+            // lossless audio is never transcoded
+            opus: opus && quality != AudioQuality::Lossless,
+        }
+    }
+
+    pub fn copied(&self) -> CacheKey<'_> {
+        CacheKey {
+            track: self.track.copied(),
+            quality: self.quality,
+            opus: self.opus,
+        }
+    }
+
+    pub fn codec(&self) -> &'static str {
+        self.quality.codec(self.opus)
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{}",
+            self.track.disc_id,
+            self.track.track_id,
+            self.quality,
+            self.codec()
+        )
+    }
+
+    /// Keys that can be played instead of this one: itself, then the same track
+    /// at higher qualities.
+    fn candidates(&self) -> impl Iterator<Item = CacheKey<'_>> {
+        AudioQuality::ALL
+            .into_iter()
+            .filter(|quality| *quality >= self.quality)
+            .map(|quality| CacheKey::new(self.track.copied(), quality, self.opus))
+    }
+}
+
+/// Progress of a cache file being written, shared with the sources reading it.
+#[derive(Debug, Clone)]
+pub struct WriteProgress {
+    len: Arc<AtomicUsize>,
+    writing: Arc<AtomicBool>,
+}
+
+impl WriteProgress {
+    fn new(len: usize, writing: bool) -> Self {
+        Self {
+            len: Arc::new(AtomicUsize::new(len)),
+            writing: Arc::new(AtomicBool::new(writing)),
+        }
+    }
+
+    /// Progress of a file which is written completely.
+    pub fn finished(len: usize) -> Self {
+        Self::new(len, false)
+    }
+
+    /// Number of bytes written to the file.
+    pub fn written(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Whether the file is still being written.
+    pub fn is_writing(&self) -> bool {
+        self.writing.load(Ordering::Acquire)
+    }
+}
+
+/// Cache files being written, keyed by their paths.
+type InFlight = Arc<Mutex<HashMap<PathBuf, WriteProgress>>>;
+
+/// Writer of a cache file, which appends to the file and reports its progress.
+///
+/// The file is in flight until the writer is dropped. In-flight files are not truncated
+/// or written by others, and never evicted.
+#[derive(Debug)]
+pub struct CacheWriter {
+    file: File,
+    path: PathBuf,
+    progress: WriteProgress,
+    in_flight: InFlight,
+}
+
+impl CacheWriter {
+    pub fn progress(&self) -> &WriteProgress {
+        &self.progress
+    }
+
+    /// Length of the file, including bytes written before it was opened.
+    pub fn file_len(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    /// Discards the content of the file.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.progress.len.store(0, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl Write for CacheWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.file.write(buf)?;
+        self.progress.len.fetch_add(n, Ordering::AcqRel);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for CacheWriter {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+        self.progress.writing.store(false, Ordering::Release);
+        self.in_flight.lock().unwrap().remove(&self.path);
+    }
+}
+
+/// Cache file returned by [`CacheStore::acquire`].
+pub enum Acquired {
+    /// The cache is complete.
+    Cached(File),
+    /// The cache is being written by another source or a download.
+    /// `reader` can read as far as `progress` goes.
+    Writing {
+        reader: File,
+        progress: WriteProgress,
+    },
+    /// The cache was missing or invalid, and has been cleared to be filled by `writer`.
+    Empty { reader: File, writer: CacheWriter },
+}
+
 #[derive(Debug, Clone)]
 pub struct CacheStore {
     base: PathBuf,
+    /// Maximum size of the cache in bytes. The cache is unbounded if `None`.
+    quota: Option<u64>,
+    /// Albums kept for offline use, which are never evicted.
+    pinned: Arc<RwLock<HashSet<String>>>,
+    /// Files being written by sources and downloads, shared by clones of the store.
+    in_flight: InFlight,
 }
 
 impl CacheStore {
     pub fn new(base: PathBuf) -> Self {
-        Self { base }
+        let pinned: HashSet<String> = File::open(base.join(PINNED_FILE))
+            .ok()
+            .and_then(|f| serde_json::from_reader(f).ok())
+            .unwrap_or_default();
+
+        Self {
+            base,
+            quota: None,
+            pinned: Arc::new(RwLock::new(pinned)),
+            in_flight: Default::default(),
+        }
+    }
+
+    /// Limits the size of the cache to `quota` bytes.
+    ///
+    /// Least recently used entries are evicted when the quota is exceeded.
+    pub fn with_quota(mut self, quota: u64) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    pub fn quota(&self) -> Option<u64> {
+        self.quota
     }
 
-    /// Returns the path to given `track`
-    pub fn loaction_of(&self, track: RawTrackIdentifier) -> PathBuf {
+    /// Returns the path to given `key`
+    pub fn location_of(&self, key: CacheKey) -> PathBuf {
         let mut tmp = self.base.clone();
 
-        tmp.extend([
-            track.album_id.as_ref(),
-            &format!(
-                "{}_{}",
-                track.disc_id.to_string(),
-                track.track_id.to_string(),
-            ),
-        ]);
+        tmp.extend([key.track.album_id.as_ref(), &key.file_name()]);
         tmp
     }
 
-    /// Attempts to open a cache file corresponding to `track` and validates it.
-    ///
-    /// On success, returns a `Result<File, (File, File)>`.
-    /// If the cache exists and is valid, opens it in read mode and returns an `Ok(_)`.
-    /// Otherwise, creates or truncates a cache file, opens it in read mode as a `reader`
-    /// and append mode as a `writer`, and returns an `Err((reader, writer))`
-    ///
-    /// On error, an [`Error`](std::io::Error) is returned.
-    pub fn acquire(&self, track: RawTrackIdentifier) -> io::Result<Result<File, (File, File)>> {
-        let path = self.loaction_of(track.copied());
+    /// Opens the cache file of `key` if it exists and is complete.
+    fn open_valid(&self, key: CacheKey) -> io::Result<Option<File>> {
+        let path = self.location_of(key.copied());
+        if !path.exists() || self.in_flight.lock().unwrap().contains_key(&path) {
+            return Ok(None);
+        }
 
-        if path.exists() {
-            let content_length = self.acquire_info::<u64>(track.copied(), "content-length")?;
-            let f = File::open(&path)?;
+        let content_length = self.acquire_info::<u64>(key.copied(), "content-length")?;
+        let f = File::open(&path)?;
 
-            if content_length == Some(f.metadata()?.len()) || validate_audio(&path).unwrap_or(false)
-            {
-                return Ok(Ok(f));
+        // a partial download is never valid, even if what we have can be decoded
+        let valid = match content_length {
+            Some(len) => len == f.metadata()?.len(),
+            None => validate_audio(&path).unwrap_or(false),
+        };
+
+        Ok(valid.then_some(f))
+    }
+
+    /// Returns whether `key`, or the same track at a higher quality, is completely cached.
+    pub fn is_cached(&self, key: CacheKey) -> io::Result<bool> {
+        for candidate in key.candidates() {
+            if self.open_valid(candidate)?.is_some() {
+                return Ok(true);
             }
+        }
+        Ok(false)
+    }
+
+    /// Opens the cache file of `key` for writing without truncating it,
+    /// so that a partial download can be resumed from its current length.
+    ///
+    /// Returns `Err(progress)` if the file is being written by someone else.
+    pub fn open_partial(&self, key: CacheKey) -> io::Result<Result<CacheWriter, WriteProgress>> {
+        let path = self.location_of(key);
+        create_dir_all(path.parent().unwrap())?; // parent of `path` exists
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(progress) = in_flight.get(&path) {
+            return Ok(Err(progress.clone()));
+        }
+
+        let file = File::options().append(true).create(true).open(&path)?;
+        let progress = WriteProgress::new(file.metadata()?.len() as usize, true);
+        in_flight.insert(path.clone(), progress.clone());
+
+        Ok(Ok(CacheWriter {
+            file,
+            path,
+            progress,
+            in_flight: self.in_flight.clone(),
+        }))
+    }
 
-            log::warn!("cache of {track} exists but is invalid");
+    /// Attempts to open a cache file corresponding to `key` and validates it.
+    /// A valid cache of the same track at a higher quality is accepted as well.
+    ///
+    /// If the cache is being written, e.g. by a download, the partial file is opened
+    /// in read mode to follow the writer. Otherwise, an invalid cache file is truncated
+    /// and returned with a [`CacheWriter`] to fill it.
+    pub fn acquire(&self, key: CacheKey) -> io::Result<Acquired> {
+        for candidate in key.candidates() {
+            if let Some(f) = self.open_valid(candidate.copied())? {
+                // mark as recently used
+                let _ = File::options()
+                    .append(true)
+                    .open(self.location_of(candidate))
+                    .and_then(|f| f.set_modified(SystemTime::now()));
+                return Ok(Acquired::Cached(f));
+            }
         }
 
+        let path = self.location_of(key.copied());
         create_dir_all(path.parent().unwrap())?; // parent of `path` exists
 
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(progress) = in_flight.get(&path) {
+            log::debug!("cache of {} is being written, following it", key.track);
+            let reader = File::open(&path)?;
+            return Ok(Acquired::Writing {
+                reader,
+                progress: progress.clone(),
+            });
+        }
+
+        if path.exists() {
+            log::warn!("cache of {} exists but is invalid", key.track);
+        }
+
         let _ = File::options()
             .write(true)
             .truncate(true)
@@ -77,32 +319,43 @@ impl CacheStore {
             .open(&path)?; // truncate the file first to clear incorrect data
 
         let reader = File::options().read(true).open(&path)?;
-        let writer = File::options().append(true).open(path)?;
+        let progress = WriteProgress::new(0, true);
+        in_flight.insert(path.clone(), progress.clone());
+        let writer = CacheWriter {
+            file: File::options().append(true).open(&path)?,
+            path,
+            progress,
+            in_flight: self.in_flight.clone(),
+        };
 
-        Ok(Err((reader, writer)))
+        Ok(Acquired::Empty { reader, writer })
     }
 
-    pub fn add(&self, path: &Path, track: RawTrackIdentifier) -> io::Result<()> {
-        let location = self.loaction_of(track);
+    pub fn add(&self, path: &Path, key: CacheKey) -> io::Result<()> {
+        let location = self.location_of(key);
 
         if location.exists() {
             Err(ErrorKind::AlreadyExists.into())
         } else if validate_audio(path).unwrap_or(false) {
-            fs::copy(path, location).map(|_| {})
+            create_dir_all(location.parent().unwrap())?;
+            fs::copy(path, location)?;
+            self.evict().map(|_| {})
         } else {
             Err(io::Error::new(ErrorKind::Other, "invalid cache"))
         }
     }
 
-    pub fn store_info<S>(&self, track: RawTrackIdentifier, key: &str, value: S) -> io::Result<()>
+    fn info_location_of(&self, key: CacheKey) -> PathBuf {
+        let mut p = self.location_of(key);
+        p.set_extension("info");
+        p
+    }
+
+    pub fn store_info<S>(&self, cache: CacheKey, key: &str, value: S) -> io::Result<()>
     where
         S: Serialize,
     {
-        let path = {
-            let mut p = self.loaction_of(track.copied());
-            p.set_extension("info");
-            p
-        };
+        let path = self.info_location_of(cache);
 
         let mut info = match File::open(&path) {
             Ok(f) => read_info(&f)?,
@@ -124,14 +377,10 @@ impl CacheStore {
 
     pub fn acquire_info<T: DeserializeOwned>(
         &self,
-        track: RawTrackIdentifier,
+        cache: CacheKey,
         key: &str,
     ) -> io::Result<Option<T>> {
-        let path = {
-            let mut p = self.loaction_of(track);
-            p.set_extension("info");
-            p
-        };
+        let path = self.info_location_of(cache);
 
         match File::open(&path) {
             Ok(f) => Ok(read_info(&f)?
@@ -142,6 +391,141 @@ impl CacheStore {
             Err(e) => return Err(e),
         }
     }
+
+    /// Pins an album for offline use. Cache of pinned albums is never evicted.
+    pub fn pin(&self, album_id: &str) -> io::Result<()> {
+        let mut pinned = self.pinned.write().unwrap();
+        if pinned.insert(album_id.to_owned()) {
+            self.save_pinned(&pinned)?;
+        }
+        Ok(())
+    }
+
+    pub fn unpin(&self, album_id: &str) -> io::Result<()> {
+        let mut pinned = self.pinned.write().unwrap();
+        if pinned.remove(album_id) {
+            self.save_pinned(&pinned)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_pinned(&self, album_id: &str) -> bool {
+        self.pinned.read().unwrap().contains(album_id)
+    }
+
+    pub fn pinned(&self) -> Vec<String> {
+        self.pinned.read().unwrap().iter().cloned().collect()
+    }
+
+    fn save_pinned(&self, pinned: &HashSet<String>) -> io::Result<()> {
+        create_dir_all(&self.base)?;
+        let writer = File::create(self.base.join(PINNED_FILE))?;
+        serde_json::to_writer(writer, pinned)?;
+        Ok(())
+    }
+
+    /// Total size of cached files in bytes.
+    pub fn usage(&self) -> io::Result<u64> {
+        Ok(self.entries()?.iter().map(|e| e.size).sum())
+    }
+
+    /// Evicts least recently used entries of unpinned albums until the cache fits in the quota.
+    /// Files being written are never evicted.
+    ///
+    /// Returns the number of bytes freed.
+    pub fn evict(&self) -> io::Result<u64> {
+        let Some(quota) = self.quota else {
+            return Ok(0);
+        };
+
+        let mut entries = self.entries()?;
+        let mut usage: u64 = entries.iter().map(|e| e.size).sum();
+        if usage <= quota {
+            return Ok(0);
+        }
+
+        entries.sort_by_key(|e| e.modified);
+
+        let mut freed = 0;
+        for entry in entries {
+            if usage <= quota {
+                break;
+            }
+            // hold the lock so that the file is not acquired while being removed
+            let in_flight = self.in_flight.lock().unwrap();
+            if entry.pinned || in_flight.contains_key(&entry.path) {
+                continue;
+            }
+
+            log::debug!("evicting cache {}", entry.path.display());
+            if let Err(e) = fs::remove_file(&entry.path) {
+                log::warn!("failed to evict {}: {e}", entry.path.display());
+                continue;
+            }
+
+            let mut info = entry.path.clone();
+            info.set_extension("info");
+            let _ = fs::remove_file(&info);
+
+            usage = usage.saturating_sub(entry.size);
+            freed += entry.size;
+
+            // remove the album directory if it's empty now
+            let _ = fs::remove_dir(entry.path.parent().unwrap());
+        }
+
+        Ok(freed)
+    }
+
+    /// Lists cached audio files. `size` of an entry includes its info file.
+    fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let albums = match fs::read_dir(&self.base) {
+            Ok(albums) => albums,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let pinned = self.pinned.read().unwrap();
+        let mut entries = Vec::new();
+        for album in albums {
+            let album = album?;
+            if !album.file_type()?.is_dir() {
+                continue;
+            }
+            let is_pinned = pinned.contains(album.file_name().to_string_lossy().as_ref());
+
+            for file in fs::read_dir(album.path())? {
+                let file = file?;
+                let path = file.path();
+                let metadata = file.metadata()?;
+
+                if path.extension().is_some_and(|e| e == "info") {
+                    // counted with the audio file
+                    continue;
+                }
+
+                let mut info = path.clone();
+                info.set_extension("info");
+                let info_size = fs::metadata(&info).map(|m| m.len()).unwrap_or(0);
+
+                entries.push(CacheEntry {
+                    path,
+                    size: metadata.len() + info_size,
+                    modified: metadata.modified()?,
+                    pinned: is_pinned,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    pinned: bool,
 }
 
 fn read_info(f: &File) -> serde_json::Result<HashMap<String, Value>> {
@@ -196,3 +580,156 @@ pub enum ValidationError {
     #[error("Validation is not supported on the source")]
     Unsupported,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, num::NonZeroU8, time::Duration};
+
+    use super::*;
+
+    fn key(album_id: &str, track_id: u8, quality: AudioQuality) -> CacheKey<'_> {
+        CacheKey::new(
+            RawTrackIdentifier::new(
+                album_id,
+                NonZeroU8::new(1).unwrap(),
+                NonZeroU8::new(track_id).unwrap(),
+            ),
+            quality,
+            true,
+        )
+    }
+
+    fn write_entry(store: &CacheStore, key: CacheKey, size: usize, age: u64) {
+        let path = store.location_of(key.copied());
+        create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![0; size]).unwrap();
+        store
+            .store_info(key, "content-length", size as u64)
+            .unwrap();
+
+        File::options()
+            .append(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(age))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_acquire_by_quality() {
+        let base = std::env::temp_dir().join("anni-playback-test-acquire");
+        let _ = fs::remove_dir_all(&base);
+        let store = CacheStore::new(base.clone());
+
+        write_entry(&store, key("album", 1, AudioQuality::High), 16, 0);
+
+        // higher quality cache is accepted
+        assert!(matches!(
+            store.acquire(key("album", 1, AudioQuality::Low)).unwrap(),
+            Acquired::Cached(_)
+        ));
+        // lower quality cache is not
+        assert!(matches!(
+            store
+                .acquire(key("album", 1, AudioQuality::Lossless))
+                .unwrap(),
+            Acquired::Empty { .. }
+        ));
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_evict() {
+        let base = std::env::temp_dir().join("anni-playback-test-evict");
+        let _ = fs::remove_dir_all(&base);
+        let store = CacheStore::new(base.clone()).with_quota(5000);
+
+        store.pin("pinned").unwrap();
+        write_entry(&store, key("pinned", 1, AudioQuality::Low), 2048, 300);
+        write_entry(&store, key("album", 1, AudioQuality::Low), 2048, 200);
+        write_entry(&store, key("album", 2, AudioQuality::Low), 2048, 100);
+        write_entry(&store, key("album", 3, AudioQuality::Low), 2048, 0);
+
+        assert!(store.evict().unwrap() > 0);
+        assert!(store.usage().unwrap() <= 5000);
+
+        // pinned album and recently used entries are kept
+        assert!(store
+            .location_of(key("pinned", 1, AudioQuality::Low))
+            .exists());
+        assert!(store
+            .location_of(key("album", 3, AudioQuality::Low))
+            .exists());
+        assert!(!store
+            .location_of(key("album", 1, AudioQuality::Low))
+            .exists());
+
+        // pinned albums are persisted
+        assert!(CacheStore::new(base.clone()).is_pinned("pinned"));
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_partial() {
+        let base = std::env::temp_dir().join("anni-playback-test-partial");
+        let _ = fs::remove_dir_all(&base);
+        let store = CacheStore::new(base.clone());
+
+        let key = key("album", 1, AudioQuality::Low);
+        let mut partial = store.open_partial(key.copied()).unwrap().unwrap();
+        partial.write_all(&[0; 16]).unwrap();
+        store
+            .store_info(key.copied(), "content-length", 32_u64)
+            .unwrap();
+        drop(partial);
+        assert!(!store.is_cached(key.copied()).unwrap());
+
+        // reopening keeps the downloaded part
+        let mut partial = store.open_partial(key.copied()).unwrap().unwrap();
+        assert_eq!(partial.file_len().unwrap(), 16);
+        partial.write_all(&[0; 16]).unwrap();
+        drop(partial);
+        assert!(store.is_cached(key).unwrap());
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_in_flight() {
+        let base = std::env::temp_dir().join("anni-playback-test-in-flight");
+        let _ = fs::remove_dir_all(&base);
+        let store = CacheStore::new(base.clone()).with_quota(1024);
+
+        write_entry(&store, key("album", 1, AudioQuality::Low), 2048, 100);
+        let track = key("album", 2, AudioQuality::Low);
+        let mut download = store.open_partial(track.copied()).unwrap().unwrap();
+        download.write_all(&[0; 2048]).unwrap();
+
+        // another writer of the same file is refused
+        assert!(store.open_partial(track.copied()).unwrap().is_err());
+
+        // playback follows the download instead of truncating it
+        let Acquired::Writing { progress, .. } = store.acquire(track.copied()).unwrap() else {
+            panic!("in-flight cache should be followed");
+        };
+        assert_eq!(progress.written(), 2048);
+        assert!(progress.is_writing());
+        assert_eq!(download.file_len().unwrap(), 2048);
+
+        // the in-flight file is not evicted, even if it's over the quota
+        store.evict().unwrap();
+        assert!(store.location_of(track.copied()).exists());
+        assert!(!store
+            .location_of(key("album", 1, AudioQuality::Low))
+            .exists());
+
+        drop(download);
+        assert!(!progress.is_writing());
+        store.evict().unwrap();
+        assert!(!store.location_of(track).exists());
+
+        fs::remove_dir_all(base).unwrap();
+    }
+}
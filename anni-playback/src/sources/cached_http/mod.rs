@@ -5,22 +5,18 @@ use std::{
     fs::File,
     hint::spin_loop,
     io::{ErrorKind, Read, Seek, Write},
-    sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc,
-    },
+    sync::{atomic::AtomicBool, Arc},
     thread,
 };
 
 use anni_common::models::TrackIdentifier;
-use anni_provider::providers::TypedPriorityProvider;
-use reqwest::{blocking::Client, Url};
+use reqwest::blocking::Response;
 use thiserror::Error;
 
 use crate::types::MediaSource;
 use provider::{AudioQuality, ProviderProxy};
 
-use cache::CacheStore;
+use cache::{Acquired, CacheKey, CacheStore, WriteProgress};
 
 use super::AnniSource;
 
@@ -29,9 +25,9 @@ const BUF_SIZE: usize = 1024 * 64; // 64k
 pub struct CachedHttpSource {
     identifier: TrackIdentifier,
     cache: File,
-    buf_len: Arc<AtomicUsize>,
+    /// Progress of the cache file, which may be written by this source or a download.
+    progress: WriteProgress,
     pos: usize,
-    is_buffering: Arc<AtomicBool>,
     #[allow(unused)]
     buffer_signal: Arc<AtomicBool>,
     duration: Option<u64>,
@@ -39,60 +35,70 @@ pub struct CachedHttpSource {
 }
 
 impl CachedHttpSource {
-    /// `cache_path` is the path to cache file.
+    /// `response` returns the response to fill the cache with, its duration and content length.
+    /// It is only called if the track is not cached.
     pub fn new(
         identifier: TrackIdentifier,
-        url: impl FnOnce() -> Option<(Url, Option<u64>, Option<u64>)>,
+        quality: AudioQuality,
+        opus: bool,
+        response: impl FnOnce() -> Option<(Response, Option<u64>, Option<u64>)>,
         cache_store: &CacheStore,
-        client: Client,
         buffer_signal: Arc<AtomicBool>,
     ) -> Result<Self, OpenTrackError> {
-        let (reader, writer) = match cache_store.acquire(identifier.inner.copied())? {
-            Ok(cache) => {
+        let key = CacheKey::new(identifier.inner.copied(), quality, opus);
+        let (reader, writer) = match cache_store.acquire(key.copied())? {
+            Acquired::Cached(cache) => {
                 let buf_len = cache.metadata()?.len() as usize;
 
                 return Ok(Self {
                     identifier,
                     cache,
-                    buf_len: Arc::new(AtomicUsize::new(buf_len)),
+                    progress: WriteProgress::finished(buf_len),
                     pos: 0,
-                    is_buffering: Arc::new(AtomicBool::new(false)),
                     buffer_signal,
                     duration: None,
                     content_length: Some(buf_len as u64),
                 });
             }
-            Err(cache) => cache,
+            Acquired::Writing { reader, progress } => {
+                // a download of the same track is in progress, read along with it
+                let content_length = cache_store.acquire_info(key, "content-length")?;
+
+                return Ok(Self {
+                    identifier,
+                    cache: reader,
+                    progress,
+                    pos: 0,
+                    buffer_signal,
+                    duration: None,
+                    content_length,
+                });
+            }
+            Acquired::Empty { reader, writer } => (reader, writer),
         };
 
-        let buf_len = Arc::new(AtomicUsize::new(0));
-        let is_buffering = Arc::new(AtomicBool::new(true));
+        let progress = writer.progress().clone();
 
-        let (url, duration, content_length) = url().ok_or(OpenTrackError::NoAvailableAnnil)?;
+        let (mut response, duration, content_length) =
+            response().ok_or(OpenTrackError::NoAvailableAnnil)?;
 
         log::debug!("got duration {duration:?}");
 
         thread::spawn({
             let mut cache = writer;
-            let buf_len = Arc::clone(&buf_len);
             let mut buf = [0; BUF_SIZE];
-            let is_buffering = Arc::clone(&is_buffering);
             let identifier = identifier.clone();
+            let cache_store = cache_store.clone();
 
             move || {
-                let mut response = match client.get(url).send() {
-                    Ok(r) => r,
-                    Err(e) => {
-                        log::error!("failed to send request: {e}");
-                        is_buffering.store(false, Ordering::Release);
-                        return;
-                    }
-                };
-
                 loop {
                     match response.read(&mut buf) {
                         Ok(0) => {
                             log::info!("{identifier} reached eof");
+                            // the file is still in flight, so it's kept
+                            if let Err(e) = cache_store.evict() {
+                                log::warn!("failed to evict cache: {e}");
+                            }
                             break;
                         }
                         Ok(n) => {
@@ -102,7 +108,6 @@ impl CachedHttpSource {
                             }
 
                             let _ = cache.flush();
-                            buf_len.fetch_add(n, Ordering::AcqRel);
 
                             log::trace!("wrote {n} bytes to {identifier}");
                         }
@@ -114,16 +119,16 @@ impl CachedHttpSource {
                     }
                 }
 
-                is_buffering.store(false, Ordering::Release);
+                // marks the file as written
+                drop(cache);
             }
         });
 
         Ok(Self {
             identifier,
             cache: reader,
-            buf_len,
+            progress,
             pos: 0,
-            is_buffering,
             buffer_signal,
             duration,
             content_length,
@@ -135,8 +140,8 @@ impl Read for CachedHttpSource {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         // A naive spin loop that waits until we have more data to read.
         loop {
-            let is_buffering = self.is_buffering.load(Ordering::Acquire);
-            let buf_len = self.buf_len.load(Ordering::Acquire);
+            let is_buffering = self.progress.is_writing();
+            let buf_len = self.progress.written();
             let has_buf = buf_len > self.pos;
 
             if has_buf {
@@ -167,7 +172,7 @@ impl Seek for CachedHttpSource {
 
 impl MediaSource for CachedHttpSource {
     fn is_seekable(&self) -> bool {
-        !self.is_buffering.load(Ordering::Acquire)
+        !self.progress.is_writing()
     }
 
     fn byte_len(&self) -> Option<u64> {
@@ -180,45 +185,58 @@ impl AnniSource for CachedHttpSource {
     fn duration_hint(&self) -> Option<u64> {
         self.duration
     }
+
+    fn track(&self) -> Option<&TrackIdentifier> {
+        Some(&self.identifier)
+    }
 }
 
 pub struct CachedAnnilSource(CachedHttpSource);
 
 impl CachedAnnilSource {
-    pub fn new(
+    /// Opens `track` from cache, or from the first available provider of `providers`.
+    pub fn new<'a>(
         track: TrackIdentifier,
         quality: AudioQuality,
         cache_store: &CacheStore,
-        client: Client,
-        provider: &TypedPriorityProvider<ProviderProxy>,
+        providers: impl IntoIterator<Item = &'a ProviderProxy>,
         buffer_signal: Arc<AtomicBool>,
         opus: bool,
     ) -> Result<Self, OpenTrackError> {
         let cloned_track = track.clone();
 
-        let mut source = provider
-            .providers()
+        let mut source = providers
+            .into_iter()
             .filter_map(|p| {
-                p.head(cloned_track.inner.copied(), quality, opus)
+                p.get(cloned_track.inner.copied(), quality, opus)
                     .and_then(|r| r.error_for_status())
                     .inspect_err(|e| log::warn!("{e}"))
                     .ok()
             })
             .map(|r| {
-                let (url, headers) = (r.url(), r.headers());
+                let headers = r.headers();
                 let parse_header = |key| headers.get(key).and_then(|v| v.to_str().ok());
                 let duration = parse_header("X-Duration-Seconds").and_then(|v| v.parse().ok());
                 if let Some(content_length) = r.content_length() {
                     let _ = cache_store.store_info(
-                        cloned_track.inner.copied(),
+                        CacheKey::new(cloned_track.inner.copied(), quality, opus),
                         "content-length",
                         content_length,
                     );
                 }
-                (url.clone(), duration, r.content_length())
+                let content_length = r.content_length();
+                (r, duration, content_length)
             });
 
-        CachedHttpSource::new(track, || source.next(), cache_store, client, buffer_signal).map(Self)
+        CachedHttpSource::new(
+            track,
+            quality,
+            opus,
+            || source.next(),
+            cache_store,
+            buffer_signal,
+        )
+        .map(Self)
     }
 }
 
@@ -248,6 +266,10 @@ impl AnniSource for CachedAnnilSource {
     fn duration_hint(&self) -> Option<u64> {
         self.0.duration
     }
+
+    fn track(&self) -> Option<&TrackIdentifier> {
+        self.0.track()
+    }
 }
 
 #[derive(Debug, Error)]
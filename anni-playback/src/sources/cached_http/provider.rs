@@ -1,10 +1,17 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    sync::{Arc, RwLock},
+};
 
-use reqwest::blocking::{Client, Response};
+use reqwest::{
+    blocking::{Client, RequestBuilder, Response},
+    header::{AUTHORIZATION, RANGE},
+    StatusCode,
+};
 
 use anni_common::models::RawTrackIdentifier;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AudioQuality {
     Low,
     Medium,
@@ -12,6 +19,25 @@ pub enum AudioQuality {
     Lossless,
 }
 
+impl AudioQuality {
+    /// All qualities, from the lowest to the highest.
+    pub const ALL: [AudioQuality; 4] = [
+        AudioQuality::Low,
+        AudioQuality::Medium,
+        AudioQuality::High,
+        AudioQuality::Lossless,
+    ];
+
+    /// Codec of the audio served by annil at this quality.
+    pub fn codec(&self, opus: bool) -> &'static str {
+        match (self, opus) {
+            (AudioQuality::Lossless, _) => "flac",
+            (_, true) => "opus",
+            (_, false) => "aac",
+        }
+    }
+}
+
 impl Display for AudioQuality {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -23,15 +49,37 @@ impl Display for AudioQuality {
     }
 }
 
+/// Returns a new token for a provider after the current one was rejected,
+/// or `None` if it can not be refreshed.
+pub type TokenProvider = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+#[derive(Clone)]
 pub struct ProviderProxy {
     url: String,
     client: Client,
-    auth: String,
+    /// Shared between clones, so a refreshed token is used by all of them.
+    auth: Arc<RwLock<String>>,
+    token_provider: Option<TokenProvider>,
 }
 
 impl ProviderProxy {
     pub fn new(url: String, auth: String, client: Client) -> Self {
-        Self { url, auth, client }
+        Self {
+            url,
+            client,
+            auth: Arc::new(RwLock::new(auth)),
+            token_provider: None,
+        }
+    }
+
+    /// Sets a callback to refresh the token when annil responds with `401 Unauthorized`.
+    pub fn with_token_provider(mut self, token_provider: TokenProvider) -> Self {
+        self.token_provider = Some(token_provider);
+        self
+    }
+
+    pub fn set_token(&self, auth: String) {
+        *self.auth.write().unwrap() = auth;
     }
 
     pub fn format_url(
@@ -40,10 +88,27 @@ impl ProviderProxy {
         quality: AudioQuality,
         opus: bool,
     ) -> String {
-        format!(
-            "{}/{}?auth={}&quality={}&opus={}",
-            self.url, track, self.auth, quality, opus
-        )
+        format!("{}/{}?quality={}&opus={}", self.url, track, quality, opus)
+    }
+
+    /// Sends the request built by `request` with the token in the `Authorization` header.
+    ///
+    /// If the token is rejected and a new one is available from the token provider,
+    /// the request is retried once with the new token.
+    fn send(&self, request: impl Fn() -> RequestBuilder) -> reqwest::Result<Response> {
+        let auth = self.auth.read().unwrap().clone();
+        let response = request().header(AUTHORIZATION, &auth).send()?;
+
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let Some(token) = self.token_provider.as_ref().and_then(|provider| provider()) else {
+            return Ok(response);
+        };
+
+        log::info!("token rejected by {}, retrying with a new one", self.url);
+        self.set_token(token.clone());
+        request().header(AUTHORIZATION, token).send()
     }
 
     pub fn get(
@@ -52,9 +117,30 @@ impl ProviderProxy {
         quality: AudioQuality,
         opus: bool,
     ) -> reqwest::Result<Response> {
-        self.client
-            .get(self.format_url(track, quality, opus))
-            .send()
+        let url = self.format_url(track, quality, opus);
+        self.send(|| self.client.get(&url))
+    }
+
+    /// Requests the audio starting from byte `offset`.
+    ///
+    /// The server may ignore the range and respond with the whole file,
+    /// so check for `206 Partial Content` before appending the body.
+    pub fn get_range(
+        &self,
+        track: RawTrackIdentifier,
+        quality: AudioQuality,
+        opus: bool,
+        offset: u64,
+    ) -> reqwest::Result<Response> {
+        let url = self.format_url(track, quality, opus);
+        self.send(|| {
+            let request = self.client.get(&url);
+            if offset > 0 {
+                request.header(RANGE, format!("bytes={offset}-"))
+            } else {
+                request
+            }
+        })
     }
 
     pub fn head(
@@ -63,9 +149,7 @@ impl ProviderProxy {
         quality: AudioQuality,
         opus: bool,
     ) -> reqwest::Result<Response> {
-        self.client
-            .get(self.format_url(track, quality, opus))
-            // .header("Authorization", &self.auth)
-            .send()
+        let url = self.format_url(track, quality, opus);
+        self.send(|| self.client.head(&url))
     }
 }
@@ -14,10 +14,12 @@
 // You should have received a copy of the GNU Lesser General Public License along with this program.
 // If not, see <https://www.gnu.org/licenses/>.
 
+use anni_common::models::TrackIdentifier;
 use symphonia_core::io::MediaSource;
 
 pub mod cached_http;
 pub mod http;
+pub mod provider;
 pub mod streamable;
 
 /// A type that holds an ID and a `std::sync::mpsc::Receiver`.
@@ -32,6 +34,12 @@ pub trait AnniSource: MediaSource + IntoBoxedMediaSource {
     fn duration_hint(&self) -> Option<u64> {
         None
     }
+
+    /// The track played by this source, if known.
+    /// Used to detect consecutive tracks of an album, which are played without crossfade.
+    fn track(&self) -> Option<&TrackIdentifier> {
+        None
+    }
 }
 
 impl MediaSource for Box<dyn AnniSource> {
@@ -0,0 +1,191 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    sync::{Arc, Mutex},
+};
+
+use anni_common::models::TrackIdentifier;
+use anni_provider::{AnniProvider, AudioInfo, ProviderError, Range, ResourceReader};
+use once_cell::sync::Lazy;
+use symphonia_core::io::MediaSource;
+use tokio::{io::AsyncReadExt, runtime::Runtime};
+
+use super::{cached_http::provider::ProviderProxy, AnniSource};
+
+/// Runtime driving the async providers, shared by all [`ProviderSource`]s.
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("anni-playback-provider")
+        .enable_all()
+        .build()
+        .expect("failed to create runtime for providers")
+});
+
+/// A provider in the priority list of [`AnniPlayer`](crate::player::AnniPlayer).
+#[derive(Clone)]
+pub enum PlaybackProvider {
+    /// An annil server accessed over HTTP. Audio is cached in the `CacheStore`.
+    Remote(ProviderProxy),
+    /// Any [`AnniProvider`], e.g. a local workspace. Audio is read directly without caching.
+    Local(Arc<dyn AnniProvider + Send + Sync>),
+}
+
+impl PlaybackProvider {
+    pub fn as_remote(&self) -> Option<&ProviderProxy> {
+        match self {
+            PlaybackProvider::Remote(remote) => Some(remote),
+            PlaybackProvider::Local(_) => None,
+        }
+    }
+}
+
+impl From<ProviderProxy> for PlaybackProvider {
+    fn from(value: ProviderProxy) -> Self {
+        PlaybackProvider::Remote(value)
+    }
+}
+
+/// Reads a track from an [`AnniProvider`].
+///
+/// Audio is pulled with [`AnniProvider::get_audio`] on a background runtime, and the
+/// decoder thread blocks until the data is ready. Seeking reopens the audio from the
+/// new position with a range request.
+pub struct ProviderSource {
+    provider: Arc<dyn AnniProvider + Send + Sync>,
+    track: TrackIdentifier,
+    info: AudioInfo,
+    /// Reader from `pos`, or `None` if it should be reopened after seeking.
+    reader: Mutex<Option<ResourceReader>>,
+    pos: u64,
+}
+
+impl ProviderSource {
+    /// Opens `track` from `provider`.
+    ///
+    /// Must not be called in an async context, as it blocks until the audio is available.
+    pub fn new(
+        provider: Arc<dyn AnniProvider + Send + Sync>,
+        track: TrackIdentifier,
+    ) -> Result<Self, ProviderError> {
+        let audio = RUNTIME.block_on(provider.get_audio(
+            &track.inner.album_id,
+            track.inner.disc_id,
+            track.inner.track_id,
+            Range::FULL,
+        ))?;
+
+        Ok(Self {
+            provider,
+            track,
+            info: audio.info,
+            reader: Mutex::new(Some(audio.reader)),
+            pos: 0,
+        })
+    }
+
+    pub fn info(&self) -> &AudioInfo {
+        &self.info
+    }
+
+    /// Opens the audio from `pos` with a range request.
+    ///
+    /// Providers may ignore the range and return the audio from an earlier position, in which
+    /// case the bytes before `pos` are skipped.
+    fn open_at(&self, pos: u64) -> io::Result<ResourceReader> {
+        let audio = RUNTIME
+            .block_on(self.provider.get_audio(
+                &self.track.inner.album_id,
+                self.track.inner.disc_id,
+                self.track.inner.track_id,
+                Range::new(pos, None),
+            ))
+            .map_err(io::Error::other)?;
+
+        let start = audio.range.start;
+        if start > pos {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("requested audio from {pos}, but provider returned from {start}"),
+            ));
+        }
+
+        let mut reader = audio.reader;
+        if start < pos {
+            log::debug!(
+                "range of {} is not supported, skipping to {pos}",
+                self.track
+            );
+
+            let skip = pos - start;
+            let skipped = RUNTIME.block_on(tokio::io::copy(
+                &mut (&mut reader).take(skip),
+                &mut tokio::io::sink(),
+            ))?;
+            if skipped != skip {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+
+        Ok(reader)
+    }
+}
+
+impl Read for ProviderSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.info.size as u64 {
+            return Ok(0);
+        }
+
+        if self.reader.get_mut().unwrap().is_none() {
+            log::debug!("reopening {} from {}", self.track, self.pos);
+
+            let reader = self.open_at(self.pos)?;
+            *self.reader.get_mut().unwrap() = Some(reader);
+        }
+        let reader = self.reader.get_mut().unwrap().as_mut().unwrap();
+
+        let n = RUNTIME.block_on(reader.read(buf))?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for ProviderSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => (self.info.size as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+
+        if pos != self.pos {
+            // the reader only moves forward, reopen it on the next read
+            *self.reader.get_mut().unwrap() = None;
+            self.pos = pos;
+        }
+
+        Ok(pos)
+    }
+}
+
+impl MediaSource for ProviderSource {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.info.size as u64)
+    }
+}
+
+impl AnniSource for ProviderSource {
+    fn duration_hint(&self) -> Option<u64> {
+        // `AudioInfo::duration` is in milliseconds
+        Some(self.info.duration / 1000)
+    }
+
+    fn track(&self) -> Option<&TrackIdentifier> {
+        Some(&self.track)
+    }
+}
@@ -0,0 +1,162 @@
+#![cfg(all(feature = "mpris", target_os = "linux"))]
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc::Receiver,
+};
+
+use anni_playback::{
+    mpris::{MprisOptions, MprisServer, TrackMetadata},
+    types::{PlayerEvent, ProgressState},
+    Controls,
+};
+use zbus::{
+    blocking::{connection, proxy, Proxy},
+    proxy::CacheProperties,
+    zvariant::OwnedValue,
+};
+
+/// A private session bus started with `dbus-daemon --session`.
+///
+/// Tests using it are ignored by default, run them with `cargo test --features mpris -- --ignored`.
+struct SessionBus {
+    daemon: Child,
+    address: String,
+}
+
+impl SessionBus {
+    fn start() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start dbus-daemon");
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.as_mut().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        Self {
+            daemon,
+            address: address.trim().to_owned(),
+        }
+    }
+}
+
+impl Drop for SessionBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+fn setup(bus: &SessionBus) -> (Controls, Receiver<PlayerEvent>, MprisServer, Proxy<'static>) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let controls = Controls::new(sender);
+    let server = MprisServer::new(
+        controls.clone(),
+        MprisOptions {
+            name: "anni_test".to_owned(),
+            identity: "Anni".to_owned(),
+            address: Some(bus.address.clone()),
+        },
+    )
+    .unwrap();
+
+    let client = connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .unwrap();
+    let proxy = proxy::Builder::new(&client)
+        .destination("org.mpris.MediaPlayer2.anni_test")
+        .unwrap()
+        .path("/org/mpris/MediaPlayer2")
+        .unwrap()
+        .interface("org.mpris.MediaPlayer2.Player")
+        .unwrap()
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap();
+
+    (controls, receiver, server, proxy)
+}
+
+#[test]
+#[ignore = "requires dbus-daemon"]
+fn test_mpris_controls() {
+    let bus = SessionBus::start();
+    let (controls, receiver, server, proxy) = setup(&bus);
+
+    let status: String = proxy.get_property("PlaybackStatus").unwrap();
+    assert_eq!(status, "Stopped");
+
+    proxy.call_method("Play", &()).unwrap();
+    assert!(controls.is_playing());
+    let event = receiver.recv().unwrap();
+    assert!(matches!(event, PlayerEvent::Play));
+    server.handle_event(&event).unwrap();
+
+    let status: String = proxy.get_property("PlaybackStatus").unwrap();
+    assert_eq!(status, "Playing");
+
+    proxy.call_method("PlayPause", &()).unwrap();
+    assert!(!controls.is_playing());
+    let status: String = proxy.get_property("PlaybackStatus").unwrap();
+    assert_eq!(status, "Paused");
+
+    proxy.set_property("Volume", 0.5_f64).unwrap();
+    assert_eq!(*controls.volume(), 0.5);
+
+    proxy.set_property("LoopStatus", "Track").unwrap();
+    assert!(controls.is_looping());
+
+    // Seek forward by 5 seconds
+    proxy.call_method("Seek", &(5_000_000_i64,)).unwrap();
+    assert_eq!(controls.progress().position, 5000);
+    let position: i64 = proxy.get_property("Position").unwrap();
+    assert_eq!(position, 5_000_000);
+}
+
+#[test]
+#[ignore = "requires dbus-daemon"]
+fn test_mpris_metadata() {
+    let bus = SessionBus::start();
+    let (_controls, _receiver, server, proxy) = setup(&bus);
+
+    let can_play: bool = proxy.get_property("CanPlay").unwrap();
+    assert!(!can_play);
+
+    server
+        .set_metadata(Some(TrackMetadata {
+            track_id: "d6b6ca50-4d19-4c4e-9b4c-2e3b4a3c1f2d/1/1".to_owned(),
+            title: Some("Title".to_owned()),
+            artists: vec!["Artist".to_owned()],
+            album: Some("Album".to_owned()),
+            track_number: Some(1),
+            ..Default::default()
+        }))
+        .unwrap();
+    server
+        .handle_event(&PlayerEvent::Progress(ProgressState {
+            position: 0,
+            duration: 180_000,
+        }))
+        .unwrap();
+
+    let metadata: HashMap<String, OwnedValue> = proxy.get_property("Metadata").unwrap();
+    assert_eq!(
+        String::try_from(metadata["xesam:title"].try_clone().unwrap()).unwrap(),
+        "Title"
+    );
+    assert_eq!(
+        i64::try_from(metadata["mpris:length"].try_clone().unwrap()).unwrap(),
+        180_000_000
+    );
+    assert!(metadata.contains_key("mpris:trackid"));
+
+    let can_play: bool = proxy.get_property("CanPlay").unwrap();
+    assert!(can_play);
+}
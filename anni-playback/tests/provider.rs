@@ -0,0 +1,150 @@
+use std::{
+    borrow::Cow,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    num::NonZeroU8,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+};
+
+use anni_common::models::RawTrackIdentifier;
+use anni_playback::sources::cached_http::provider::{AudioQuality, ProviderProxy};
+use reqwest::{blocking::Client, StatusCode};
+
+const TOKEN: &str = "refreshed-token";
+
+struct Request {
+    path: String,
+    authorization: Option<String>,
+    range: Option<String>,
+}
+
+/// Starts a mock annil responding `401 Unauthorized` unless [`TOKEN`] is sent.
+fn mock_server() -> (String, Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap().to_owned();
+
+            let mut authorization = None;
+            let mut range = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let Some((key, value)) = line.trim_end().split_once(": ") else {
+                    break;
+                };
+                match key.to_ascii_lowercase().as_str() {
+                    "authorization" => authorization = Some(value.to_owned()),
+                    "range" => range = Some(value.to_owned()),
+                    _ => {}
+                }
+            }
+
+            let status = if authorization.as_deref() == Some(TOKEN) {
+                "200 OK"
+            } else {
+                "401 Unauthorized"
+            };
+            let _ = sender.send(Request {
+                path,
+                authorization,
+                range,
+            });
+
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+            )
+            .unwrap();
+        }
+    });
+
+    (url, receiver)
+}
+
+fn track() -> RawTrackIdentifier<'static> {
+    RawTrackIdentifier {
+        album_id: Cow::Borrowed("d6b6ca50-4d19-4c4e-9b4c-2e3b4a3c1f2d"),
+        disc_id: NonZeroU8::new(1).unwrap(),
+        track_id: NonZeroU8::new(2).unwrap(),
+    }
+}
+
+#[test]
+fn test_refresh_token() {
+    let (url, requests) = mock_server();
+    let refreshed = Arc::new(AtomicUsize::new(0));
+    let provider = ProviderProxy::new(url, "expired-token".to_owned(), Client::new())
+        .with_token_provider({
+            let refreshed = refreshed.clone();
+            Arc::new(move || {
+                refreshed.fetch_add(1, Ordering::SeqCst);
+                Some(TOKEN.to_owned())
+            })
+        });
+
+    let response = provider
+        .get_range(track(), AudioQuality::Low, true, 1024)
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+
+    // rejected request with the old token
+    let request = requests.recv().unwrap();
+    assert_eq!(
+        request.path,
+        "/d6b6ca50-4d19-4c4e-9b4c-2e3b4a3c1f2d/1/2?quality=low&opus=true"
+    );
+    assert_eq!(request.authorization.as_deref(), Some("expired-token"));
+    assert_eq!(request.range.as_deref(), Some("bytes=1024-"));
+
+    // retried request with the refreshed token
+    let request = requests.recv().unwrap();
+    assert_eq!(
+        request.path,
+        "/d6b6ca50-4d19-4c4e-9b4c-2e3b4a3c1f2d/1/2?quality=low&opus=true"
+    );
+    assert_eq!(request.authorization.as_deref(), Some(TOKEN));
+    assert_eq!(request.range.as_deref(), Some("bytes=1024-"));
+
+    // the refreshed token is kept, also by clones
+    let response = provider
+        .clone()
+        .head(track(), AudioQuality::Low, true)
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+    let request = requests.recv().unwrap();
+    assert_eq!(request.authorization.as_deref(), Some(TOKEN));
+    assert!(requests.try_recv().is_err());
+}
+
+#[test]
+fn test_refresh_token_unavailable() {
+    let (url, requests) = mock_server();
+    let provider = ProviderProxy::new(url, "expired-token".to_owned(), Client::new())
+        .with_token_provider(Arc::new(|| None));
+
+    let response = provider
+        .get(track(), AudioQuality::Lossless, false)
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let request = requests.recv().unwrap();
+    assert_eq!(request.authorization.as_deref(), Some("expired-token"));
+    assert_eq!(request.range, None);
+    assert!(requests.try_recv().is_err());
+}
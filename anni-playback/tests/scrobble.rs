@@ -0,0 +1,178 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use anni_playback::{
+    scrobble::{
+        Listen, ListenBrainz, ListenEvent, ListenOptions, ListenTrack, ListenTracker, ScrobbleQueue,
+    },
+    types::{PlayerEvent, ProgressState},
+};
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+struct Request {
+    path: String,
+    authorization: Option<String>,
+    body: Value,
+}
+
+/// Starts a mock server responding `status` to every request.
+fn mock_server(status: u16) -> (String, Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap().to_owned();
+
+            let mut authorization = None;
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let Some((key, value)) = line.trim_end().split_once(": ") else {
+                    break;
+                };
+                match key.to_ascii_lowercase().as_str() {
+                    "authorization" => authorization = Some(value.to_owned()),
+                    "content-length" => content_length = value.parse().unwrap(),
+                    _ => {}
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let _ = sender.send(Request {
+                path,
+                authorization,
+                body: serde_json::from_slice(&body).unwrap(),
+            });
+
+            write!(
+                stream,
+                "HTTP/1.1 {status} Mock\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+            )
+            .unwrap();
+        }
+    });
+
+    (url, receiver)
+}
+
+/// An url that refuses connections.
+fn offline_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+fn track(title: &str) -> ListenTrack {
+    ListenTrack {
+        track_id: "d6b6ca50-4d19-4c4e-9b4c-2e3b4a3c1f2d/1/1".to_owned(),
+        title: title.to_owned(),
+        artist: "Artist".to_owned(),
+        album: Some("Album".to_owned()),
+        track_number: Some(1),
+    }
+}
+
+fn progress(position: u64) -> PlayerEvent {
+    PlayerEvent::Progress(ProgressState {
+        position,
+        duration: 180_000,
+    })
+}
+
+#[test]
+fn test_listen_tracker() {
+    let (mut tracker, events) = ListenTracker::new(ListenOptions::default());
+
+    tracker.start(track("Completed"));
+    assert!(matches!(events.try_recv(), Ok(ListenEvent::Started(_))));
+    for position in (0..=100_000).step_by(500) {
+        tracker.handle_event(&progress(position));
+    }
+    tracker.handle_event(&PlayerEvent::PreloadPlayed);
+
+    let Ok(ListenEvent::Completed(listen)) = events.try_recv() else {
+        panic!("expected a completed listen");
+    };
+    assert_eq!(listen.listened, 100_000);
+    assert_eq!(listen.duration, 180_000);
+
+    // seeking to the end does not count as listening
+    tracker.start(track("Skipped"));
+    tracker.handle_event(&progress(0));
+    tracker.handle_event(&progress(1000));
+    tracker.handle_event(&progress(170_000));
+    tracker.handle_event(&progress(171_000));
+    tracker.handle_event(&PlayerEvent::Stop);
+
+    assert!(matches!(events.try_recv(), Ok(ListenEvent::Started(_))));
+    let Ok(ListenEvent::Skipped(listen)) = events.try_recv() else {
+        panic!("expected a skipped listen");
+    };
+    assert_eq!(listen.listened, 2000);
+}
+
+#[test]
+fn test_listenbrainz_queue() {
+    let path = std::env::temp_dir().join("anni-playback-test-scrobble.json");
+    let _ = std::fs::remove_file(&path);
+
+    let listen = Listen {
+        track: track("Title"),
+        listened_at: 1_700_000_000,
+        listened: 120_000,
+        duration: 180_000,
+    };
+
+    // listens are kept while offline
+    let scrobbler = ListenBrainz::new("token".to_owned(), Client::new()).with_url(offline_url());
+    let mut queue = ScrobbleQueue::new(scrobbler, path.clone()).unwrap();
+    assert!(queue.push(listen.clone()).is_err());
+    assert_eq!(queue.pending(), std::slice::from_ref(&listen));
+    drop(queue);
+
+    // and submitted in the next session
+    let (url, requests) = mock_server(200);
+    let scrobbler = ListenBrainz::new("token".to_owned(), Client::new()).with_url(url);
+    let mut queue = ScrobbleQueue::new(scrobbler, path.clone()).unwrap();
+    assert_eq!(queue.pending().len(), 1);
+    queue.flush().unwrap();
+    assert!(queue.pending().is_empty());
+
+    let request = requests.recv().unwrap();
+    assert_eq!(request.path, "/1/submit-listens");
+    assert_eq!(request.authorization.as_deref(), Some("Token token"));
+    assert_eq!(request.body["listen_type"], "single");
+    let payload = &request.body["payload"][0];
+    assert_eq!(payload["listened_at"], 1_700_000_000);
+    assert_eq!(payload["track_metadata"]["track_name"], "Title");
+    assert_eq!(payload["track_metadata"]["release_name"], "Album");
+    assert_eq!(
+        payload["track_metadata"]["additional_info"]["duration_ms"],
+        180_000
+    );
+
+    // now playing is not queued
+    queue.handle_event(&ListenEvent::Started(track("Next")));
+    let request = requests.recv().unwrap();
+    assert_eq!(request.body["listen_type"], "playing_now");
+    assert!(request.body["payload"][0].get("listened_at").is_none());
+    assert!(queue.pending().is_empty());
+
+    // submitted listens are removed from disk
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+
+    std::fs::remove_file(path).unwrap();
+}
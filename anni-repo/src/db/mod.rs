@@ -1,6 +1,6 @@
 mod rows;
 
-pub const DB_VERSION: &str = "1.1";
+pub const DB_VERSION: &str = "1.5";
 
 #[cfg(feature = "db-read")]
 mod read;
@@ -8,6 +8,12 @@ mod read;
 #[cfg(feature = "db-read")]
 pub use read::RepoDatabaseRead;
 
+#[cfg(feature = "db-read")]
+mod query;
+
+#[cfg(feature = "db-read")]
+pub use query::{AlbumOrder, AlbumQuery};
+
 #[cfg(feature = "db-write")]
 mod write;
 
@@ -0,0 +1,229 @@
+use crate::db::{rows, RepoDatabaseRead};
+use crate::prelude::RepoResult;
+use anni_metadata::model::{AnniDate, ArtistRole, TrackType};
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use serde::Deserialize;
+
+/// Column used to sort albums in an [`AlbumQuery`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlbumOrder {
+    #[default]
+    ReleaseDate,
+    Title,
+    Catalog,
+}
+
+impl AlbumOrder {
+    fn column(&self) -> &'static str {
+        match self {
+            AlbumOrder::ReleaseDate => "release_date",
+            AlbumOrder::Title => "title",
+            AlbumOrder::Catalog => "catalog",
+        }
+    }
+}
+
+/// A typed query over albums in [`RepoDatabaseRead`].
+///
+/// All filters are optional and combined with `AND`.
+#[derive(Debug, Clone, Default)]
+pub struct AlbumQuery {
+    artist: Option<String>,
+    credit: Option<(Option<ArtistRole>, String)>,
+    released_after: Option<AnniDate>,
+    released_before: Option<AnniDate>,
+    track_type: Option<TrackType>,
+    tag: Option<(String, bool)>,
+    catalog_prefix: Option<String>,
+    order: AlbumOrder,
+    descending: bool,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+impl AlbumQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Albums whose artist, or artist of any track, contains `artist`.
+    pub fn artist(mut self, artist: impl Into<String>) -> Self {
+        self.artist = Some(artist.into());
+        self
+    }
+
+    /// Albums crediting an artist whose name contains `name`, on the album itself or any of its discs or tracks.
+    ///
+    /// If `role` is given, only credits of that role are matched.
+    pub fn credit(mut self, role: Option<ArtistRole>, name: impl Into<String>) -> Self {
+        self.credit = Some((role, name.into()));
+        self
+    }
+
+    /// Albums released on or after `date`.
+    pub fn released_after(mut self, date: AnniDate) -> Self {
+        self.released_after = Some(date);
+        self
+    }
+
+    /// Albums released before `date`.
+    ///
+    /// Partial dates are compared as prefixes, so `2020` excludes albums released in `2020-05`.
+    pub fn released_before(mut self, date: AnniDate) -> Self {
+        self.released_before = Some(date);
+        self
+    }
+
+    /// Albums with at least one track of `track_type`.
+    pub fn track_type(mut self, track_type: TrackType) -> Self {
+        self.track_type = Some(track_type);
+        self
+    }
+
+    /// Albums tagged by tag named `tag`, on the album itself or any of its discs or tracks.
+    ///
+    /// If `recursive` is true, albums tagged by children of the tag are included.
+    pub fn tag(mut self, tag: impl Into<String>, recursive: bool) -> Self {
+        self.tag = Some((tag.into(), recursive));
+        self
+    }
+
+    /// Albums whose catalog starts with `prefix`.
+    pub fn catalog_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.catalog_prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn order_by(mut self, order: AlbumOrder, descending: bool) -> Self {
+        self.order = order;
+        self.descending = descending;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Build `WHERE` clause and its parameters.
+    fn filter(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        if let Some(artist) = &self.artist {
+            conditions.push(
+                r"(artist LIKE ? ESCAPE '\' OR album_id IN (SELECT album_id FROM repo_track WHERE artist LIKE ? ESCAPE '\'))",
+            );
+            let pattern = format!("%{}%", escape_like(artist));
+            params.push(Value::Text(pattern.clone()));
+            params.push(Value::Text(pattern));
+        }
+        if let Some((role, name)) = &self.credit {
+            match role {
+                Some(role) => {
+                    conditions.push(
+                        r"album_id IN (SELECT album_id FROM repo_artist_credit WHERE role = ? AND name LIKE ? ESCAPE '\')",
+                    );
+                    params.push(Value::Text(role.to_string()));
+                }
+                None => conditions.push(
+                    r"album_id IN (SELECT album_id FROM repo_artist_credit WHERE name LIKE ? ESCAPE '\')",
+                ),
+            }
+            params.push(Value::Text(format!("%{}%", escape_like(name))));
+        }
+        if let Some(date) = &self.released_after {
+            conditions.push("release_date >= ?");
+            params.push(Value::Text(date.to_string()));
+        }
+        if let Some(date) = &self.released_before {
+            conditions.push("release_date < ?");
+            params.push(Value::Text(date.to_string()));
+        }
+        if let Some(track_type) = &self.track_type {
+            conditions.push("album_id IN (SELECT album_id FROM repo_track WHERE track_type = ?)");
+            params.push(Value::Text(track_type.as_ref().to_string()));
+        }
+        if let Some((tag, recursive)) = &self.tag {
+            if *recursive {
+                conditions.push(
+                    r#"album_id IN (
+    SELECT album_id FROM repo_tag_detail WHERE tag_id IN (
+        WITH RECURSIVE recursive_tags(tag_id) AS (
+          SELECT tag_id FROM repo_tag WHERE name = ?
+
+          UNION
+
+          SELECT rl.tag_id FROM repo_tag_relation rl, recursive_tags rt WHERE rl.parent_id = rt.tag_id
+        )
+        SELECT tag_id FROM recursive_tags
+    )
+)"#,
+                );
+            } else {
+                conditions.push(
+                    "album_id IN (SELECT album_id FROM repo_tag_detail WHERE tag_id IN (SELECT tag_id FROM repo_tag WHERE name = ?))",
+                );
+            }
+            params.push(Value::Text(tag.clone()));
+        }
+        if let Some(prefix) = &self.catalog_prefix {
+            conditions.push(r"catalog LIKE ? ESCAPE '\'");
+            params.push(Value::Text(format!("{}%", escape_like(prefix))));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!(" WHERE {}", conditions.join(" AND ")), params)
+        }
+    }
+}
+
+/// Escape `%`, `_` and `\` in `input` for `LIKE` patterns with `ESCAPE '\'`.
+fn escape_like(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+impl RepoDatabaseRead {
+    /// Query albums matching `query`, sorted and paginated as requested.
+    pub fn query_albums(&self, query: &AlbumQuery) -> RepoResult<Vec<rows::AlbumRow>> {
+        let (filter, mut params) = query.filter();
+        let order = if query.descending { "DESC" } else { "ASC" };
+        let sql = format!(
+            "SELECT * FROM repo_album{filter} ORDER BY {column} {order}, catalog {order} LIMIT ? OFFSET ?",
+            column = query.order.column(),
+        );
+        // negative limit means no limit in sqlite
+        params.push(Value::Integer(query.limit.map_or(-1, |l| l as i64)));
+        params.push(Value::Integer(query.offset as i64));
+
+        self.query_list(&sql, params_from_iter(params))
+    }
+
+    /// Count albums matching `query`, ignoring pagination.
+    pub fn count_albums(&self, query: &AlbumQuery) -> RepoResult<usize> {
+        #[derive(Deserialize)]
+        struct CountRow {
+            count: usize,
+        }
+
+        let (filter, params) = query.filter();
+        let sql = format!("SELECT count(*) AS count FROM repo_album{filter}");
+        let row: Option<CountRow> = self.query_optional(&sql, params_from_iter(params))?;
+        Ok(row.map_or(0, |row| row.count))
+    }
+}
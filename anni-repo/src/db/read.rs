@@ -1,7 +1,8 @@
 use crate::db::rows;
 use crate::prelude::RepoResult;
 use anni_metadata::model::{
-    Album, AlbumInfo, AnniDate, Disc, DiscInfo, TagString, TagType, Track, TrackType,
+    Album, AlbumInfo, AnniDate, ArtistCredit, ArtistCredits, ArtistKind, ArtistRole, Disc,
+    DiscInfo, ExternalIds, Localized, LocalizedText, TagRef, TagString, TagType, Track, TrackType,
 };
 use rusqlite::{params, Connection, OpenFlags, Params};
 use serde::de::DeserializeOwned;
@@ -124,43 +125,57 @@ impl RepoDatabaseRead {
             None => return Ok(None),
         };
         let album_tags = self.get_item_tags(album_id, None, None)?;
+        let album_credits = self.get_item_credits(album_id, None, None)?;
         let album_info = AlbumInfo {
             album_id,
             title: album_row.title,
             edition: album_row.edition,
             artist: album_row.artist,
-            artists: None,
+            artists: non_empty(album_credits),
             release_date: AnniDate::from_str(&album_row.release_date)?,
             album_type: TrackType::from_str(&album_row.album_type)?,
             catalog: album_row.catalog,
             tags: album_tags,
+            localized: self.get_item_localized(album_id, None, None)?,
+            ids: self.get_item_external_ids(album_id, None, None)?,
         };
 
         let discs_row = self.get_discs(album_id)?;
         let mut discs = Vec::with_capacity(discs_row.len());
         for disc in discs_row {
             let disc_tags = self.get_item_tags(album_id, Some(disc.disc_id), None)?;
-            let disc_info = DiscInfo::new(
+            let disc_credits = self.get_item_credits(album_id, Some(disc.disc_id), None)?;
+            let mut disc_info = DiscInfo::new(
                 disc.catalog,
                 Some(disc.title),
                 Some(disc.artist),
-                None,
+                non_empty(disc_credits),
                 Some(TrackType::from_str(&disc.disc_type)?),
                 disc_tags,
             );
+            disc_info.localized = self.get_item_localized(album_id, Some(disc.disc_id), None)?;
+            disc_info.ids = self.get_item_external_ids(album_id, Some(disc.disc_id), None)?;
 
             let tracks_row = self.get_tracks(album_id, disc.disc_id)?;
             let mut tracks = Vec::with_capacity(tracks_row.len());
             for track in tracks_row {
                 let track_tags =
                     self.get_item_tags(album_id, Some(disc.disc_id), Some(track.track_id))?;
-                let track = Track::new(
+                let track_credits =
+                    self.get_item_credits(album_id, Some(disc.disc_id), Some(track.track_id))?;
+                let track_localized =
+                    self.get_item_localized(album_id, Some(disc.disc_id), Some(track.track_id))?;
+                let track_ids =
+                    self.get_item_external_ids(album_id, Some(disc.disc_id), Some(track.track_id))?;
+                let mut track = Track::new(
                     track.title,
                     Some(track.artist),
-                    None,
+                    non_empty(track_credits),
                     Some(TrackType::from_str(&track.track_type)?),
                     track_tags,
                 );
+                track.localized = track_localized;
+                track.ids = track_ids;
                 tracks.push(track);
             }
 
@@ -248,6 +263,92 @@ impl RepoDatabaseRead {
         Ok(result)
     }
 
+    /// Get artist credits written in an album, a disc, or a track.
+    pub fn get_item_credits(
+        &self,
+        album_id: Uuid,
+        disc_id: Option<u8>,
+        track_id: Option<u8>,
+    ) -> RepoResult<ArtistCredits> {
+        #[derive(Deserialize)]
+        struct CreditRow {
+            role: String,
+            name: String,
+            kind: Option<ArtistKind>,
+            tag: Option<String>,
+        }
+
+        let credits: Vec<CreditRow> = self.query_list(
+            "SELECT role, name, kind, tag FROM repo_artist_credit WHERE album_id = ? AND disc_id IS ? AND track_id IS ? ORDER BY rowid",
+            params![album_id, disc_id, track_id],
+        )?;
+        Ok(credits
+            .into_iter()
+            .map(|row| ArtistCredit {
+                role: ArtistRole::from_str(&row.role).unwrap(),
+                name: row.name,
+                kind: row.kind,
+                tag: row.tag.map(|tag| TagRef::from_cow_str(tag).into()),
+            })
+            .collect())
+    }
+
+    /// Get localized titles and artists written in an album, a disc, or a track.
+    pub fn get_item_localized(
+        &self,
+        album_id: Uuid,
+        disc_id: Option<u8>,
+        track_id: Option<u8>,
+    ) -> RepoResult<Localized> {
+        #[derive(Deserialize)]
+        struct LocalizedRow {
+            locale: String,
+            title: Option<String>,
+            artist: Option<String>,
+        }
+
+        let rows: Vec<LocalizedRow> = self.query_list(
+            "SELECT locale, title, artist FROM repo_localized WHERE album_id = ? AND disc_id IS ? AND track_id IS ? ORDER BY rowid",
+            params![album_id, disc_id, track_id],
+        )?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.locale,
+                    LocalizedText {
+                        title: row.title,
+                        artist: row.artist,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Get external identifiers written in an album, a disc, or a track.
+    pub fn get_item_external_ids(
+        &self,
+        album_id: Uuid,
+        disc_id: Option<u8>,
+        track_id: Option<u8>,
+    ) -> RepoResult<ExternalIds> {
+        #[derive(Deserialize)]
+        struct ExternalIdRow {
+            kind: String,
+            value: String,
+        }
+
+        let rows: Vec<ExternalIdRow> = self.query_list(
+            "SELECT kind, value FROM repo_external_id WHERE album_id = ? AND disc_id IS ? AND track_id IS ?",
+            params![album_id, disc_id, track_id],
+        )?;
+        let mut ids = ExternalIds::default();
+        for row in rows {
+            ids.set(&row.kind, row.value);
+        }
+        Ok(ids)
+    }
+
     /// Get relationship between tags
     pub fn get_tags_relationship(&self) -> RepoResult<HashMap<TagString, TagEntry>> {
         #[derive(Deserialize)]
@@ -338,6 +439,10 @@ SELECT * FROM repo_album WHERE album_id IN (
     }
 }
 
+fn non_empty(credits: ArtistCredits) -> Option<ArtistCredits> {
+    (!credits.is_empty()).then_some(credits)
+}
+
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     use super::rows::wasm::*;
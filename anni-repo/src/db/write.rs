@@ -1,9 +1,10 @@
 use crate::db::DB_VERSION;
 use crate::prelude::RepoResult;
-use anni_metadata::model::{Album, Tag, TagType};
-use rusqlite::{params, Connection};
-use std::collections::HashMap;
+use anni_metadata::model::{Album, ArtistCredits, ExternalIds, Localized, Tag, TagType};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use uuid::Uuid;
 
 pub struct RepoDatabaseWrite {
     conn: Connection,
@@ -19,6 +20,36 @@ impl RepoDatabaseWrite {
         Ok(me)
     }
 
+    /// Opens an existing database to update it in place.
+    ///
+    /// Unlike [`RepoDatabaseWrite::create`], journal is kept so that updates in [`RepoDatabaseWrite::transaction`]
+    /// can be rolled back.
+    pub fn open(path: impl AsRef<Path>) -> RepoResult<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "synchronous", "off")?;
+        let me = Self { conn };
+        me.create_tables()?;
+        Ok(me)
+    }
+
+    /// Runs `f` in a transaction, which is rolled back if `f` fails.
+    pub fn transaction<F>(&self, f: F) -> RepoResult<()>
+    where
+        F: FnOnce(&Self) -> RepoResult<()>,
+    {
+        self.conn.execute_batch("BEGIN;")?;
+        match f(self) {
+            Ok(()) => {
+                self.conn.execute_batch("COMMIT;")?;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK;");
+                Err(e)
+            }
+        }
+    }
+
     fn create_tables(&self) -> RepoResult<()> {
         self.conn.execute_batch(r#"
 BEGIN;
@@ -87,6 +118,42 @@ CREATE TABLE IF NOT EXISTS "repo_tag_relation" (
   FOREIGN KEY("parent_id") REFERENCES "repo_tag"("tag_id")
 );
 
+CREATE TABLE IF NOT EXISTS "repo_artist_credit" (
+  "album_id"    BLOB NOT NULL,
+  "disc_id"     INTEGER,
+  "track_id"    INTEGER,
+  "role"        TEXT NOT NULL,
+  "name"        TEXT NOT NULL,
+  "kind"        TEXT CHECK("kind" IN ('person', 'group')),
+  "tag"         TEXT,
+  FOREIGN KEY("album_id") REFERENCES "repo_album"("album_id")
+);
+
+CREATE TABLE IF NOT EXISTS "repo_localized" (
+  "album_id"    BLOB NOT NULL,
+  "disc_id"     INTEGER,
+  "track_id"    INTEGER,
+  "locale"      TEXT NOT NULL,
+  "title"       TEXT,
+  "artist"      TEXT,
+  FOREIGN KEY("album_id") REFERENCES "repo_album"("album_id")
+);
+
+CREATE TABLE IF NOT EXISTS "repo_external_id" (
+  "album_id"    BLOB NOT NULL,
+  "disc_id"     INTEGER,
+  "track_id"    INTEGER,
+  "kind"        TEXT NOT NULL,
+  "value"       TEXT NOT NULL,
+  FOREIGN KEY("album_id") REFERENCES "repo_album"("album_id")
+);
+
+CREATE TABLE IF NOT EXISTS "repo_album_hash" (
+  "album_id"  BLOB NOT NULL UNIQUE,
+  "path"      TEXT NOT NULL UNIQUE,
+  "hash"      TEXT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS "repo_info" (
   "key"    TEXT NOT NULL UNIQUE,
   "value"  TEXT
@@ -101,7 +168,7 @@ COMMIT;"#)?;
             r#"
 BEGIN;
 
-CREATE UNIQUE INDEX "repo_album_index" ON "repo_album" (
+CREATE UNIQUE INDEX IF NOT EXISTS "repo_album_index" ON "repo_album" (
   "album_id"
 );
 
@@ -122,6 +189,29 @@ CREATE INDEX IF NOT EXISTS "repo_tag_detail_index" ON "repo_tag_detail" (
   "track_id"
 );
 
+CREATE INDEX IF NOT EXISTS "repo_artist_credit_index" ON "repo_artist_credit" (
+  "album_id",
+  "disc_id",
+  "track_id"
+);
+
+CREATE INDEX IF NOT EXISTS "repo_localized_index" ON "repo_localized" (
+  "album_id",
+  "disc_id",
+  "track_id"
+);
+
+CREATE INDEX IF NOT EXISTS "repo_external_id_index" ON "repo_external_id" (
+  "album_id",
+  "disc_id",
+  "track_id"
+);
+
+CREATE INDEX IF NOT EXISTS "repo_external_id_value_index" ON "repo_external_id" (
+  "kind",
+  "value"
+);
+
 COMMIT;
 "#,
         )?;
@@ -157,6 +247,9 @@ COMMIT;
                 ],
             )?;
         }
+        self.add_credits(album_id, None, None, album.artists.as_ref())?;
+        self.add_localized(album_id, None, None, &album.localized)?;
+        self.add_external_ids(album_id, None, None, &album.ids)?;
 
         for (disc_id, disc) in album.iter().enumerate() {
             let disc_id = disc_id + 1;
@@ -186,6 +279,9 @@ COMMIT;
                     ],
                 )?;
             }
+            self.add_credits(album_id, Some(disc_id), None, disc.raw().artists.as_ref())?;
+            self.add_localized(album_id, Some(disc_id), None, &disc.raw().localized)?;
+            self.add_external_ids(album_id, Some(disc_id), None, &disc.raw().ids)?;
 
             for (track_id, track) in disc.iter().enumerate() {
                 let track_id = track_id + 1;
@@ -215,11 +311,128 @@ COMMIT;
                         ],
                     )?;
                 }
+                self.add_credits(
+                    album_id,
+                    Some(disc_id),
+                    Some(track_id),
+                    track.raw().artists.as_ref(),
+                )?;
+                self.add_localized(
+                    album_id,
+                    Some(disc_id),
+                    Some(track_id),
+                    &track.raw().localized,
+                )?;
+                self.add_external_ids(album_id, Some(disc_id), Some(track_id), &track.raw().ids)?;
             }
         }
         Ok(())
     }
 
+    /// Add credits written in an album, a disc or a track.
+    ///
+    /// Inherited credits are not written, as tags.
+    fn add_credits(
+        &self,
+        album_id: Uuid,
+        disc_id: Option<usize>,
+        track_id: Option<usize>,
+        credits: Option<&ArtistCredits>,
+    ) -> RepoResult<()> {
+        for credit in credits.into_iter().flatten() {
+            self.conn.execute(
+                "INSERT INTO repo_artist_credit (album_id, disc_id, track_id, role, name, kind, tag) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    album_id,
+                    disc_id,
+                    track_id,
+                    credit.role.as_str(),
+                    credit.name,
+                    credit.kind.as_ref().map(AsRef::as_ref),
+                    credit.tag.as_ref().map(ToString::to_string),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Add localized titles and artists written in an album, a disc or a track.
+    fn add_localized(
+        &self,
+        album_id: Uuid,
+        disc_id: Option<usize>,
+        track_id: Option<usize>,
+        localized: &Localized,
+    ) -> RepoResult<()> {
+        for (locale, text) in localized.iter() {
+            self.conn.execute(
+                "INSERT INTO repo_localized (album_id, disc_id, track_id, locale, title, artist) VALUES (?, ?, ?, ?, ?, ?)",
+                params![album_id, disc_id, track_id, locale, text.title, text.artist],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Add external identifiers written in an album, a disc or a track.
+    ///
+    /// Inherited identifiers are not written.
+    fn add_external_ids(
+        &self,
+        album_id: Uuid,
+        disc_id: Option<usize>,
+        track_id: Option<usize>,
+        ids: &ExternalIds,
+    ) -> RepoResult<()> {
+        for (kind, value) in ids.iter() {
+            self.conn.execute(
+                "INSERT INTO repo_external_id (album_id, disc_id, track_id, kind, value) VALUES (?, ?, ?, ?, ?)",
+                params![album_id, disc_id, track_id, kind, value],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes an album, with its discs, tracks and tags.
+    pub fn remove_album(&self, album_id: &Uuid) -> RepoResult<()> {
+        for table in [
+            "repo_tag_detail",
+            "repo_artist_credit",
+            "repo_localized",
+            "repo_external_id",
+            "repo_track",
+            "repo_disc",
+            "repo_album",
+            "repo_album_hash",
+        ] {
+            self.conn.execute(
+                &format!("DELETE FROM {table} WHERE album_id = ?"),
+                [album_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Content hashes of album files the database was built from.
+    ///
+    /// Returns a map from path of album file relative to repository root, to album id and hash.
+    pub fn album_hashes(&self) -> RepoResult<HashMap<String, (Uuid, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, album_id, hash FROM repo_album_hash")?;
+        let hashes = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+            .collect::<Result<_, _>>()?;
+        Ok(hashes)
+    }
+
+    pub fn set_album_hash(&self, album_id: &Uuid, path: &str, hash: &str) -> RepoResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO repo_album_hash (album_id, path, hash) VALUES (?, ?, ?)",
+            params![album_id, path, hash],
+        )?;
+        Ok(())
+    }
+
     fn add_tag(&self, name: &str, tag_type: &TagType) -> RepoResult<i32> {
         self.conn.execute(
             "INSERT INTO repo_tag (name, tag_type) VALUES (?, ?)",
@@ -253,11 +466,49 @@ COMMIT;
     }
 
     pub fn add_tags<'tag>(&self, tags: impl Iterator<Item = &'tag Tag>) -> RepoResult<()> {
+        self.write_tags(tags, HashMap::new())?;
+        Ok(())
+    }
+
+    /// Updates tags to `tags`, keeping ids of existing tags.
+    ///
+    /// Returns names of tags added or removed. Albums tagged by those names should be written again,
+    /// as album tags are resolved by name.
+    pub fn update_tags<'tag>(
+        &self,
+        tags: impl Iterator<Item = &'tag Tag>,
+    ) -> RepoResult<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag_id, name, tag_type FROM repo_tag")?;
+        let existing = stmt
+            .query_map([], |row| Ok(((row.get(1)?, row.get(2)?), row.get(0)?)))?
+            .collect::<Result<_, _>>()?;
+
+        // i18n and relations are cheap to rebuild
+        self.conn
+            .execute_batch("DELETE FROM repo_tag_i18n; DELETE FROM repo_tag_relation;")?;
+        self.write_tags(tags, existing)
+    }
+
+    /// Writes `tags`, reusing ids in `existing` and removing tags left in it.
+    fn write_tags<'tag>(
+        &self,
+        tags: impl Iterator<Item = &'tag Tag>,
+        mut existing: HashMap<(String, String), i32>,
+    ) -> RepoResult<HashSet<String>> {
         let mut tag_id = HashMap::new();
         let mut relation_deferred = HashMap::new();
+        let mut changed = HashSet::new();
 
         for tag in tags {
-            let id = self.add_tag(tag.name(), tag.tag_type())?;
+            let id = match existing.remove(&(tag.name().to_string(), tag.tag_type().to_string())) {
+                Some(id) => id,
+                None => {
+                    changed.insert(tag.name().to_string());
+                    self.add_tag(tag.name(), tag.tag_type())?
+                }
+            };
             tag_id.insert(tag.as_ref(), id);
 
             // add i18n
@@ -278,17 +529,38 @@ COMMIT;
             relation_deferred.insert(id, tag.parents());
         }
 
+        // remove tags which no longer exist
+        for ((name, _), id) in existing {
+            self.conn
+                .execute("DELETE FROM repo_tag_detail WHERE tag_id = ?", [id])?;
+            self.conn
+                .execute("DELETE FROM repo_tag WHERE tag_id = ?", [id])?;
+            changed.insert(name);
+        }
+
         for (child_id, parents) in relation_deferred {
             for parent in parents {
                 self.add_parent(child_id, tag_id[parent])?;
             }
         }
-        Ok(())
+        Ok(changed)
+    }
+
+    pub fn db_version(&self) -> RepoResult<Option<String>> {
+        let version = self
+            .conn
+            .query_row(
+                "SELECT value FROM repo_info WHERE key = 'db_version'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(version)
     }
 
     fn add_info(&self, key: &str, value: &str) -> RepoResult<()> {
         self.conn.execute(
-            "INSERT INTO repo_info (key, value) VALUES (?, ?)",
+            "INSERT OR REPLACE INTO repo_info (key, value) VALUES (?, ?)",
             [key, value],
         )?;
         Ok(())
@@ -0,0 +1,312 @@
+//! Field level difference between two versions of an album.
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anni_metadata::model::{Album, AnniDate, UNKNOWN_ARTIST};
+use serde::Deserialize;
+use toml::Value;
+
+use crate::prelude::{Error, RepoResult};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Path to a field in album toml, e.g. `discs[0].tracks[1].title`.
+///
+/// The empty path refers to the whole album.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldPath(Vec<PathSegment>);
+
+impl FieldPath {
+    pub fn new(segments: Vec<PathSegment>) -> Self {
+        Self(segments)
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    pub(crate) fn join(&self, segment: PathSegment) -> Self {
+        let mut path = self.clone();
+        path.0.push(segment);
+        path
+    }
+
+    /// Whether the last segment of path is `key`.
+    pub(crate) fn ends_with(&self, key: &str) -> bool {
+        matches!(self.0.last(), Some(PathSegment::Key(k)) if k == key)
+    }
+
+    /// Whether this path is `prefix` or a field inside it.
+    pub fn starts_with(&self, prefix: &FieldPath) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => write!(f, "{key}")?,
+                PathSegment::Key(key) => write!(f, ".{key}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FieldPath {
+    type Err = String;
+
+    /// Parse path in the form printed by [Display], e.g. `discs[0].tracks[1].title`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        if s.is_empty() {
+            return Ok(Self(segments));
+        }
+
+        for part in s.split('.') {
+            let (key, indexes) = part.split_once('[').unwrap_or((part, ""));
+            if key.is_empty() {
+                return Err(format!("empty key in field path: {s}"));
+            }
+            segments.push(PathSegment::Key(key.to_string()));
+            if indexes.is_empty() {
+                continue;
+            }
+            for index in indexes.trim_end_matches(']').split("][") {
+                let index = index
+                    .parse()
+                    .map_err(|_| format!("invalid index in field path: {s}"))?;
+                segments.push(PathSegment::Index(index));
+            }
+        }
+        Ok(Self(segments))
+    }
+}
+
+/// A changed field. `None` means the field does not exist in that version.
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub path: FieldPath,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Compare two versions of an album field by field.
+///
+/// Discs and tracks are compared by their position, so added or removed discs and tracks are reported as a whole.
+/// If one of the versions is `None`, the whole album is reported as added or removed.
+pub fn diff_album(old: Option<&Album>, new: Option<&Album>) -> Vec<FieldChange> {
+    let to_value = |album: &Album| Value::try_from(album).expect("album should be serializable");
+    let old = old.map(to_value);
+    let new = new.map(to_value);
+
+    let mut changes = Vec::new();
+    diff_value(
+        &FieldPath::default(),
+        old.as_ref(),
+        new.as_ref(),
+        &mut changes,
+    );
+    changes
+}
+
+fn diff_value(
+    path: &FieldPath,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    if old == new {
+        return;
+    }
+
+    match (old, new) {
+        (Some(Value::Table(old)), Some(Value::Table(new))) => {
+            let keys = old
+                .keys()
+                .chain(new.keys().filter(|key| !old.contains_key(*key)));
+            for key in keys {
+                diff_value(
+                    &path.join(PathSegment::Key(key.clone())),
+                    old.get(key),
+                    new.get(key),
+                    changes,
+                );
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new)))
+            if !path.ends_with("tags") && old.iter().chain(new).all(Value::is_table) =>
+        {
+            for i in 0..old.len().max(new.len()) {
+                diff_value(
+                    &path.join(PathSegment::Index(i)),
+                    old.get(i),
+                    new.get(i),
+                    changes,
+                );
+            }
+        }
+        _ => changes.push(FieldChange {
+            path: path.clone(),
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+    }
+}
+
+/// Align `remote` album, fetched from an external source, with `album` in repository.
+///
+/// Returns `album` with artists of tracks resolved, and a copy of it where fields provided by `remote` are replaced.
+/// Diff of the two albums is what the external source would change.
+///
+/// Discs and tracks are aligned by position. Fields which external sources do not provide,
+/// such as album id, catalog, tags and types, are kept.
+pub fn align_album(album: &Album, remote: &Album) -> (Album, Album) {
+    let old = resolve_artists(album);
+    let remote = resolve_artists(remote);
+    let mut new = old.clone();
+
+    new.title = remote.title.clone();
+    if remote.edition.is_some() {
+        new.edition = remote.edition.clone();
+    }
+    if is_known_artist(&remote.artist) {
+        new.artist = remote.artist.clone();
+    }
+    if remote.release_date != AnniDate::UNKNOWN {
+        new.release_date = remote.release_date.clone();
+    }
+    new.ids = remote.ids.clone().inherit(&old.ids);
+
+    new.discs.truncate(remote.discs.len());
+    for (disc_id, remote_disc) in remote.discs.iter().enumerate() {
+        let Some(disc) = new.discs.get_mut(disc_id) else {
+            let mut disc = remote_disc.clone();
+            disc.catalog = old.catalog().to_string();
+            new.discs.push(disc);
+            continue;
+        };
+        if remote_disc.title.is_some() {
+            disc.title = remote_disc.title.clone();
+        }
+        disc.ids = remote_disc.ids.clone().inherit(&disc.ids);
+
+        disc.tracks.truncate(remote_disc.tracks.len());
+        for (track_id, remote_track) in remote_disc.tracks.iter().enumerate() {
+            let Some(track) = disc.tracks.get_mut(track_id) else {
+                disc.tracks.push(remote_track.clone());
+                continue;
+            };
+            track.title = remote_track.title.clone();
+            if remote_track.artist.is_some() {
+                track.artist = remote_track.artist.clone();
+            }
+            track.ids = remote_track.ids.clone().inherit(&track.ids);
+        }
+    }
+
+    (old, new)
+}
+
+fn is_known_artist(artist: &str) -> bool {
+    !artist.is_empty() && artist != UNKNOWN_ARTIST
+}
+
+/// Write inherited artist to every track, so that tracks can be compared regardless of where artist is written.
+fn resolve_artists(album: &Album) -> Album {
+    let mut resolved = album.clone();
+    for (disc, resolved_disc) in album.iter().zip(resolved.discs.iter_mut()) {
+        for (track, resolved_track) in disc.iter().zip(resolved_disc.tracks.iter_mut()) {
+            resolved_track.artist = Some(track.artist())
+                .filter(|artist| is_known_artist(artist))
+                .map(str::to_string);
+        }
+        resolved_disc.artist = None;
+    }
+    resolved
+}
+
+/// Apply `changes` of fields to `album`.
+///
+/// Changes are usually a part of [diff_album] of `album` and another version of it.
+/// Call [Album::format] on the result to move common artists and types of tracks back to discs.
+///
+/// Changes are applied all or nothing. Adding a disc or track after the end of the existing ones,
+/// e.g. accepting the third added track without the second, fails with [Error::InvalidFieldChange].
+pub fn apply_changes<'a, I>(album: &Album, changes: I) -> RepoResult<Album>
+where
+    I: IntoIterator<Item = &'a FieldChange>,
+{
+    let mut value = Value::try_from(album).expect("album should be serializable");
+
+    let mut removed = Vec::new();
+    for change in changes {
+        match &change.new {
+            Some(new) => set_value(&mut value, &change.path, Some(new.clone()))?,
+            None => removed.push(change),
+        }
+    }
+    // remove fields from the end, so that indexes of the remaining ones do not shift
+    for change in removed.into_iter().rev() {
+        set_value(&mut value, &change.path, None)?;
+    }
+
+    Album::deserialize(value.clone()).map_err(|err| Error::TomlParseError {
+        target: "Album",
+        input: value.to_string(),
+        err,
+    })
+}
+
+fn set_value(value: &mut Value, path: &FieldPath, new: Option<Value>) -> RepoResult<()> {
+    let invalid = || Error::InvalidFieldChange(path.to_string());
+    let Some((last, parents)) = path.segments().split_last() else {
+        if let Some(new) = new {
+            *value = new;
+        }
+        return Ok(());
+    };
+
+    let mut current = value;
+    for segment in parents {
+        current = match (segment, current) {
+            (PathSegment::Key(key), Value::Table(table)) => table
+                .entry(key.clone())
+                .or_insert_with(|| Value::Table(Default::default())),
+            (PathSegment::Index(index), Value::Array(array)) => {
+                array.get_mut(*index).ok_or_else(invalid)?
+            }
+            _ => return Err(invalid()),
+        };
+    }
+
+    match (last, current, new) {
+        (PathSegment::Key(key), Value::Table(table), Some(new)) => {
+            table.insert(key.clone(), new);
+        }
+        (PathSegment::Key(key), Value::Table(table), None) => {
+            table.remove(key);
+        }
+        (PathSegment::Index(index), Value::Array(array), Some(new)) => {
+            if *index < array.len() {
+                array[*index] = new;
+            } else if *index == array.len() {
+                array.push(new);
+            } else {
+                // items before it were not added
+                return Err(invalid());
+            }
+        }
+        (PathSegment::Index(index), Value::Array(array), None) if *index < array.len() => {
+            array.remove(*index);
+        }
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
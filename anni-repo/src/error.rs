@@ -11,6 +11,9 @@ pub enum Error {
         err: toml::de::Error,
     },
 
+    #[error(transparent)]
+    TomlEditError(#[from] toml_edit::TomlError),
+
     #[error("album with the same catalog already exists: {0}")]
     RepoAlbumExists(String),
 
@@ -38,6 +41,12 @@ pub enum Error {
     #[error("invalid date: {0}")]
     InvalidDate(String),
 
+    #[error("can not apply change of {0}: the field before it is missing")]
+    InvalidFieldChange(String),
+
+    #[error("invalid album schema: {0}")]
+    InvalidAlbumSchema(String),
+
     #[error(transparent)]
     IOError(#[from] std::io::Error),
 
@@ -52,6 +61,10 @@ pub enum Error {
     #[error(transparent)]
     MetadataError(#[from] anni_metadata::error::Error),
 
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
     #[cfg(feature = "git")]
     #[error(transparent)]
     GitError(#[from] git2::Error),
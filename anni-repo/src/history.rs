@@ -0,0 +1,136 @@
+//! Change history of albums, recovered from git commits of the repository.
+use std::path::Path;
+use std::str::FromStr;
+
+use anni_metadata::model::Album;
+use git2::{DiffFile, Repository, Sort};
+use toml::Value;
+use uuid::Uuid;
+
+use crate::diff::{diff_album, FieldChange, FieldPath, PathSegment};
+use crate::prelude::*;
+
+/// A commit which changed an album.
+#[derive(Debug, Clone)]
+pub struct AlbumCommit {
+    /// Commit hash
+    pub id: String,
+    pub author: String,
+    pub email: String,
+    /// Commit time in seconds since unix epoch
+    pub time: i64,
+    pub summary: String,
+    pub changes: Vec<FieldChange>,
+}
+
+impl AlbumCommit {
+    /// Whether this commit changed field at `path`, or any field inside it.
+    pub fn touches(&self, path: &FieldPath) -> bool {
+        self.changes
+            .iter()
+            .any(|change| change.path.starts_with(path) || path.starts_with(&change.path))
+    }
+}
+
+/// Walk commits reachable from `HEAD` of repository at `root`, newest first,
+/// and collect field changes of album `album_id`.
+///
+/// Album files are matched by `album_id`, so renamed files are followed.
+/// Merge commits are skipped, as their changes are recorded in the merged commits.
+pub(crate) fn album_history(root: &Path, album_id: &Uuid) -> RepoResult<Vec<AlbumCommit>> {
+    let repo = Repository::discover(root)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push_head()?;
+
+    let read_album = |file: &DiffFile| {
+        let is_toml = file
+            .path()
+            .map_or(false, |path| path.extension() == Some("toml".as_ref()));
+        if !is_toml || file.id().is_zero() {
+            return None;
+        }
+        let blob = repo.find_blob(file.id()).ok()?;
+        Album::from_str(std::str::from_utf8(blob.content()).ok()?)
+            .ok()
+            .filter(|album| &album.album_id() == album_id)
+    };
+
+    let mut history = Vec::new();
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+        let (mut old, mut new) = (None, None);
+        for delta in diff.deltas() {
+            old = old.or_else(|| read_album(&delta.old_file()));
+            new = new.or_else(|| read_album(&delta.new_file()));
+        }
+        if old.is_none() && new.is_none() {
+            continue;
+        }
+
+        // formatting-only changes are not interesting
+        let changes = diff_album(old.as_ref(), new.as_ref());
+        if changes.is_empty() {
+            continue;
+        }
+
+        let author = commit.author();
+        history.push(AlbumCommit {
+            id: commit.id().to_string(),
+            author: author.name().unwrap_or_default().to_string(),
+            email: author.email().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            changes,
+        });
+    }
+
+    Ok(history)
+}
+
+/// Find the last commit in `history` which changed each field of `album`.
+///
+/// `history` should be ordered newest first, as returned by [RepositoryManager::album_history](crate::RepositoryManager::album_history).
+pub fn blame<'a>(
+    album: &Album,
+    history: &'a [AlbumCommit],
+) -> Vec<(FieldPath, Value, Option<&'a AlbumCommit>)> {
+    let mut fields = Vec::new();
+    let album = Value::try_from(album).expect("album should be serializable");
+    collect_fields(FieldPath::default(), album, &mut fields);
+
+    fields
+        .into_iter()
+        .map(|(path, value)| {
+            let commit = history.iter().find(|commit| commit.touches(&path));
+            (path, value, commit)
+        })
+        .collect()
+}
+
+fn collect_fields(path: FieldPath, value: Value, fields: &mut Vec<(FieldPath, Value)>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                collect_fields(path.join(PathSegment::Key(key)), value, fields);
+            }
+        }
+        Value::Array(array) if !path.ends_with("tags") && array.iter().all(Value::is_table) => {
+            for (i, value) in array.into_iter().enumerate() {
+                collect_fields(path.join(PathSegment::Index(i)), value, fields);
+            }
+        }
+        value => fields.push((path, value)),
+    }
+}
@@ -1,7 +1,15 @@
+pub mod diff;
 pub mod error;
 pub mod library;
 mod manager;
+pub mod merge;
 pub mod models;
+pub mod retag;
+pub mod schema;
+pub mod span;
+
+#[cfg(feature = "git")]
+pub mod history;
 
 #[cfg(feature = "search")]
 pub mod search;
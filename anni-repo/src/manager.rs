@@ -1,6 +1,7 @@
 use crate::prelude::*;
+use crate::{retag, schema};
 use anni_common::fs;
-use anni_metadata::model::{Album, Tag, TagRef, TagType, Tags};
+use anni_metadata::model::{Album, Tag, TagRef, TagString, TagType, Tags};
 use indexmap::IndexSet;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -49,6 +50,20 @@ impl RepositoryManager {
         Self::new(root.as_ref())
     }
 
+    /// Use `driver` as git merge driver of album files in this repository.
+    ///
+    /// `driver` is the command line run by git, with placeholders like `%O`, `%A` and `%B`.
+    #[cfg(feature = "git")]
+    pub fn install_merge_driver(&self, driver: &str) -> RepoResult<()> {
+        crate::utils::git::install_merge_driver(&self.root, &self.album_roots(), driver)
+    }
+
+    /// Get commits which changed album `album_id`, newest first.
+    #[cfg(feature = "git")]
+    pub fn album_history(&self, album_id: &Uuid) -> RepoResult<Vec<crate::history::AlbumCommit>> {
+        crate::history::album_history(&self.root, album_id)
+    }
+
     pub fn name(&self) -> &str {
         self.repo.name()
     }
@@ -195,6 +210,21 @@ impl RepositoryManager {
         Ok(())
     }
 
+    /// Upgrade all album files to the latest schema version, see [schema::upgrade_album].
+    ///
+    /// Returns upgraded files.
+    pub fn upgrade_albums(&self) -> RepoResult<Vec<PathBuf>> {
+        let mut upgraded = Vec::new();
+        for path in self.all_album_paths()? {
+            let input = fs::read_to_string(&path)?;
+            if let Some(output) = schema::upgrade_album(&input)? {
+                fs::write(&path, output)?;
+                upgraded.push(path);
+            }
+        }
+        Ok(upgraded)
+    }
+
     pub fn into_owned_manager(self) -> RepoResult<OwnedRepositoryManager> {
         OwnedRepositoryManager::new(self)
     }
@@ -204,6 +234,9 @@ impl RepositoryManager {
     }
 }
 
+/// Edit of a file, which returns `None` if the file is not changed.
+type FileEdit<'a> = dyn Fn(&str) -> RepoResult<Option<String>> + 'a;
+
 /// A repository manager which own full copy of a repo.
 ///
 /// This is helpful when you need to perform a full-repo operation,
@@ -213,6 +246,8 @@ pub struct OwnedRepositoryManager {
 
     /// All available tags.
     tags: HashMap<String, HashMap<TagType, Tag>>,
+    /// Aliases of tags
+    tag_aliases: TagAliases,
     /// Parent to child tag relation
     tags_relation: HashMap<TagRef<'static>, IndexSet<TagRef<'static>>>,
     /// Tag -> File
@@ -227,9 +262,19 @@ pub struct OwnedRepositoryManager {
 
 impl OwnedRepositoryManager {
     pub fn new(repo: RepositoryManager) -> RepoResult<Self> {
+        let mut repo = Self::tags_only(repo)?;
+        repo.load_albums()?;
+        Ok(repo)
+    }
+
+    /// Load tags of repository without albums.
+    ///
+    /// This is helpful when albums are being edited, and may refer to tags which are not defined yet.
+    pub fn tags_only(repo: RepositoryManager) -> RepoResult<Self> {
         let mut repo = Self {
             repo,
             tags: Default::default(),
+            tag_aliases: Default::default(),
             tags_relation: Default::default(),
             tag_path: Default::default(),
             album_tags: Default::default(),
@@ -245,7 +290,6 @@ impl OwnedRepositoryManager {
 
         fs::write(lock_file, "")?;
         repo.load_tags()?;
-        repo.load_albums()?;
 
         Ok(repo)
     }
@@ -270,12 +314,21 @@ impl OwnedRepositoryManager {
         self.albums.values()
     }
 
+    /// Get tag by its name or one of its aliases.
     pub fn tag(&self, tag: &TagRef<'_>) -> Option<&Tag> {
+        let tag = self.alias_target(tag).unwrap_or(tag);
         self.tags
             .get(tag.name())
             .and_then(|tags| tags.get(tag.tag_type()))
     }
 
+    /// Get the tag which `alias` refers to, if `alias` is an alias.
+    fn alias_target(&self, alias: &TagRef<'_>) -> Option<&TagRef<'static>> {
+        self.tag_aliases
+            .get(alias.name())
+            .and_then(|aliases| aliases.get(alias.tag_type()))
+    }
+
     pub fn tags_iter(&self) -> impl Iterator<Item = &Tag> {
         self.tags.values().flat_map(|m| m.values())
     }
@@ -301,20 +354,27 @@ impl OwnedRepositoryManager {
     }
 
     fn add_tag(&mut self, tag: Tag, tag_relative_path: PathBuf) -> Result<(), Error> {
-        // fully duplicated tags are not allowed
+        // fully duplicated tags are not allowed, and names of tags can not be aliases of others
         if let Some(tag) = self.tag(tag.as_ref()) {
             return Err(Error::RepoTagDuplicated(tag.get_owned_ref()));
         }
+        for alias in tag.alias_refs() {
+            if self.tag(&alias).is_some() || alias == *tag.as_ref() {
+                return Err(Error::RepoTagDuplicated(alias.full_clone()));
+            }
+        }
 
-        let map = if let Some(map) = self.tags.get_mut(tag.name()) {
-            map
-        } else {
-            let map = HashMap::with_capacity(1);
-            self.tags.insert(tag.name().to_string(), map);
-            self.tags.get_mut(tag.name()).unwrap()
-        };
         let tag_ref = tag.get_owned_ref();
-        map.insert(tag_ref.tag_type().clone(), tag);
+        for alias in tag.aliases() {
+            self.tag_aliases
+                .entry(alias.clone())
+                .or_default()
+                .insert(tag_ref.tag_type().clone(), tag_ref.clone());
+        }
+        self.tags
+            .entry(tag.name().to_string())
+            .or_default()
+            .insert(tag_ref.tag_type().clone(), tag);
         self.tag_path.insert(tag_ref, tag_relative_path);
         Ok(())
     }
@@ -338,8 +398,12 @@ impl OwnedRepositoryManager {
 
         // clear tags
         self.tags.clear();
+        self.tag_aliases.clear();
         self.tags_relation.clear();
 
+        // relations may refer to aliases, which are resolved after all tags are loaded
+        let mut relations = Vec::new();
+
         // iterate over tag files
         for tag_file in tags_path {
             let text = fs::read_to_string(&tag_file)?;
@@ -354,7 +418,7 @@ impl OwnedRepositoryManager {
 
             for tag in tags {
                 for parent in tag.parents() {
-                    self.add_tag_relation(parent.clone(), tag.get_owned_ref());
+                    relations.push((parent.clone(), tag.get_owned_ref()));
                 }
 
                 // add children to set
@@ -362,13 +426,30 @@ impl OwnedRepositoryManager {
                     let parent = tag.get_owned_ref();
                     let full = child.clone().into_full(vec![parent.into()]);
                     self.add_tag(full, relative_path.clone())?;
-                    self.add_tag_relation(tag.get_owned_ref(), child.clone());
+                    relations.push((tag.get_owned_ref(), child.clone()));
                 }
 
                 self.add_tag(tag, relative_path.clone())?;
             }
         }
 
+        for (parent, child) in relations {
+            let parent = self.alias_target(&parent).cloned().unwrap_or(parent);
+            let child = self.alias_target(&child).cloned().unwrap_or(child);
+            self.add_tag_relation(parent, child);
+        }
+        let aliases = &self.tag_aliases;
+        for tag in self.tags.values_mut().flat_map(|tags| tags.values_mut()) {
+            for relation in tag.relations_mut() {
+                if let Some(target) = aliases
+                    .get(relation.name())
+                    .and_then(|aliases| aliases.get(relation.tag_type()))
+                {
+                    *relation = target.clone().into();
+                }
+            }
+        }
+
         // check tag relationship
         let all_tags: HashSet<_> = self.tags_iter().map(Tag::as_ref).collect();
         let mut rel_tags: HashSet<_> = self.tags_relation.keys().collect();
@@ -389,7 +470,7 @@ impl OwnedRepositoryManager {
         let mut problems = vec![];
         for path in self.repo.all_album_paths()? {
             let mut album = self.repo.load_album(&path)?;
-            album.resolve_tags(&self.tags)?;
+            album.resolve_tags(&self.tags, &self.tag_aliases)?;
 
             let album_id = album.album_id();
             let catalog = album.catalog();
@@ -416,6 +497,15 @@ impl OwnedRepositoryManager {
                     self.album_tags.get_mut(tag_ref).unwrap().push(album_id);
                 }
             }
+            // tags linked by artist credits must exist, but albums are not indexed by them
+            for tag_ref in album.credits().filter_map(|credit| credit.tag.as_deref()) {
+                if self.tag(tag_ref).is_none() {
+                    log::error!(
+                        "Orphan tag {tag_ref} found in artist credits of album {album_id}, catalog = {catalog}"
+                    );
+                    problems.push(Error::RepoTagsUndefined(vec![tag_ref.clone()]));
+                }
+            }
             if let Some(album_with_same_id) = self.albums.insert(album_id, album) {
                 log::error!(
                     "Duplicated album id detected: {}",
@@ -497,13 +587,101 @@ impl OwnedRepositoryManager {
         None
     }
 
+    /// Find tag relations which are not allowed by types of tags. See [TagType::can_include].
+    ///
+    /// Returns pairs of parent and child.
+    pub fn check_tag_types(&self) -> Vec<(&TagRef<'static>, &TagRef<'static>)> {
+        self.tags_relation
+            .iter()
+            .flat_map(|(parent, children)| children.iter().map(move |child| (parent, child)))
+            .filter(|(parent, child)| !parent.tag_type().can_include(child.tag_type()))
+            .collect()
+    }
+
+    /// Rename tag `from` to `to`, and rewrite albums and tags which refer to it.
+    ///
+    /// If `to` has no tag type, the type of `from` is kept.
+    /// Loaded albums and tags are not updated. Returns changed files, relative to repository root.
+    pub fn rename_tag(&self, from: &TagRef<'_>, to: &TagRef<'_>) -> RepoResult<Vec<PathBuf>> {
+        let from = self.defined_tag(from)?;
+        let to = match to.tag_type() {
+            TagType::Unknown => TagRef::new(to.name().to_string(), from.tag_type().clone()),
+            _ => to.full_clone(),
+        };
+        if self.tag(&to).is_some() {
+            return Err(Error::RepoTagDuplicated(to));
+        }
+
+        let replace = |input: &str| retag::replace_tag_references(input, &from, &to);
+        let rename = |input: &str| retag::rename_tag_definition(input, &from, &to);
+        self.rewrite_files(&[&replace], &[&replace, &rename])
+    }
+
+    /// Merge tag `from` into `into`, and rewrite albums and tags which refer to `from`.
+    ///
+    /// Definition of `from` is removed, and its name becomes an alias of `into`.
+    /// Loaded albums and tags are not updated. Returns changed files, relative to repository root.
+    pub fn merge_tag(&self, from: &TagRef<'_>, into: &TagRef<'_>) -> RepoResult<Vec<PathBuf>> {
+        let from = self.defined_tag(from)?;
+        let into = self.defined_tag(into)?;
+        if from == into {
+            return Ok(Vec::new());
+        }
+        let (tag, into_tag) = (self.tag(&from).unwrap(), self.tag(&into).unwrap());
+
+        let replace = |input: &str| retag::replace_tag_references(input, &from, &into);
+        let remove = |input: &str| retag::remove_tag_definition(input, &from);
+        let merge = |input: &str| retag::merge_tag_definition(input, into_tag, tag);
+        self.rewrite_files(&[&replace], &[&replace, &remove, &merge])
+    }
+
+    /// Get reference of tag `tag` by its name or alias, or report it as undefined.
+    ///
+    /// Tags without type are resolved as those in albums.
+    fn defined_tag(&self, tag: &TagRef<'_>) -> RepoResult<TagRef<'static>> {
+        let mut tag: TagString = tag.full_clone().into();
+        tag.resolve_tags(&self.tags, &self.tag_aliases)?;
+        self.tag(&tag)
+            .map(Tag::get_owned_ref)
+            .ok_or_else(|| Error::RepoTagsUndefined(vec![tag.full_clone()]))
+    }
+
+    /// Apply `album_edits` to every album file and `tag_edits` to every tag file in order,
+    /// and write changed files back.
+    fn rewrite_files(
+        &self,
+        album_edits: &[&FileEdit<'_>],
+        tag_edits: &[&FileEdit<'_>],
+    ) -> RepoResult<Vec<PathBuf>> {
+        let tag_files: IndexSet<_> = self.tag_path.values().collect();
+        let files = tag_files
+            .into_iter()
+            .map(|path| (path, tag_edits))
+            .chain(self.album_path.values().map(|path| (path, album_edits)));
+
+        let mut changed = Vec::new();
+        for (path, edits) in files {
+            let file = self.repo.root.join(path);
+            let input = fs::read_to_string(&file)?;
+            let mut output = None;
+            for edit in edits {
+                if let Some(edited) = edit(output.as_deref().unwrap_or(&input))? {
+                    output = Some(edited);
+                }
+            }
+            if let Some(output) = output {
+                fs::write(&file, output)?;
+                changed.push(path.clone());
+            }
+        }
+        Ok(changed)
+    }
+
     #[cfg(feature = "db-write")]
     pub fn to_database<P>(&self, database_path: P) -> RepoResult<()>
     where
         P: AsRef<Path>,
     {
-        use std::time::{SystemTime, UNIX_EPOCH};
-
         // remove database first
         let _ = std::fs::remove_file(database_path.as_ref());
 
@@ -516,16 +694,120 @@ impl OwnedRepositoryManager {
         db.add_tags(tags)?;
 
         // Write all albums
-        for album in self.albums_iter() {
+        for (album_id, album) in self.albums() {
+            let path = &self.album_path[album_id];
+            let content = fs::read_to_string(self.repo.root().join(path))?;
+            db.set_album_hash(
+                album_id,
+                &path.to_string_lossy(),
+                &album_file_hash(&content),
+            )?;
             db.add_album(album)?;
         }
 
         // Create Index
         db.create_index()?;
 
-        // Creation time
+        self.write_last_modified(database_path.as_ref())
+    }
+
+    /// Update database at `database_path` in place.
+    ///
+    /// Album files are hashed and compared with hashes recorded in the database, and only new or
+    /// changed files are parsed and written again. Albums which no longer exist are removed.
+    /// Tags are always rebuilt.
+    ///
+    /// Albums are read from disk instead of the manager, so a manager created by
+    /// [`OwnedRepositoryManager::tags_only`] is enough.
+    /// The database is built from scratch if it does not exist or was built with another version.
+    #[cfg(feature = "db-write")]
+    pub fn to_database_incremental<P>(&self, database_path: P) -> RepoResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        use crate::db::{RepoDatabaseWrite, DB_VERSION};
+
+        let database_path = database_path.as_ref();
+        let db = if database_path.exists() {
+            let db = RepoDatabaseWrite::open(database_path)?;
+            if db.db_version()?.as_deref() == Some(DB_VERSION) {
+                db
+            } else {
+                log::info!("Database version mismatch, rebuilding {database_path:?}");
+                drop(db);
+                std::fs::remove_file(database_path)?;
+                RepoDatabaseWrite::create(database_path)?
+            }
+        } else {
+            RepoDatabaseWrite::create(database_path)?
+        };
+
+        db.transaction(|db| {
+            db.write_info(self.repo.name(), self.repo.edition(), "", "")?;
+
+            // album tags are resolved by name, so albums tagged by added or removed tags are affected too
+            let changed_tags = db.update_tags(self.tags_iter())?;
+
+            let mut hashes = db.album_hashes()?;
+            let mut album_ids = HashSet::new();
+            let mut updated = 0;
+            for path in self.repo.all_album_paths()? {
+                let relative_path = pathdiff::diff_paths(&path, self.repo.root()).unwrap();
+                let relative_path = relative_path.to_string_lossy();
+                let content = fs::read_to_string(&path)?;
+                let hash = album_file_hash(&content);
+
+                let old = hashes.remove(relative_path.as_ref());
+                if let Some((album_id, old_hash)) = &old {
+                    // tag names are searched in the raw file, so that unchanged albums are not parsed
+                    let tag_changed = changed_tags
+                        .iter()
+                        .any(|tag| content.contains(tag.as_str()));
+                    if *old_hash == hash && !tag_changed {
+                        album_ids.insert(*album_id);
+                        continue;
+                    }
+                    db.remove_album(album_id)?;
+                }
+
+                let mut album = Album::from_str(&content)?;
+                album.resolve_tags(&self.tags, &self.tag_aliases)?;
+                let album_id = album.album_id();
+                if !album_ids.insert(album_id) {
+                    return Err(Error::RepoDuplicatedAlbumId(album_id.to_string()));
+                }
+
+                // the album may have been moved from another file
+                db.remove_album(&album_id)?;
+                db.add_album(&album)?;
+                db.set_album_hash(&album_id, &relative_path, &hash)?;
+                updated += 1;
+            }
+
+            // files left were removed from repository
+            let mut removed = 0;
+            for (album_id, _) in hashes.values() {
+                if !album_ids.contains(album_id) {
+                    db.remove_album(album_id)?;
+                    removed += 1;
+                }
+            }
+
+            log::info!("Database updated: {updated} albums written, {removed} albums removed");
+            Ok(())
+        })?;
+        db.create_index()?;
+
+        self.write_last_modified(database_path)
+    }
+
+    /// Write database modification time to `repo.json` next to the database.
+    #[cfg(feature = "db-write")]
+    fn write_last_modified(&self, database_path: &Path) -> RepoResult<()> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
         fs::write(
-            database_path.as_ref().with_file_name("repo.json"),
+            database_path.with_file_name("repo.json"),
             format!(
                 "{{\"last_modified\": {}}}",
                 SystemTime::now()
@@ -537,34 +819,43 @@ impl OwnedRepositoryManager {
         Ok(())
     }
 
+    /// Build full-text search index of albums, discs, tracks and tags at `path`.
     #[cfg(feature = "search")]
-    pub fn build_search_index<P>(&self, path: P)
+    pub fn build_search_index<P>(&self, path: P) -> Result<(), tantivy::TantivyError>
     where
         P: AsRef<Path>,
     {
         use crate::search::RepositorySearchManager;
+        use anni_metadata::model::TrackIdentifier;
 
-        let searcher = RepositorySearchManager::create(path).unwrap();
-        let mut index_writer = searcher.index.writer(100_000_000).unwrap();
+        let searcher = RepositorySearchManager::create(path)?;
+        let mut index_writer = searcher.index.writer(100_000_000)?;
 
         for album in self.albums_iter() {
-            for (disc_id_v, disc) in album.iter().enumerate() {
-                let disc_id_v = disc_id_v + 1;
-                for (track_id_v, track) in disc.iter().enumerate() {
-                    let track_id_v = track_id_v + 1;
-                    index_writer
-                        .add_document(searcher.build_document(
-                            track.title(),
-                            track.artist(),
-                            &album.album_id,
-                            disc_id_v as i64,
-                            track_id_v as i64,
-                        ))
-                        .unwrap();
+            let album_id = album.album_id();
+            index_writer.add_document(searcher.build_album_document(album))?;
+            for (disc_id, disc) in album.iter().enumerate() {
+                let disc_id = (disc_id + 1) as u32;
+                index_writer
+                    .add_document(searcher.build_disc_document(&album_id, disc_id, &disc))?;
+                for (track_id, track) in disc.iter().enumerate() {
+                    let track_id = (track_id + 1) as u32;
+                    index_writer.add_document(searcher.build_track_document(
+                        TrackIdentifier {
+                            album_id,
+                            disc_id,
+                            track_id,
+                        },
+                        &track,
+                    ))?;
                 }
             }
         }
-        index_writer.commit().unwrap();
+        for tag in self.repo.tags_iter() {
+            index_writer.add_document(searcher.build_tag_document(tag))?;
+        }
+        index_writer.commit()?;
+        Ok(())
     }
 }
 
@@ -578,3 +869,11 @@ impl Drop for OwnedRepositoryManager {
         }
     }
 }
+
+/// Content hash of an album file, used to detect changed albums in incremental database builds.
+#[cfg(feature = "db-write")]
+fn album_file_hash(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    format!("{:x}", Sha256::digest(content))
+}
@@ -0,0 +1,339 @@
+//! Structural three-way merge of album metadata files.
+//!
+//! Albums are merged field by field, so that concurrent edits to different fields of the same album
+//! do not conflict. Conflict markers are only emitted for fields changed differently on both sides.
+use anni_metadata::model::{Album, Disc, Track};
+use serde::Serialize;
+use toml::Value;
+use toml_edit::{DocumentMut, Item, Key, Table};
+
+use crate::diff::{FieldPath, PathSegment};
+use crate::prelude::*;
+
+/// A field changed differently on both sides.
+///
+/// `None` means the field was removed on that side.
+#[derive(Debug, Clone)]
+pub struct MergeConflict {
+    pub path: FieldPath,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+/// Result of [merge_album].
+#[derive(Debug)]
+pub struct AlbumMerge {
+    /// Merged album. Conflicting fields keep the value of our side.
+    pub album: Album,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merge `ours` and `theirs`, which are derived from the common ancestor `base`.
+///
+/// `base` can be `None` if the album was added on both sides.
+pub fn merge_album(base: Option<&Album>, ours: &Album, theirs: &Album) -> RepoResult<AlbumMerge> {
+    let to_value = |album: &Album| Value::try_from(album).expect("album should be serializable");
+    let base = base.map(to_value);
+    let ours = to_value(ours);
+    let theirs = to_value(theirs);
+
+    let mut conflicts = Vec::new();
+    let merged = merge_value(
+        &FieldPath::default(),
+        base.as_ref(),
+        Some(&ours),
+        Some(&theirs),
+        &mut conflicts,
+    )
+    .unwrap_or(ours);
+    let album = merged
+        .clone()
+        .try_into()
+        .map_err(|err| Error::TomlParseError {
+            target: "Album",
+            input: toml::to_string_pretty(&merged).unwrap_or_default(),
+            err,
+        })?;
+
+    Ok(AlbumMerge { album, conflicts })
+}
+
+fn merge_value(
+    path: &FieldPath,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+
+    match (ours, theirs) {
+        (Some(Value::Table(ours)), Some(Value::Table(theirs))) => {
+            let base = base.and_then(Value::as_table);
+            let mut merged = toml::map::Map::new();
+            let keys = ours
+                .keys()
+                .chain(theirs.keys().filter(|key| !ours.contains_key(*key)));
+            for key in keys {
+                let value = merge_value(
+                    &path.join(PathSegment::Key(key.clone())),
+                    base.and_then(|base| base.get(key)),
+                    ours.get(key),
+                    theirs.get(key),
+                    conflicts,
+                );
+                if let Some(value) = value {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Table(merged))
+        }
+        // tags are unordered, apply additions and removals of both sides
+        (Some(Value::Array(ours)), Some(Value::Array(theirs))) if path.ends_with("tags") => {
+            let base = base.and_then(Value::as_array);
+            let in_base = |tag: &Value| base.map_or(false, |base| base.contains(tag));
+            let mut merged: Vec<_> = ours
+                .iter()
+                .filter(|tag| !in_base(tag) || theirs.contains(tag))
+                .cloned()
+                .collect();
+            for tag in theirs {
+                if !in_base(tag) && !merged.contains(tag) {
+                    merged.push(tag.clone());
+                }
+            }
+            Some(Value::Array(merged))
+        }
+        // discs and tracks are merged one by one if the count is not changed differently
+        (Some(Value::Array(ours)), Some(Value::Array(theirs)))
+            if ours.len() == theirs.len() && ours.iter().chain(theirs).all(Value::is_table) =>
+        {
+            let base = base.and_then(Value::as_array);
+            let merged = ours
+                .iter()
+                .zip(theirs)
+                .enumerate()
+                .map(|(i, (ours, theirs))| {
+                    merge_value(
+                        &path.join(PathSegment::Index(i)),
+                        base.and_then(|base| base.get(i)),
+                        Some(ours),
+                        Some(theirs),
+                        conflicts,
+                    )
+                    .unwrap_or_else(|| ours.clone())
+                })
+                .collect();
+            Some(Value::Array(merged))
+        }
+        _ => {
+            conflicts.push(MergeConflict {
+                path: path.clone(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+struct Markers {
+    ours: String,
+    separator: String,
+    theirs: String,
+}
+
+impl AlbumMerge {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Serialize merged album to toml, with git style conflict markers of `marker_size` around conflicting fields.
+    ///
+    /// Conflicting keys are wrapped line by line, while conflicting discs or tracks are wrapped as a whole.
+    pub fn to_toml_string(&self, marker_size: usize) -> String {
+        let content = self.album.clone().format_to_string();
+        if self.is_clean() {
+            return content;
+        }
+
+        let mut document: DocumentMut = content.parse().expect("serialized album should be valid");
+        let markers = Markers {
+            ours: format!("{} ours\n", "<".repeat(marker_size)),
+            separator: format!("{}\n", "=".repeat(marker_size)),
+            theirs: format!("{} theirs\n", ">".repeat(marker_size)),
+        };
+        for conflict in self.conflicts.iter() {
+            write_conflict(&mut document, conflict, &markers);
+        }
+        document.to_string()
+    }
+}
+
+fn write_conflict(document: &mut DocumentMut, conflict: &MergeConflict, markers: &Markers) {
+    let Some((PathSegment::Key(key), parent)) = conflict.path.segments().split_last() else {
+        return;
+    };
+    let Some(table) = table_mut(document.as_table_mut(), parent) else {
+        return;
+    };
+
+    if let Some(Item::ArrayOfTables(tables)) = table.get_mut(key) {
+        // wrap all tables, and put their side after the last table
+        if let Some(first) = tables.get_mut(0) {
+            let prefix = decor_prefix(first.decor().prefix());
+            first
+                .decor_mut()
+                .set_prefix(format!("{prefix}{}", markers.ours));
+        }
+        let theirs = conflict
+            .theirs
+            .as_ref()
+            .map(|theirs| tables_to_string(conflict.path.segments(), theirs))
+            .unwrap_or_default();
+        let end = format!("{}{theirs}{}", markers.separator, markers.theirs);
+
+        // the next table is the next sibling of parent table, or there's nothing after the tables
+        let sibling = match parent.split_last() {
+            Some((PathSegment::Index(index), grandparent)) => {
+                let mut path = grandparent.to_vec();
+                path.push(PathSegment::Index(index + 1));
+                table_mut(document.as_table_mut(), &path)
+            }
+            _ => None,
+        };
+        match sibling {
+            Some(sibling) => {
+                let prefix = decor_prefix(sibling.decor().prefix());
+                sibling.decor_mut().set_prefix(format!("{end}{prefix}"));
+            }
+            None => {
+                let trailing = document.trailing().as_str().unwrap_or_default().to_string();
+                document.set_trailing(format!("{trailing}{end}"));
+            }
+        }
+        return;
+    }
+
+    let theirs = conflict
+        .theirs
+        .as_ref()
+        .map(|theirs| format!("{} = {theirs}\n", Key::new(key.as_str()).display_repr()))
+        .unwrap_or_default();
+    let (prefix, suffix) = match table.get_mut(key) {
+        Some(item) => {
+            if item.is_table() {
+                // nested tables can not be wrapped by markers, use inline table instead
+                *item = std::mem::take(item)
+                    .into_value()
+                    .map(Item::Value)
+                    .unwrap_or_else(|item| item);
+            }
+            (
+                markers.ours.clone(),
+                format!("\n{}{theirs}{}", markers.separator, markers.theirs),
+            )
+        }
+        None => {
+            // our side removed the field, show their side only
+            let Some(value) = conflict
+                .theirs
+                .as_ref()
+                .and_then(|theirs| theirs.to_string().parse::<toml_edit::Value>().ok())
+            else {
+                return;
+            };
+            table.insert(key, Item::Value(value));
+            (
+                format!("{}{}", markers.ours, markers.separator),
+                format!("\n{}", markers.theirs),
+            )
+        }
+    };
+
+    if let Some(mut key) = table.key_mut(key) {
+        let decor = key.leaf_decor_mut();
+        let existing = decor_prefix(decor.prefix());
+        decor.set_prefix(format!("{existing}{prefix}"));
+    }
+    if let Some(value) = table.get_mut(key).and_then(Item::as_value_mut) {
+        // the line break after value is written by toml_edit
+        value
+            .decor_mut()
+            .set_suffix(suffix.strip_suffix('\n').unwrap_or(&suffix).to_string());
+    }
+}
+
+fn decor_prefix(prefix: Option<&toml_edit::RawString>) -> String {
+    prefix
+        .and_then(|prefix| prefix.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Find table at `path`, where index segments refer to tables in array of tables.
+fn table_mut<'a>(mut table: &'a mut Table, path: &[PathSegment]) -> Option<&'a mut Table> {
+    let mut segments = path.iter().peekable();
+    while let Some(segment) = segments.next() {
+        let PathSegment::Key(key) = segment else {
+            return None;
+        };
+        let item = table.get_mut(key)?;
+        table = match segments.peek() {
+            Some(PathSegment::Index(index)) => {
+                segments.next();
+                item.as_array_of_tables_mut()?.get_mut(*index)?
+            }
+            _ => item.as_table_mut()?,
+        };
+    }
+    Some(table)
+}
+
+/// Serialize discs or tracks at `path`, with headers of their full path.
+///
+/// Values are converted back to [Disc] or [Track] to keep the field order of album files.
+fn tables_to_string(path: &[PathSegment], tables: &Value) -> String {
+    #[derive(Serialize)]
+    struct Discs<T> {
+        discs: T,
+    }
+
+    #[derive(Serialize)]
+    struct Tracks {
+        tracks: Vec<Track>,
+    }
+
+    let (header, content) = match path {
+        [PathSegment::Key(_)] => {
+            let discs: Option<Vec<Disc>> = tables.clone().try_into().ok();
+            (
+                "[[discs]]",
+                discs.map(|discs| toml::to_string_pretty(&Discs { discs })),
+            )
+        }
+        _ => {
+            let tracks: Option<Vec<Track>> = tables.clone().try_into().ok();
+            (
+                "[[discs.tracks]]",
+                tracks.map(|tracks| {
+                    toml::to_string_pretty(&Discs {
+                        discs: [Tracks { tracks }],
+                    })
+                }),
+            )
+        }
+    };
+    let content = content.and_then(Result::ok).unwrap_or_default();
+
+    // skip headers of parent tables
+    match content.find(header) {
+        Some(start) => content[start..].to_string(),
+        None => content,
+    }
+}
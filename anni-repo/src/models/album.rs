@@ -1,19 +1,36 @@
 use crate::prelude::*;
-use anni_metadata::model::{Album, Disc, Tag, TagString, TagType, Track, TrackType};
+use anni_metadata::model::{
+    Album, ArtistCredits, Disc, Tag, TagRef, TagString, TagType, Track, TrackType,
+};
 use std::collections::HashMap;
 
+/// Alias name -> Tag type -> Aliased tag
+pub type TagAliases = HashMap<String, HashMap<TagType, TagRef<'static>>>;
+
+/// Fill in types of tags written without type, and replace aliases with the tags they refer to.
 pub trait ResolveTags {
-    fn resolve_tags(&mut self, tags: &HashMap<String, HashMap<TagType, Tag>>) -> RepoResult<()>;
+    fn resolve_tags(
+        &mut self,
+        tags: &HashMap<String, HashMap<TagType, Tag>>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()>;
 }
 
 impl ResolveTags for Album {
-    fn resolve_tags(&mut self, tags: &HashMap<String, HashMap<TagType, Tag>>) -> RepoResult<()> {
+    fn resolve_tags(
+        &mut self,
+        tags: &HashMap<String, HashMap<TagType, Tag>>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()> {
         for tag in self.info.tags.iter_mut() {
-            tag.resolve_tags(tags)?;
+            tag.resolve_tags(tags, aliases)?;
+        }
+        if let Some(artists) = self.info.artists.as_mut() {
+            artists.resolve_tags(tags, aliases)?;
         }
 
         for disc in self.discs.iter_mut() {
-            disc.resolve_tags(tags)?;
+            disc.resolve_tags(tags, aliases)?;
         }
 
         Ok(())
@@ -21,12 +38,19 @@ impl ResolveTags for Album {
 }
 
 impl ResolveTags for Disc {
-    fn resolve_tags(&mut self, tags: &HashMap<String, HashMap<TagType, Tag>>) -> RepoResult<()> {
+    fn resolve_tags(
+        &mut self,
+        tags: &HashMap<String, HashMap<TagType, Tag>>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()> {
         for tag in self.tags.iter_mut() {
-            tag.resolve_tags(tags)?;
+            tag.resolve_tags(tags, aliases)?;
+        }
+        if let Some(artists) = self.artists.as_mut() {
+            artists.resolve_tags(tags, aliases)?;
         }
         for track in self.tracks.iter_mut() {
-            track.resolve_tags(tags)?;
+            track.resolve_tags(tags, aliases)?;
         }
 
         Ok(())
@@ -34,9 +58,32 @@ impl ResolveTags for Disc {
 }
 
 impl ResolveTags for Track {
-    fn resolve_tags(&mut self, tags: &HashMap<String, HashMap<TagType, Tag>>) -> RepoResult<()> {
+    fn resolve_tags(
+        &mut self,
+        tags: &HashMap<String, HashMap<TagType, Tag>>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()> {
         for tag in self.tags.iter_mut() {
-            tag.resolve_tags(tags)?;
+            tag.resolve_tags(tags, aliases)?;
+        }
+        if let Some(artists) = self.artists.as_mut() {
+            artists.resolve_tags(tags, aliases)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ResolveTags for ArtistCredits {
+    fn resolve_tags(
+        &mut self,
+        tags: &HashMap<String, HashMap<TagType, Tag>>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()> {
+        for credit in self.iter_mut() {
+            if let Some(tag) = credit.tag.as_mut() {
+                tag.resolve_tags(tags, aliases)?;
+            }
         }
 
         Ok(())
@@ -44,37 +91,85 @@ impl ResolveTags for Track {
 }
 
 impl ResolveTags for TagString {
-    fn resolve_tags(&mut self, tags: &HashMap<String, HashMap<TagType, Tag>>) -> RepoResult<()> {
+    fn resolve_tags(
+        &mut self,
+        tags: &HashMap<String, HashMap<TagType, Tag>>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()> {
         if let TagType::Unknown = self.tag_type() {
-            if let Some(tags) = tags.get(self.name()) {
-                if tags.len() > 1 {
-                    return Err(Error::RepoTagDuplicated(self.full_clone()));
-                }
-
-                let actual_type = tags.values().next().unwrap().tag_type().clone();
-                self.set_tag_type(actual_type);
+            let defined = tags
+                .get(self.name())
+                .into_iter()
+                .flat_map(|tags| tags.keys());
+            let aliased = aliases
+                .get(self.name())
+                .into_iter()
+                .flat_map(|aliases| aliases.values());
+            let candidates: Vec<_> = defined
+                .map(|tag_type| TagRef::new(self.name().to_string(), tag_type.clone()))
+                .chain(aliased.cloned())
+                .collect();
+            match candidates.as_slice() {
+                [] => {}
+                [tag] => *self = tag.clone().into(),
+                _ => return Err(Error::RepoTagDuplicated(self.full_clone())),
             }
+        } else if let Some(tag) = aliases
+            .get(self.name())
+            .and_then(|aliases| aliases.get(self.tag_type()))
+        {
+            *self = tag.clone().into();
         }
 
         Ok(())
     }
 }
 
+/// Apply album metadata to flac files.
+///
+/// Titles and artists are written in the first available locale of `locales`,
+/// or the original ones if `locales` is empty.
+///
+/// External identifiers are written with keys used by MusicBrainz Picard, such as `ISRC` and `MUSICBRAINZ_ALBUMID`.
 #[cfg(feature = "apply")]
 pub trait ApplyMetadata {
     fn apply_strict<P>(
         &self,
         directory: P,
         detailed: bool,
+        locales: &[&str],
     ) -> Result<(), crate::error::AlbumApplyError>
     where
         P: AsRef<std::path::Path>;
 
-    fn apply_convention<P>(&self, directory: P) -> Result<(), crate::error::AlbumApplyError>
+    fn apply_convention<P>(
+        &self,
+        directory: P,
+        locales: &[&str],
+    ) -> Result<(), crate::error::AlbumApplyError>
     where
         P: AsRef<std::path::Path>;
 }
 
+/// Vorbis comments of external identifiers.
+#[cfg(feature = "apply")]
+fn id_comments(ids: &anni_metadata::model::ExternalIds) -> Vec<String> {
+    ids.iter()
+        .map(|(kind, value)| {
+            let key = match kind {
+                "barcode" => "BARCODE",
+                "isrc" => "ISRC",
+                "musicbrainz-release" => "MUSICBRAINZ_ALBUMID",
+                "musicbrainz-release-group" => "MUSICBRAINZ_RELEASEGROUPID",
+                "musicbrainz-recording" => "MUSICBRAINZ_TRACKID",
+                "musicbrainz-track" => "MUSICBRAINZ_RELEASETRACKID",
+                _ => unreachable!(),
+            };
+            format!("{key}={value}")
+        })
+        .collect()
+}
+
 #[cfg(feature = "apply")]
 impl ApplyMetadata for Album {
     /// Apply album metadata to a directory formatted with strict album format.
@@ -86,6 +181,7 @@ impl ApplyMetadata for Album {
         &self,
         directory: P,
         detailed: bool,
+        locales: &[&str],
     ) -> Result<(), crate::error::AlbumApplyError>
     where
         P: AsRef<std::path::Path>,
@@ -174,13 +270,19 @@ impl ApplyMetadata for Album {
     DISCNUMBER={disc_number}
     DISCTOTAL={disc_total}
     "#,
-                    title = track.title(),
-                    album = disc.title(),
-                    artist = track.artist(),
+                    title = track.title_in(locales),
+                    album = disc.title_in(locales),
+                    artist = track.artist_in(locales),
                     release_date = self.release_date(),
                     track_number = track_num,
                     disc_number = disc_num,
                 );
+                let ids = if detailed {
+                    id_comments(&track.ids())
+                } else {
+                    Vec::new()
+                };
+                let meta = ids.iter().fold(meta, |meta, id| meta + id + "\n");
 
                 // let mut modified = false;
                 // no comment block exist, or comments is not correct
@@ -190,15 +292,18 @@ impl ApplyMetadata for Album {
                     comments.clear();
 
                     if detailed {
-                        comments.push(UserComment::title(track.title()));
-                        comments.push(UserComment::album(disc.title()));
-                        comments.push(UserComment::artist(track.artist()));
+                        comments.push(UserComment::title(track.title_in(locales)));
+                        comments.push(UserComment::album(disc.title_in(locales)));
+                        comments.push(UserComment::artist(track.artist_in(locales)));
                         comments.push(UserComment::date(self.release_date()));
                     }
                     comments.push(UserComment::track_number(track_num));
                     comments.push(UserComment::track_total(track_total));
                     comments.push(UserComment::disc_number(disc_num));
                     comments.push(UserComment::disc_total(disc_total));
+                    for id in ids {
+                        comments.push(UserComment::new(id));
+                    }
                     // modified = true;
                 }
 
@@ -229,7 +334,11 @@ impl ApplyMetadata for Album {
     /// Apply album metadata to a directory formatted with **convention album format**.
     ///
     /// This function applies metadata only. Cover is not checked
-    fn apply_convention<P>(&self, directory: P) -> Result<(), crate::error::AlbumApplyError>
+    fn apply_convention<P>(
+        &self,
+        directory: P,
+        locales: &[&str],
+    ) -> Result<(), crate::error::AlbumApplyError>
     where
         P: AsRef<std::path::Path>,
     {
@@ -286,27 +395,32 @@ TRACKTOTAL={track_total}
 DISCNUMBER={disc_number}
 DISCTOTAL={disc_total}
 "#,
-                    title = track.title(),
-                    album = disc.title(),
-                    artist = track.artist(),
+                    title = track.title_in(locales),
+                    album = disc.title_in(locales),
+                    artist = track.artist_in(locales),
                     release_date = self.release_date(),
                     track_number = track_num,
                     track_total = track_total,
                     disc_number = disc_num,
                     disc_total = disc_total,
                 );
+                let ids = id_comments(&track.ids());
+                let meta = ids.iter().fold(meta, |meta, id| meta + id + "\n");
                 // no comment block exist, or comments is not correct
                 if comments.is_none() || comments.unwrap().to_string() != meta {
                     let comments = flac.comments_mut();
                     comments.clear();
-                    comments.push(UserComment::title(track.title()));
-                    comments.push(UserComment::album(disc.title()));
-                    comments.push(UserComment::artist(track.artist()));
+                    comments.push(UserComment::title(track.title_in(locales)));
+                    comments.push(UserComment::album(disc.title_in(locales)));
+                    comments.push(UserComment::artist(track.artist_in(locales)));
                     comments.push(UserComment::date(self.release_date()));
                     comments.push(UserComment::track_number(track_num));
                     comments.push(UserComment::track_total(track_total));
                     comments.push(UserComment::disc_number(disc_num));
                     comments.push(UserComment::disc_total(disc_total));
+                    for id in ids {
+                        comments.push(UserComment::new(id));
+                    }
                     flac.save::<String>(None)?;
                 }
             }
@@ -342,13 +456,15 @@ impl From<anni_flac::FlacHeader> for RepoTrack {
                     .unwrap_or_default();
                 // auto audio type for instrumental, drama and radio
                 let track_type = TrackType::guess(&title);
-                RepoTrack(Track::new(
+                let mut track = Track::new(
                     title,
                     map.get("ARTIST").map(|v| v.value().to_string()),
                     None,
                     track_type,
                     Default::default(),
-                ))
+                );
+                track.ids.isrc = map.get("ISRC").map(|v| v.value().to_string());
+                RepoTrack(track)
             }
             None => RepoTrack(Track::empty()),
         }
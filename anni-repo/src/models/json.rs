@@ -1,7 +1,10 @@
-use std::{collections::HashMap, str::FromStr};
+use std::str::FromStr;
 
 use crate::error::Error;
-use anni_metadata::model::{Album, AlbumInfo, AnniDate, Disc, TagString, TrackType};
+use anni_metadata::model::{
+    Album, AlbumInfo, AnniDate, ArtistCredits, Disc, ExternalIds, Localized, TagString, TrackType,
+    ALBUM_SCHEMA_VERSION,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -34,7 +37,7 @@ pub struct JsonAlbumInfo {
     /// Album artists
     #[serde(default)]
     #[serde(skip_serializing_if = "is_artists_empty")]
-    pub artists: Option<HashMap<String, String>>,
+    pub artists: Option<ArtistCredits>,
     /// Album release date
     #[serde(rename = "date")]
     pub release_date: String,
@@ -46,6 +49,14 @@ pub struct JsonAlbumInfo {
     /// Album tags
     #[serde(default)]
     pub tags: Vec<TagString>,
+    /// Localized album titles and artists
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Localized::is_empty")]
+    pub localized: Localized,
+    /// External identifiers of the album
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ExternalIds::is_empty")]
+    pub ids: ExternalIds,
 }
 
 impl FromStr for JsonAlbum {
@@ -74,6 +85,8 @@ impl From<Album> for JsonAlbum {
             album_type,
             catalog,
             tags,
+            localized,
+            ids,
         } = album.info;
         JsonAlbum {
             info: JsonAlbumInfo {
@@ -86,6 +99,8 @@ impl From<Album> for JsonAlbum {
                 album_type,
                 catalog,
                 tags,
+                localized,
+                ids,
             },
             discs: album.discs,
         }
@@ -106,8 +121,11 @@ impl TryFrom<JsonAlbum> for Album {
             album_type,
             catalog,
             tags,
+            localized,
+            ids,
         } = album.info;
         Ok(Album {
+            schema: ALBUM_SCHEMA_VERSION,
             info: AlbumInfo {
                 album_id,
                 title,
@@ -118,12 +136,60 @@ impl TryFrom<JsonAlbum> for Album {
                 album_type,
                 catalog,
                 tags,
+                localized,
+                ids,
             },
             discs: album.discs,
         })
     }
 }
 
+/// Convert album to json in the same structure as album files, with dates written as strings.
+///
+/// The result is described by the JSON Schema of albums, and can be read by [albums_from_json].
+pub fn album_to_json(album: &Album) -> serde_json::Value {
+    // serialize through text, as dates can only be serialized by toml serializer
+    let album = toml::to_string(album).unwrap();
+    toml_to_json(toml::from_str(&album).unwrap())
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(value) => value.into(),
+        toml::Value::Integer(value) => value.into(),
+        toml::Value::Float(value) => value.into(),
+        toml::Value::Boolean(value) => value.into(),
+        toml::Value::Datetime(value) => value.to_string().into(),
+        toml::Value::Array(values) => values.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect(),
+    }
+}
+
+/// Parse albums exported by [album_to_json], or in the format of [JsonAlbum].
+///
+/// `input` can be either an album or an array of albums.
+pub fn albums_from_json(input: &str) -> Result<Vec<Album>, Error> {
+    match serde_json::from_str(input)? {
+        serde_json::Value::Array(values) => values.into_iter().map(album_from_json).collect(),
+        value => Ok(vec![album_from_json(value)?]),
+    }
+}
+
+fn album_from_json(value: serde_json::Value) -> Result<Album, Error> {
+    if value.get("album").is_none() {
+        let album: JsonAlbum = serde_json::from_value(value)?;
+        return album.try_into();
+    }
+
+    if let Some(schema) = value.get("schema").and_then(serde_json::Value::as_u64) {
+        Album::check_schema(u32::try_from(schema).unwrap_or(u32::MAX))?;
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
 mod test {
     #[test]
     fn test_json_album_serialize_deserialize() {
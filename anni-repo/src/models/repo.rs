@@ -3,14 +3,19 @@ use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Repository {
     repo: RepositoryInner,
 }
 
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct RepositoryInner {
+    /// Repository name
     name: String,
+    /// Edition of repository
     edition: String,
+    /// Folders of album files, relative to repository root
     #[serde(default = "default_albums")]
     albums: Vec<String>,
 }
@@ -51,3 +56,9 @@ impl Repository {
         self.repo.albums.as_ref()
     }
 }
+
+/// JSON Schema of `repo.toml`.
+#[cfg(feature = "schema")]
+pub fn repository_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(Repository)
+}
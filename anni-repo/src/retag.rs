@@ -0,0 +1,268 @@
+//! Rewriting tag references in album and tag files.
+//!
+//! Files are edited with `toml_edit`, so that formatting and comments are kept.
+use anni_metadata::model::{Tag, TagRef, TagType};
+use std::str::FromStr;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::prelude::*;
+
+/// Replace references to `from` with `to` in an album or tag file.
+///
+/// References are album, disc and track `tags`, `tag` of artist credits, and `included-by` and `includes` of tags.
+/// References written without tag type are replaced if their names match.
+/// If `to` is already referenced in the same list, the replaced reference is removed instead.
+///
+/// Returns `None` if `input` does not refer to `from`.
+pub fn replace_tag_references(
+    input: &str,
+    from: &TagRef<'_>,
+    to: &TagRef<'_>,
+) -> RepoResult<Option<String>> {
+    let mut document: DocumentMut = input.parse()?;
+    let mut replacer = Replacer {
+        from,
+        to,
+        changed: false,
+    };
+    replacer.visit_table(document.as_table_mut());
+    Ok(replacer.changed.then(|| document.to_string()))
+}
+
+/// Change name and type of tag `from` to those of `to`, if it is defined in this tag file.
+pub fn rename_tag_definition(
+    input: &str,
+    from: &TagRef<'_>,
+    to: &TagRef<'_>,
+) -> RepoResult<Option<String>> {
+    let mut document: DocumentMut = input.parse()?;
+    let Some(definition) = tag_definitions(&mut document)
+        .and_then(|tags| tags.iter_mut().find(|table| is_definition(table, from)))
+    else {
+        return Ok(None);
+    };
+
+    set_string(&mut definition["name"], to.name());
+    if to.tag_type() != from.tag_type() {
+        set_string(&mut definition["type"], to.tag_type().as_ref());
+    }
+    Ok(Some(document.to_string()))
+}
+
+/// Remove definition of tag `tag`, if it is defined in this tag file.
+pub fn remove_tag_definition(input: &str, tag: &TagRef<'_>) -> RepoResult<Option<String>> {
+    let mut document: DocumentMut = input.parse()?;
+    let Some(tags) = tag_definitions(&mut document) else {
+        return Ok(None);
+    };
+    let Some(index) = tags.iter().position(|table| is_definition(table, tag)) else {
+        return Ok(None);
+    };
+
+    tags.remove(index);
+    Ok(Some(document.to_string()))
+}
+
+/// Merge `tag` into definition of tag `into`, if it is defined in this tag file.
+///
+/// Name and aliases of `tag` become aliases of `into`.
+/// Localized names, parents and children of `tag` which `into` does not have are added to it.
+pub fn merge_tag_definition(input: &str, into: &Tag, tag: &Tag) -> RepoResult<Option<String>> {
+    let mut document: DocumentMut = input.parse()?;
+    let Some(definition) = tag_definitions(&mut document).and_then(|tags| {
+        tags.iter_mut()
+            .find(|table| is_definition(table, into.as_ref()))
+    }) else {
+        return Ok(None);
+    };
+
+    let aliases = std::iter::once(tag.name())
+        .chain(tag.aliases().iter().map(String::as_str))
+        .filter(|alias| *alias != into.name());
+    extend_array(definition, "aliases", aliases.map(str::to_string), str::eq);
+
+    if !tag.names().is_empty() {
+        if let Some(names) = definition
+            .entry("names")
+            .or_insert(Item::Value(Value::InlineTable(InlineTable::new())))
+            .as_table_like_mut()
+        {
+            for (language, name) in tag.names() {
+                if !names.contains_key(language) {
+                    names.insert(language, toml_edit::value(name.as_str()));
+                }
+            }
+        }
+    }
+
+    let parents = tag.parents().filter(|parent| {
+        !references(parent, into.as_ref()) && !into.parents().any(|p| p == *parent)
+    });
+    extend_array(
+        definition,
+        "included-by",
+        parents.map(ToString::to_string),
+        same_tag,
+    );
+    let children = tag.simple_children().filter(|child| {
+        !references(child, into.as_ref()) && !into.simple_children().any(|c| c == *child)
+    });
+    extend_array(
+        definition,
+        "includes",
+        children.map(ToString::to_string),
+        same_tag,
+    );
+
+    Ok(Some(document.to_string()))
+}
+
+struct Replacer<'a, 'from, 'to> {
+    from: &'a TagRef<'from>,
+    to: &'a TagRef<'to>,
+    changed: bool,
+}
+
+impl Replacer<'_, '_, '_> {
+    fn visit_table(&mut self, table: &mut Table) {
+        for (key, item) in table.iter_mut() {
+            self.visit_item(key.get(), item);
+        }
+    }
+
+    fn visit_item(&mut self, key: &str, item: &mut Item) {
+        match item {
+            Item::Value(value) => self.visit_value(Some(key), value),
+            Item::Table(table) => self.visit_table(table),
+            Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    self.visit_table(table);
+                }
+            }
+            Item::None => {}
+        }
+    }
+
+    fn visit_value(&mut self, key: Option<&str>, value: &mut Value) {
+        match (key, value) {
+            (Some("tags" | "included-by" | "includes"), Value::Array(array)) => {
+                self.replace_in_array(array)
+            }
+            (Some("tag"), value @ Value::String(_))
+                if value.as_str().is_some_and(|tag| self.is_from(tag)) =>
+            {
+                set_value(value, &self.to.to_string());
+                self.changed = true;
+            }
+            (_, Value::Array(array)) => {
+                for value in array.iter_mut() {
+                    self.visit_value(None, value);
+                }
+            }
+            (_, Value::InlineTable(table)) => {
+                for (key, value) in table.iter_mut() {
+                    self.visit_value(Some(key.get()), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn replace_in_array(&mut self, array: &mut Array) {
+        let mut index = 0;
+        while index < array.len() {
+            if !array
+                .get(index)
+                .and_then(Value::as_str)
+                .is_some_and(|tag| self.is_from(tag))
+            {
+                index += 1;
+                continue;
+            }
+
+            self.changed = true;
+            let exists = array.iter().enumerate().any(|(i, value)| {
+                i != index
+                    && value
+                        .as_str()
+                        .is_some_and(|tag| references(&TagRef::from_cow_str(tag), self.to))
+            });
+            if exists {
+                array.remove(index);
+            } else {
+                set_value(array.get_mut(index).unwrap(), &self.to.to_string());
+                index += 1;
+            }
+        }
+    }
+
+    fn is_from(&self, tag: &str) -> bool {
+        references(&TagRef::from_cow_str(tag), self.from)
+    }
+}
+
+/// Whether `reference` refers to `tag`. References without tag type refer to tags with the same name.
+fn references(reference: &TagRef<'_>, tag: &TagRef<'_>) -> bool {
+    reference.name() == tag.name()
+        && (reference.tag_type() == tag.tag_type() || reference.tag_type() == &TagType::Unknown)
+}
+
+fn same_tag(a: &str, b: &str) -> bool {
+    references(&TagRef::from_cow_str(a), &TagRef::from_cow_str(b))
+}
+
+fn tag_definitions(document: &mut DocumentMut) -> Option<&mut ArrayOfTables> {
+    document.get_mut("tag")?.as_array_of_tables_mut()
+}
+
+fn is_definition(table: &Table, tag: &TagRef<'_>) -> bool {
+    let name = table.get("name").and_then(Item::as_str);
+    let tag_type = table
+        .get("type")
+        .and_then(Item::as_str)
+        .and_then(|tag_type| TagType::from_str(tag_type).ok());
+    name == Some(tag.name()) && tag_type.as_ref() == Some(tag.tag_type())
+}
+
+/// Append `values` to array `key` of `table`, skipping values which are already in it.
+fn extend_array<I, F>(table: &mut Table, key: &str, values: I, same: F)
+where
+    I: Iterator<Item = String>,
+    F: Fn(&str, &str) -> bool,
+{
+    let mut values = values.peekable();
+    if values.peek().is_none() {
+        return;
+    }
+
+    let Some(array) = table
+        .entry(key)
+        .or_insert(toml_edit::value(Array::new()))
+        .as_array_mut()
+    else {
+        return;
+    };
+    for value in values {
+        let exists = array
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|existing| same(existing, &value));
+        if !exists {
+            array.push(value);
+        }
+    }
+}
+
+/// Replace string `item`, keeping its surrounding whitespaces and comments.
+fn set_string(item: &mut Item, value: &str) {
+    match item.as_value_mut() {
+        Some(old) => set_value(old, value),
+        None => *item = toml_edit::value(value),
+    }
+}
+
+fn set_value(old: &mut Value, value: &str) {
+    let decor = old.decor().clone();
+    *old = Value::from(value);
+    *old.decor_mut() = decor;
+}
@@ -0,0 +1,136 @@
+//! Versioning and migration of album files.
+//!
+//! Album files record their schema version in the top-level `schema` key, see [ALBUM_SCHEMA_VERSION].
+//! Older files are upgraded by applying migrations one version at a time.
+//! Files are edited with `toml_edit`, so that formatting and comments are kept.
+use anni_metadata::error::Error as MetadataError;
+use anni_metadata::model::ALBUM_SCHEMA_VERSION;
+use toml_edit::{Array, DocumentMut, Item, TableLike, Value};
+
+use crate::prelude::*;
+
+/// A step which upgrades album files to schema version `to` from the version before it.
+struct Migration {
+    to: u32,
+    migrate: fn(&mut DocumentMut) -> RepoResult<()>,
+}
+
+/// Migrations in order of version. The last one must upgrade to [ALBUM_SCHEMA_VERSION].
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    migrate: migrate_v2,
+}];
+
+/// Separator of artist names joined in one credit before version 2.
+const JOINED_ARTIST_SEPARATOR: char = '、';
+
+/// Get schema version of an album file. Files without `schema` are of version 1.
+pub fn album_schema(input: &str) -> RepoResult<u32> {
+    let document: DocumentMut = input.parse()?;
+    schema_of(&document)
+}
+
+/// Upgrade an album file to [ALBUM_SCHEMA_VERSION].
+///
+/// Returns `None` if the file is already of the latest version,
+/// or an error if it is newer than this version of anni supports.
+pub fn upgrade_album(input: &str) -> RepoResult<Option<String>> {
+    let mut document: DocumentMut = input.parse()?;
+    let schema = schema_of(&document)?;
+    if schema > ALBUM_SCHEMA_VERSION {
+        return Err(MetadataError::UnsupportedAlbumSchema {
+            version: schema,
+            supported: ALBUM_SCHEMA_VERSION,
+        }
+        .into());
+    }
+    if schema == ALBUM_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.to > schema) {
+        (migration.migrate)(&mut document)?;
+        set_schema(&mut document, migration.to);
+    }
+    Ok(Some(document.to_string()))
+}
+
+fn schema_of(document: &DocumentMut) -> RepoResult<u32> {
+    match document.get("schema") {
+        None => Ok(1),
+        Some(item) => item
+            .as_integer()
+            .and_then(|schema| u32::try_from(schema).ok())
+            .ok_or_else(|| Error::InvalidAlbumSchema(item.to_string().trim().to_string())),
+    }
+}
+
+/// Set `schema` of the file. Top-level keys are written before `[album]`.
+fn set_schema(document: &mut DocumentMut, schema: u32) {
+    let table = document.as_table_mut();
+    match table.get_mut("schema").and_then(Item::as_value_mut) {
+        Some(value) => {
+            let decor = value.decor().clone();
+            *value = Value::from(i64::from(schema));
+            *value.decor_mut() = decor;
+        }
+        None => {
+            table.insert("schema", toml_edit::value(i64::from(schema)));
+        }
+    }
+}
+
+/// Version 2 credits several artists of a role as an array, instead of joining their names.
+///
+/// Only plain names are split, as the type and tag of a detailed credit apply to the whole name.
+fn migrate_v2(document: &mut DocumentMut) -> RepoResult<()> {
+    if let Some(album) = document.get_mut("album").and_then(Item::as_table_like_mut) {
+        split_joined_artists(album);
+    }
+
+    let Some(discs) = document
+        .get_mut("discs")
+        .and_then(Item::as_array_of_tables_mut)
+    else {
+        return Ok(());
+    };
+    for disc in discs.iter_mut() {
+        split_joined_artists(disc);
+        if let Some(tracks) = disc
+            .get_mut("tracks")
+            .and_then(Item::as_array_of_tables_mut)
+        {
+            for track in tracks.iter_mut() {
+                split_joined_artists(track);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn split_joined_artists(table: &mut dyn TableLike) {
+    let Some(artists) = table.get_mut("artists").and_then(Item::as_table_like_mut) else {
+        return;
+    };
+
+    for (_, item) in artists.iter_mut() {
+        let Some(value) = item.as_value_mut() else {
+            continue;
+        };
+        let Some(names) = value
+            .as_str()
+            .filter(|names| names.contains(JOINED_ARTIST_SEPARATOR))
+        else {
+            continue;
+        };
+
+        let names: Array = names
+            .split(JOINED_ARTIST_SEPARATOR)
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        let decor = value.decor().clone();
+        *value = Value::Array(names);
+        *value.decor_mut() = decor;
+    }
+}
@@ -1,20 +1,27 @@
 use std::path::Path;
+use std::str::FromStr;
 
+use anni_metadata::model::{
+    Album, ArtistCredits, DiscRef, Localized, Tag, TagRef, TagString, TagType, TrackIdentifier,
+    TrackRef,
+};
 use lindera_core::mode::Mode;
 use lindera_dictionary::{load_dictionary_from_config, DictionaryConfig, DictionaryKind};
 use lindera_tantivy::tokenizer::LinderaTokenizer;
 use tantivy::{
+    collector::TopDocs,
     doc,
     query::QueryParser,
-    schema::{Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, STORED},
+    schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, STORED, STRING, TEXT,
+    },
+    tokenizer::{LowerCaser, TextAnalyzer, Tokenizer},
     Document, Index, TantivyError,
 };
 
 pub use tantivy;
 use uuid::Uuid;
 
-use crate::prelude::TrackIdentifier;
-
 pub struct RepositorySearchManager {
     pub index: Index,
     fields: SearchFields,
@@ -37,7 +44,7 @@ impl RepositorySearchManager {
         P: AsRef<Path>,
     {
         let index = Index::open_in_dir(directory_path)?;
-        let fields = SearchFields::from_index(&index);
+        let fields = SearchFields::from_index(&index)?;
         let me = Self { index, fields };
         me.register_tokenizers();
         Ok(me)
@@ -52,101 +59,327 @@ impl RepositorySearchManager {
 
         self.index.tokenizers().register(
             "lang_ja",
-            LinderaTokenizer::new(dictionary, None, Mode::Normal),
+            TextAnalyzer::builder(KanaFolding::new(LinderaTokenizer::new(
+                dictionary,
+                None,
+                Mode::Normal,
+            )))
+            .filter(LowerCaser)
+            .build(),
         );
     }
 
-    pub fn build_document(
-        &self,
-        title: &str,
-        artist: &str,
-        album_id: &Uuid,
-        disc_id: i64,
-        track_id: i64,
-    ) -> Document {
-        doc!(
-            self.fields.title => title,
-            self.fields.artist => artist,
+    pub fn build_album_document(&self, album: &Album) -> Document {
+        let mut doc = doc!(
+            self.fields.kind => "album",
+            self.fields.album_id => &album.album_id().as_bytes()[..],
+            self.fields.title => album.title_raw(),
+            self.fields.artist => album.artist(),
+            self.fields.catalog => album.catalog(),
+        );
+        if let Some(edition) = album.edition() {
+            doc.add_text(self.fields.edition, edition);
+        }
+        self.add_artists(&mut doc, album.artists.as_ref());
+        self.add_localized(&mut doc, &album.localized);
+        self.add_tags(&mut doc, album.album_tags());
+        doc
+    }
+
+    pub fn build_disc_document(&self, album_id: &Uuid, disc_id: u32, disc: &DiscRef) -> Document {
+        let mut doc = doc!(
+            self.fields.kind => "disc",
             self.fields.album_id => &album_id.as_bytes()[..],
-            self.fields.disc_id => disc_id,
-            self.fields.track_id => track_id,
-        )
+            self.fields.disc_id => disc_id as i64,
+            self.fields.title => disc.title(),
+            self.fields.artist => disc.artist(),
+            self.fields.catalog => disc.catalog(),
+        );
+        self.add_artists(&mut doc, disc.artists());
+        self.add_localized(&mut doc, &disc.raw().localized);
+        self.add_tags(&mut doc, disc.tags_iter());
+        doc
+    }
+
+    pub fn build_track_document(&self, track: TrackIdentifier, item: &TrackRef) -> Document {
+        let mut doc = doc!(
+            self.fields.kind => "track",
+            self.fields.album_id => &track.album_id.as_bytes()[..],
+            self.fields.disc_id => track.disc_id as i64,
+            self.fields.track_id => track.track_id as i64,
+            self.fields.title => item.title(),
+            self.fields.artist => item.artist(),
+        );
+        self.add_artists(&mut doc, item.artists());
+        self.add_localized(&mut doc, &item.raw().localized);
+        self.add_tags(&mut doc, item.tags_iter());
+        doc
+    }
+
+    /// Tag name is stored as the first title, followed by aliases and localized names.
+    pub fn build_tag_document(&self, tag: &Tag) -> Document {
+        let mut doc = doc!(
+            self.fields.kind => "tag",
+            self.fields.title => tag.name(),
+            self.fields.tag_type => tag.tag_type().as_ref(),
+        );
+        for name in tag.aliases().iter().chain(tag.names().values()) {
+            doc.add_text(self.fields.title, name);
+        }
+        doc
+    }
+
+    /// Credited names are indexed, with names of linked tags if they are different.
+    fn add_artists(&self, doc: &mut Document, artists: Option<&ArtistCredits>) {
+        for credit in artists.into_iter().flatten() {
+            doc.add_text(self.fields.artists, &credit.name);
+            if let Some(tag) = credit.tag.as_ref().filter(|tag| tag.name() != credit.name) {
+                doc.add_text(self.fields.artists, tag.name());
+            }
+        }
+    }
+
+    /// Localized titles and artists are indexed after the original ones,
+    /// so that the first title is always the original title.
+    fn add_localized(&self, doc: &mut Document, localized: &Localized) {
+        for (_, text) in localized.iter() {
+            if let Some(title) = &text.title {
+                doc.add_text(self.fields.title, title);
+            }
+            if let Some(artist) = &text.artist {
+                doc.add_text(self.fields.artist, artist);
+            }
+        }
+    }
+
+    fn add_tags<'a, 'tag: 'a>(
+        &self,
+        doc: &mut Document,
+        tags: impl IntoIterator<Item = &'a TagRef<'tag>>,
+    ) {
+        for tag in tags {
+            doc.add_text(self.fields.tags, tag.name());
+        }
     }
 
     pub fn build_query_parser(&self) -> QueryParser {
-        QueryParser::for_index(&self.index, vec![self.fields.title, self.fields.artist])
+        let fields = &self.fields;
+        let mut parser = QueryParser::for_index(
+            &self.index,
+            vec![
+                fields.title,
+                fields.edition,
+                fields.artist,
+                fields.artists,
+                fields.catalog,
+                fields.tags,
+            ],
+        );
+        parser.set_field_boost(fields.title, 2.0);
+        parser.set_field_boost(fields.catalog, 2.0);
+        parser
+    }
+
+    /// Search albums, discs, tracks and tags matching `query`, ordered by relevance.
+    ///
+    /// Invalid parts of `query` are ignored. If `fuzzy` is true, terms within one edit are matched as well.
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        fuzzy: bool,
+    ) -> Result<Vec<SearchResult>, TantivyError> {
+        let mut parser = self.build_query_parser();
+        if fuzzy {
+            for field in [
+                self.fields.title,
+                self.fields.edition,
+                self.fields.artist,
+                self.fields.artists,
+                self.fields.tags,
+            ] {
+                parser.set_field_fuzzy(field, false, 1, true);
+            }
+        }
+        let (query, errors) = parser.parse_query_lenient(query);
+        for error in errors {
+            log::debug!("Ignored invalid query: {error}");
+        }
+
+        let searcher = self.index.reader()?.searcher();
+        let docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+
+        let mut results = Vec::with_capacity(docs.len());
+        for (score, address) in docs {
+            let doc = searcher.doc(address)?;
+            let title = doc
+                .get_first(self.fields.title)
+                .and_then(|title| title.as_text())
+                .unwrap_or_default()
+                .to_string();
+            results.push(SearchResult {
+                score,
+                title,
+                hit: self.deserialize_document(doc),
+            });
+        }
+        Ok(results)
     }
 
-    pub fn deserialize_document(&self, doc: Document) -> TrackIdentifier {
-        let album_id = doc.get_first(self.fields.album_id).unwrap();
-        let disc_id = doc.get_first(self.fields.disc_id).unwrap();
-        let track_id = doc.get_first(self.fields.track_id).unwrap();
+    pub fn deserialize_document(&self, doc: Document) -> SearchHit {
+        let text = |field| doc.get_first(field).and_then(|v| v.as_text());
+        let int = |field| {
+            doc.get_first(field)
+                .and_then(|v| v.as_i64())
+                .unwrap_or_default() as u32
+        };
+        let album_id = || {
+            let album_id = doc.get_first(self.fields.album_id).unwrap();
+            Uuid::from_slice(album_id.as_bytes().unwrap()).unwrap()
+        };
 
-        TrackIdentifier {
-            album_id: Uuid::from_slice(album_id.as_bytes().unwrap()).unwrap(),
-            disc_id: disc_id.as_i64().unwrap() as u32,
-            track_id: track_id.as_i64().unwrap() as u32,
+        match text(self.fields.kind) {
+            Some("album") => SearchHit::Album(album_id()),
+            Some("disc") => SearchHit::Disc {
+                album_id: album_id(),
+                disc_id: int(self.fields.disc_id),
+            },
+            Some("tag") => SearchHit::Tag(TagString::new(
+                text(self.fields.title).unwrap_or_default().to_string(),
+                text(self.fields.tag_type)
+                    .and_then(|t| TagType::from_str(t).ok())
+                    .unwrap_or(TagType::Unknown),
+            )),
+            _ => SearchHit::Track(TrackIdentifier {
+                album_id: album_id(),
+                disc_id: int(self.fields.disc_id),
+                track_id: int(self.fields.track_id),
+            }),
         }
     }
 }
 
+/// Kind of item a search result refers to.
+#[derive(Debug)]
+pub enum SearchHit {
+    Album(Uuid),
+    Disc { album_id: Uuid, disc_id: u32 },
+    Track(TrackIdentifier),
+    Tag(TagString),
+}
+
+#[derive(Debug)]
+pub struct SearchResult {
+    pub score: f32,
+    /// Title of album, disc or track, or name of tag.
+    pub title: String,
+    pub hit: SearchHit,
+}
+
+/// Tokenizer wrapper which converts hiragana to katakana before tokenizing,
+/// so that kana variants of the same text produce the same tokens.
+#[derive(Clone)]
+struct KanaFolding<T> {
+    inner: T,
+    buffer: String,
+}
+
+impl<T> KanaFolding<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            buffer: String::new(),
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for KanaFolding<T> {
+    type TokenStream<'a> = T::TokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        // hiragana and katakana have the same length in utf-8, so offsets are kept
+        self.buffer.clear();
+        self.buffer.extend(text.chars().map(|c| match c {
+            '\u{3041}'..='\u{3096}' | '\u{309D}'..='\u{309E}' => {
+                char::from_u32(c as u32 + 0x60).unwrap_or(c)
+            }
+            c => c,
+        }));
+        self.inner.token_stream(&self.buffer)
+    }
+}
+
 struct SearchFields {
-    pub title: Field,
-    pub artist: Field,
+    pub kind: Field,
     pub album_id: Field,
     pub disc_id: Field,
     pub track_id: Field,
+
+    pub title: Field,
+    pub edition: Field,
+    pub artist: Field,
+    pub artists: Field,
+    pub catalog: Field,
+    pub tags: Field,
+    pub tag_type: Field,
 }
 
 impl SearchFields {
     pub fn new() -> (Schema, Self) {
         let mut schema_builder = Schema::builder();
-        let title = schema_builder.add_text_field(
-            "title",
-            TextOptions::default().set_indexing_options(
-                TextFieldIndexing::default()
-                    .set_tokenizer("lang_ja")
-                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
-            ),
-        );
-        let artist = schema_builder.add_text_field(
-            "artist",
-            TextOptions::default().set_indexing_options(
-                TextFieldIndexing::default()
-                    .set_tokenizer("lang_ja")
-                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
-            ),
+        let lang_ja = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("lang_ja")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
         );
+
+        let kind = schema_builder.add_text_field("kind", STRING | STORED);
         let album_id = schema_builder.add_bytes_field("album_id", STORED);
         let disc_id = schema_builder.add_i64_field("disc_id", STORED);
         let track_id = schema_builder.add_i64_field("track_id", STORED);
 
+        let title = schema_builder.add_text_field("title", lang_ja.clone() | STORED);
+        let edition = schema_builder.add_text_field("edition", lang_ja.clone());
+        let artist = schema_builder.add_text_field("artist", lang_ja.clone());
+        let artists = schema_builder.add_text_field("artists", lang_ja.clone());
+        let catalog = schema_builder.add_text_field("catalog", TEXT);
+        let tags = schema_builder.add_text_field("tags", lang_ja);
+        let tag_type = schema_builder.add_text_field("tag_type", STRING | STORED);
+
         (
             schema_builder.build(),
             Self {
-                title,
-                artist,
+                kind,
                 album_id,
                 disc_id,
                 track_id,
+                title,
+                edition,
+                artist,
+                artists,
+                catalog,
+                tags,
+                tag_type,
             },
         )
     }
 
-    fn from_index(index: &Index) -> Self {
+    fn from_index(index: &Index) -> Result<Self, TantivyError> {
         let schema = index.schema();
-        let title = schema.get_field("title").unwrap();
-        let artist = schema.get_field("artist").unwrap();
-        let album_id = schema.get_field("album_id").unwrap();
-        let disc_id = schema.get_field("disc_id").unwrap();
-        let track_id = schema.get_field("track_id").unwrap();
+        let field = |name| schema.get_field(name);
 
-        Self {
-            title,
-            artist,
-            album_id,
-            disc_id,
-            track_id,
-        }
+        Ok(Self {
+            kind: field("kind")?,
+            album_id: field("album_id")?,
+            disc_id: field("disc_id")?,
+            track_id: field("track_id")?,
+            title: field("title")?,
+            edition: field("edition")?,
+            artist: field("artist")?,
+            artists: field("artists")?,
+            catalog: field("catalog")?,
+            tags: field("tags")?,
+            tag_type: field("tag_type")?,
+        })
     }
 }
@@ -0,0 +1,112 @@
+//! Source locations of fields in album toml.
+use std::ops::Range;
+
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+use crate::diff::{FieldPath, PathSegment};
+use crate::prelude::*;
+
+/// Byte ranges of a field in album toml.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpan {
+    /// Range of the key. For tables in `[[discs]]` form, this is the range of the table.
+    pub key: Range<usize>,
+    /// Range of the value, including quotes of strings.
+    /// For tables in `[album]` form, this is the range from the header to the last field.
+    pub value: Range<usize>,
+}
+
+impl FieldSpan {
+    /// Range from the start of key to the end of value.
+    pub fn full(&self) -> Range<usize> {
+        self.key.start.min(self.value.start)..self.key.end.max(self.value.end)
+    }
+}
+
+/// Locations of all fields in an album toml, indexed by [FieldPath].
+#[derive(Debug, Default)]
+pub struct AlbumSpans(Vec<(FieldPath, FieldSpan)>);
+
+impl AlbumSpans {
+    /// Parse `input` and record locations of every key and value in it.
+    ///
+    /// Only toml syntax is checked here, `input` is not required to be a valid album.
+    pub fn parse(input: &str) -> RepoResult<Self> {
+        let document = ImDocument::parse(input)?;
+
+        let mut spans = Vec::new();
+        collect_table(&FieldPath::default(), document.as_table(), &mut spans);
+        Ok(Self(spans))
+    }
+
+    pub fn get(&self, path: &FieldPath) -> Option<&FieldSpan> {
+        self.0
+            .iter()
+            .find(|(field, _)| field == path)
+            .map(|(_, span)| span)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&FieldPath, &FieldSpan)> {
+        self.0.iter().map(|(path, span)| (path, span))
+    }
+}
+
+fn collect_table(path: &FieldPath, table: &dyn TableLike, spans: &mut Vec<(FieldPath, FieldSpan)>) {
+    for (name, _) in table.iter() {
+        let Some((key, item)) = table.get_key_value(name) else {
+            continue;
+        };
+        let path = path.join(PathSegment::Key(name.to_string()));
+        push_span(&path, key.span(), item.span(), spans);
+        collect_item(&path, item, spans);
+    }
+}
+
+fn collect_item(path: &FieldPath, item: &Item, spans: &mut Vec<(FieldPath, FieldSpan)>) {
+    match item {
+        Item::Table(table) => collect_table(path, table, spans),
+        Item::ArrayOfTables(tables) => {
+            for (i, table) in tables.iter().enumerate() {
+                let path = path.join(PathSegment::Index(i));
+                push_span(&path, table.span(), table.span(), spans);
+                collect_table(&path, table, spans);
+            }
+        }
+        Item::Value(value) => collect_value(path, value, spans),
+        Item::None => {}
+    }
+}
+
+fn collect_value(path: &FieldPath, value: &Value, spans: &mut Vec<(FieldPath, FieldSpan)>) {
+    match value {
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                let path = path.join(PathSegment::Index(i));
+                push_span(&path, value.span(), value.span(), spans);
+                collect_value(&path, value, spans);
+            }
+        }
+        Value::InlineTable(table) => collect_table(path, table, spans),
+        _ => {}
+    }
+}
+
+/// Record span of field at `path`, using the key span as value span if the field has no value span.
+///
+/// Tables created implicitly only have the key span in header, such as `localized` in `[album.localized.en]`.
+fn push_span(
+    path: &FieldPath,
+    key: Option<Range<usize>>,
+    value: Option<Range<usize>>,
+    spans: &mut Vec<(FieldPath, FieldSpan)>,
+) {
+    if let Some(span) = key.as_ref().or(value.as_ref()) {
+        spans.push((
+            path.clone(),
+            FieldSpan {
+                key: key.clone().unwrap_or_else(|| span.clone()),
+                value: value.clone().unwrap_or_else(|| span.clone()),
+            },
+        ));
+    }
+}
@@ -1,6 +1,13 @@
+use crate::merge::merge_album;
+use crate::prelude::RepoResult;
+use anni_metadata::model::Album;
 use git2::Repository;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Stage bits of `IndexEntry::flags`, non-zero for conflicting entries.
+const INDEX_ENTRY_STAGE_MASK: u16 = 0x3000;
 
 #[cfg(feature = "git")]
 pub fn setup_git2(proxy: Option<String>) {
@@ -110,10 +117,23 @@ fn normal_merge(
         .find_commit(repo.merge_base(local.id(), remote.id())?)?
         .tree()?;
     let mut idx = repo.merge_trees(&ancestor, &local_tree, &remote_tree, None)?;
+    let unresolved = if idx.has_conflicts() {
+        resolve_album_conflicts(repo, &mut idx)?
+    } else {
+        Vec::new()
+    };
 
     if idx.has_conflicts() {
         log::error!("Merge conficts detected...");
         repo.checkout_index(Some(&mut idx), None)?;
+        // replace textual conflicts of album files with field level ones
+        if let Some(workdir) = repo.workdir() {
+            for (path, content) in unresolved {
+                if let Err(e) = std::fs::write(workdir.join(&path), content) {
+                    log::warn!("Failed to write conflicts of {}: {e}", path.display());
+                }
+            }
+        }
         return Ok(());
     }
     let result_tree = repo.find_tree(idx.write_tree_to(repo)?)?;
@@ -179,6 +199,93 @@ fn do_merge<'a>(
     Ok(())
 }
 
+/// Resolve conflicting album files in `index` with [merge_album].
+///
+/// Returns album files which still have conflicts, with content containing conflict markers.
+fn resolve_album_conflicts(
+    repo: &Repository,
+    index: &mut git2::Index,
+) -> Result<Vec<(PathBuf, String)>, git2::Error> {
+    let read_album = |entry: &git2::IndexEntry| {
+        let blob = repo.find_blob(entry.id).ok()?;
+        Album::from_str(std::str::from_utf8(blob.content()).ok()?).ok()
+    };
+
+    let conflicts = index.conflicts()?.collect::<Result<Vec<_>, _>>()?;
+    let mut unresolved = Vec::new();
+    for conflict in conflicts {
+        let (Some(mut ours), Some(theirs)) = (conflict.our, conflict.their) else {
+            continue;
+        };
+        // files other than albums can not be parsed, and are left for manual resolution
+        let (Some(our_album), Some(their_album)) = (read_album(&ours), read_album(&theirs)) else {
+            continue;
+        };
+        let base_album = conflict.ancestor.as_ref().and_then(read_album);
+        let Ok(merged) = merge_album(base_album.as_ref(), &our_album, &their_album) else {
+            continue;
+        };
+
+        let path = PathBuf::from(String::from_utf8_lossy(&ours.path).into_owned());
+        let content = merged.to_toml_string(7);
+        if merged.is_clean() {
+            log::debug!("Resolved conflicts of {} automatically", path.display());
+            ours.id = repo.blob(content.as_bytes())?;
+            ours.file_size = content.len() as u32;
+            ours.flags &= !INDEX_ENTRY_STAGE_MASK;
+            index.conflict_remove(&path)?;
+            index.add(&ours)?;
+        } else {
+            unresolved.push((path, content));
+        }
+    }
+    Ok(unresolved)
+}
+
+/// Register merge driver `anni` in git config of repository at `root`, and use it for album files.
+///
+/// `driver` is the command run by git, see `merge.<driver>.driver` of git-config(1).
+pub(crate) fn install_merge_driver(
+    root: &Path,
+    album_roots: &[PathBuf],
+    driver: &str,
+) -> RepoResult<()> {
+    let repo = Repository::discover(root)?;
+    let mut config = repo.config()?;
+    config.set_str("merge.anni.name", "anni album merge driver")?;
+    config.set_str("merge.anni.driver", driver)?;
+
+    let Some(workdir) = repo.workdir() else {
+        return Err(git2::Error::from_str("bare repository is not supported").into());
+    };
+    let workdir = workdir.canonicalize()?;
+    let attributes_path = workdir.join(".gitattributes");
+    let mut attributes = if attributes_path.exists() {
+        std::fs::read_to_string(&attributes_path)?
+    } else {
+        String::new()
+    };
+    for album_root in album_roots {
+        let Ok(album_root) = album_root.canonicalize() else {
+            continue;
+        };
+        let Some(relative) = pathdiff::diff_paths(&album_root, &workdir) else {
+            continue;
+        };
+        let pattern = relative.to_string_lossy().replace('\\', "/");
+        let line = format!("{pattern}/**/*.toml merge=anni");
+        if !attributes.lines().any(|l| l.trim() == line) {
+            if !attributes.is_empty() && !attributes.ends_with('\n') {
+                attributes.push('\n');
+            }
+            attributes.push_str(&line);
+            attributes.push('\n');
+        }
+    }
+    std::fs::write(attributes_path, attributes)?;
+    Ok(())
+}
+
 pub(crate) fn pull<P: AsRef<Path>>(root: P, remote_branch: &str) -> Result<(), git2::Error> {
     let repo = Repository::open(root.as_ref())?;
     let mut remote = repo.find_remote("origin")?;
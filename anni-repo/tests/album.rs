@@ -72,3 +72,59 @@ fn test_deserialize_album() {
         }
     }
 }
+
+#[test]
+fn test_localized_album() {
+    let album = Album::from_str(
+        r#"
+[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = "夏凪ぎ／宝物になった日"
+artist = "やなぎなぎ"
+date = 2020-12-16
+type = "normal"
+catalog = "KSLA-0178"
+
+[album.localized.ja-Latn]
+title = "Natsunagi / Takaramono ni Natta Hi"
+artist = "Nagi Yanagi"
+
+[[discs]]
+catalog = "KSLA-0178"
+
+[[discs.tracks]]
+title = "夏凪ぎ"
+localized = { ja-Latn = { title = "Natsunagi" }, en = { title = "Summer Calm" } }
+
+[[discs.tracks]]
+title = "宝物になった日(Instrumental)"
+artist = "麻枝准"
+type = "instrumental"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        album.title_in(&["ja-Latn"]),
+        "Natsunagi / Takaramono ni Natta Hi"
+    );
+    assert_eq!(album.title_in(&["en"]), "夏凪ぎ／宝物になった日");
+
+    let disc = album.iter().next().unwrap();
+    assert_eq!(
+        disc.title_in(&["ja-Latn-JP"]),
+        "Natsunagi / Takaramono ni Natta Hi"
+    );
+
+    let tracks: Vec<_> = disc.iter().collect();
+    assert_eq!(tracks[0].title_in(&["en", "ja-Latn"]), "Summer Calm");
+    assert_eq!(tracks[0].title_in(&[]), "夏凪ぎ");
+    // artist is inherited from album, together with its localized artists
+    assert_eq!(tracks[0].artist_in(&["ja-Latn"]), "Nagi Yanagi");
+    // artist written in track is not replaced by localized artist of album
+    assert_eq!(tracks[1].artist_in(&["ja-Latn"]), "麻枝准");
+    assert_eq!(
+        tracks[1].title_in(&["ja-Latn"]),
+        "宝物になった日(Instrumental)"
+    );
+}
@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use anni_metadata::model::Album;
+use anni_repo::diff::{align_album, apply_changes, diff_album};
+
+const ALBUM: &str = r#"[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = "Title"
+artist = "Artist"
+date = 2020-12-16
+type = "normal"
+catalog = "TEST-0001"
+tags = ["tag1"]
+
+[[discs]]
+catalog = "TEST-0001"
+
+[[discs.tracks]]
+title = "Track 1"
+
+[[discs.tracks]]
+title = "Track 2"
+"#;
+
+#[test]
+fn test_diff_album_fields() {
+    let old = Album::from_str(ALBUM).unwrap();
+    let new = Album::from_str(
+        &ALBUM
+            .replace("title = \"Track 2\"", "title = \"Track Two\"")
+            .replace("tags = [\"tag1\"]", "tags = [\"tag1\", \"tag2\"]"),
+    )
+    .unwrap();
+
+    let changes = diff_album(Some(&old), Some(&new));
+    let paths: Vec<_> = changes.iter().map(|c| c.path.to_string()).collect();
+    assert_eq!(paths, vec!["album.tags", "discs[0].tracks[1].title"]);
+    assert_eq!(changes[1].old.as_ref().unwrap().as_str(), Some("Track 2"));
+    assert_eq!(changes[1].new.as_ref().unwrap().as_str(), Some("Track Two"));
+}
+
+#[test]
+fn test_diff_album_added_track() {
+    let old = Album::from_str(ALBUM).unwrap();
+    let new =
+        Album::from_str(&format!("{ALBUM}\n[[discs.tracks]]\ntitle = \"Track 3\"\n")).unwrap();
+
+    let changes = diff_album(Some(&old), Some(&new));
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path.to_string(), "discs[0].tracks[2]");
+    assert!(changes[0].old.is_none());
+
+    let changes = diff_album(None, Some(&new));
+    assert_eq!(changes.len(), 1);
+    assert!(changes[0].path.segments().is_empty());
+}
+
+const REMOTE: &str = r#"[album]
+album_id = "00000000-0000-4000-8000-000000000000"
+title = "Title"
+artist = "Remote Artist"
+date = 2020-12-16
+type = "normal"
+catalog = ""
+
+[[discs]]
+catalog = ""
+
+[[discs.tracks]]
+title = "Track One"
+
+[[discs.tracks]]
+title = "Track 2"
+artist = "Guest"
+
+[[discs.tracks]]
+title = "Track 3"
+"#;
+
+#[test]
+fn test_align_album() {
+    let album = Album::from_str(ALBUM).unwrap();
+    let remote = Album::from_str(REMOTE).unwrap();
+
+    let (old, new) = align_album(&album, &remote);
+    let changes = diff_album(Some(&old), Some(&new));
+    let paths: Vec<_> = changes.iter().map(|c| c.path.to_string()).collect();
+    // album id, catalog and tags are kept
+    assert_eq!(
+        paths,
+        vec![
+            "album.artist",
+            "discs[0].tracks[0].artist",
+            "discs[0].tracks[0].title",
+            "discs[0].tracks[1].artist",
+            "discs[0].tracks[2]",
+        ]
+    );
+    assert_eq!(
+        changes[1].old.as_ref().unwrap().as_str(),
+        Some("Artist"),
+        "artist of track should be resolved"
+    );
+}
+
+#[test]
+fn test_apply_changes() {
+    let album = Album::from_str(ALBUM).unwrap();
+    let remote = Album::from_str(REMOTE).unwrap();
+    let (old, new) = align_album(&album, &remote);
+    let changes = diff_album(Some(&old), Some(&new));
+
+    // accept all changes except album artist
+    let mut updated = apply_changes(&old, changes.iter().skip(1)).unwrap();
+    updated.format();
+    assert_eq!(updated.album_id(), album.album_id());
+    assert_eq!(updated.artist(), "Artist");
+    assert_eq!(updated.catalog(), "TEST-0001");
+
+    let disc = updated.iter().next().unwrap();
+    let tracks: Vec<_> = disc
+        .iter()
+        .map(|track| (track.title().to_string(), track.artist().to_string()))
+        .collect();
+    assert_eq!(
+        tracks,
+        vec![
+            ("Track One".to_string(), "Remote Artist".to_string()),
+            ("Track 2".to_string(), "Guest".to_string()),
+            ("Track 3".to_string(), "Remote Artist".to_string()),
+        ]
+    );
+
+    // removed tracks are applied from the end
+    let changes = diff_album(Some(&new), Some(&old));
+    let updated = apply_changes(&new, changes.iter()).unwrap();
+    assert!(diff_album(Some(&old), Some(&updated)).is_empty());
+}
+
+#[test]
+fn test_apply_changes_non_contiguous() {
+    let old = Album::from_str(ALBUM).unwrap();
+    let new = Album::from_str(&format!(
+        "{ALBUM}\n[[discs.tracks]]\ntitle = \"Track 3\"\n\n[[discs.tracks]]\ntitle = \"Track 4\"\n"
+    ))
+    .unwrap();
+    let changes = diff_album(Some(&old), Some(&new));
+    assert_eq!(changes.len(), 2);
+
+    // track 4 can not be added without track 3
+    assert!(apply_changes(&old, changes.iter().skip(1)).is_err());
+    let updated = apply_changes(&old, changes.iter()).unwrap();
+    assert!(diff_album(Some(&new), Some(&updated)).is_empty());
+}
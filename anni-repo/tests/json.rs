@@ -0,0 +1,81 @@
+#![cfg(feature = "json")]
+
+use std::str::FromStr;
+
+use anni_metadata::model::Album;
+use anni_repo::models::{album_to_json, albums_from_json};
+
+const ALBUM: &str = include_str!("fixtures/test-album.toml");
+
+#[test]
+fn test_album_json_round_trip() {
+    let album = Album::from_str(ALBUM).unwrap();
+    let json = album_to_json(&album);
+    assert_eq!(json["schema"], 2);
+    assert_eq!(json["album"]["date"], "2020-12-16");
+    assert_eq!(json["discs"][0]["tracks"][0]["title"], "夏凪ぎ");
+
+    let mut albums = albums_from_json(&json.to_string()).unwrap();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].format_to_string(), ALBUM);
+
+    let array = serde_json::Value::Array(vec![json.clone(), json]);
+    assert_eq!(albums_from_json(&array.to_string()).unwrap().len(), 2);
+}
+
+#[test]
+fn test_album_json_legacy_format() {
+    let album = Album::from_str(ALBUM).unwrap();
+    let legacy = serde_json::to_string(&anni_repo::models::JsonAlbum::from(album)).unwrap();
+
+    let mut albums = albums_from_json(&legacy).unwrap();
+    assert_eq!(albums[0].format_to_string(), ALBUM);
+}
+
+#[test]
+fn test_album_json_newer_schema() {
+    let album = Album::from_str(ALBUM).unwrap();
+    let mut json = album_to_json(&album);
+    json["schema"] = 100.into();
+    let err = albums_from_json(&json.to_string()).unwrap_err();
+    assert!(err.to_string().contains("Please upgrade anni"));
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_album_json_schema() {
+    use jsonschema::JSONSchema;
+
+    let schema = serde_json::to_value(anni_metadata::schema::album_schema()).unwrap();
+    let schema = JSONSchema::compile(&schema).unwrap();
+
+    let album = Album::from_str(ALBUM).unwrap();
+    let mut json = album_to_json(&album);
+    assert!(schema.is_valid(&json));
+
+    json["album"]["type"] = "unknown".into();
+    assert!(!schema.is_valid(&json));
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_repository_json_schema() {
+    use jsonschema::JSONSchema;
+
+    let schema = serde_json::to_value(anni_repo::models::repository_schema()).unwrap();
+    let schema = JSONSchema::compile(&schema).unwrap();
+    let repo: toml::Value = toml::from_str(include_str!("repos/tag-aliases/repo.toml")).unwrap();
+    assert!(schema.is_valid(&serde_json::to_value(repo).unwrap()));
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_tag_json_schema() {
+    use jsonschema::JSONSchema;
+
+    let schema = serde_json::to_value(anni_metadata::schema::tag_schema()).unwrap();
+    let schema = JSONSchema::compile(&schema).unwrap();
+    let tags: toml::Value =
+        toml::from_str(include_str!("repos/tag-aliases/tag/default.toml")).unwrap();
+    assert!(schema.is_valid(&serde_json::to_value(tags).unwrap()));
+}
@@ -0,0 +1,132 @@
+use std::str::FromStr;
+
+use anni_metadata::model::Album;
+use anni_repo::merge::merge_album;
+
+const BASE: &str = r#"[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = "Title"
+artist = "Artist"
+date = 2020-12-16
+type = "normal"
+catalog = "TEST-0001"
+tags = [
+    "tag1",
+    "tag2",
+]
+
+[[discs]]
+catalog = "TEST-0001"
+
+[[discs.tracks]]
+title = "Track 1"
+
+[[discs.tracks]]
+title = "Track 2"
+"#;
+
+fn album(input: &str) -> Album {
+    Album::from_str(input).unwrap()
+}
+
+#[test]
+fn test_merge_different_fields() {
+    let ours = BASE
+        .replace("title = \"Title\"", "title = \"New Title\"")
+        .replace("    \"tag1\",\n", "");
+    let theirs = BASE
+        .replace(
+            "title = \"Track 2\"",
+            "title = \"Track 2\"\ntype = \"instrumental\"",
+        )
+        .replace("    \"tag2\",\n", "    \"tag2\",\n    \"tag3\",\n");
+
+    let result = merge_album(Some(&album(BASE)), &album(&ours), &album(&theirs)).unwrap();
+    assert!(result.is_clean());
+    assert_eq!(result.album.title_raw(), "New Title");
+
+    let tags: Vec<_> = result.album.album_tags().iter().map(|t| t.name()).collect();
+    assert_eq!(tags, vec!["tag2", "tag3"]);
+
+    let disc = result.album.iter().next().unwrap();
+    let track = disc.iter().nth(1).unwrap();
+    assert_eq!(track.track_type().as_ref(), "instrumental");
+}
+
+#[test]
+fn test_merge_conflict_field() {
+    let ours = BASE.replace("title = \"Track 1\"", "title = \"Ours\"");
+    let theirs = BASE
+        .replace("title = \"Track 1\"", "title = \"Theirs\"")
+        .replace("artist = \"Artist\"", "artist = \"New Artist\"");
+
+    let result = merge_album(Some(&album(BASE)), &album(&ours), &album(&theirs)).unwrap();
+    assert_eq!(result.conflicts.len(), 1);
+    assert_eq!(
+        result.conflicts[0].path.to_string(),
+        "discs[0].tracks[0].title"
+    );
+    assert_eq!(result.album.artist(), "New Artist");
+    assert_eq!(
+        result.to_toml_string(7),
+        BASE.replace("artist = \"Artist\"", "artist = \"New Artist\"")
+            .replace(
+                "title = \"Track 1\"\n",
+                r#"<<<<<<< ours
+title = "Ours"
+=======
+title = "Theirs"
+>>>>>>> theirs
+"#
+            )
+    );
+}
+
+#[test]
+fn test_merge_conflict_removed_field() {
+    let base = BASE.replace(
+        "artist = \"Artist\"\n",
+        "artist = \"Artist\"\nedition = \"Base\"\n",
+    );
+    let ours = BASE.to_string();
+    let theirs = BASE.replace(
+        "artist = \"Artist\"\n",
+        "artist = \"Artist\"\nedition = \"Theirs\"\n",
+    );
+
+    let result = merge_album(Some(&album(&base)), &album(&ours), &album(&theirs)).unwrap();
+    assert_eq!(result.conflicts.len(), 1);
+    assert!(result.album.edition().is_none());
+    assert!(result.to_toml_string(7).contains(
+        r#"<<<<<<< ours
+=======
+edition = "Theirs"
+>>>>>>> theirs
+"#
+    ));
+}
+
+#[test]
+fn test_merge_conflict_tracks() {
+    let ours = format!("{BASE}\n[[discs.tracks]]\ntitle = \"Ours\"\n");
+    let theirs = BASE.replace("\n[[discs.tracks]]\ntitle = \"Track 2\"\n", "");
+
+    let result = merge_album(Some(&album(BASE)), &album(&ours), &album(&theirs)).unwrap();
+    assert_eq!(result.conflicts.len(), 1);
+    assert_eq!(result.conflicts[0].path.to_string(), "discs[0].tracks");
+    assert_eq!(
+        result.to_toml_string(7),
+        BASE.replace(
+            "\n[[discs.tracks]]\ntitle = \"Track 1\"",
+            "\n<<<<<<< ours\n[[discs.tracks]]\ntitle = \"Track 1\""
+        ) + "\n[[discs.tracks]]\ntitle = \"Ours\"\n=======\n[[discs.tracks]]\ntitle = \"Track 1\"\n>>>>>>> theirs\n"
+    );
+}
+
+#[test]
+fn test_merge_added_on_both_sides() {
+    let ours = BASE.replace("title = \"Title\"", "title = \"Ours\"");
+    let result = merge_album(None, &album(&ours), &album(BASE)).unwrap();
+    assert_eq!(result.conflicts.len(), 1);
+    assert_eq!(result.conflicts[0].path.to_string(), "album.title");
+}
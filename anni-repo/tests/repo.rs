@@ -1,7 +1,21 @@
 use anni_metadata::model::{TagRef, TagType, TrackType};
-use anni_repo::{error::Error, prelude::*, RepositoryManager};
+use anni_repo::{error::Error, prelude::*, OwnedRepositoryManager, RepositoryManager};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        let target = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target);
+        } else {
+            std::fs::copy(&path, &target).unwrap();
+        }
+    }
+}
+
 fn repo_from_str() -> Repository {
     Repository::from_str(
         r#"[repo]
@@ -141,3 +155,316 @@ fn test_repo_album_tags() {
     assert_eq!(tracks[0].artist(), "Artist1");
     assert_eq!(tracks[0].track_type(), &TrackType::Absolute);
 }
+
+#[test]
+fn test_tag_aliases() {
+    let manager = RepositoryManager::new("tests/repos/tag-aliases")
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+
+    let group = TagRef::new("Group", TagType::Group);
+    let alias = manager
+        .tag(&TagRef::new("Old Group", TagType::Group))
+        .expect("Alias `group:Old Group` is not resolved");
+    assert_eq!(alias.as_ref(), &group);
+    assert!(manager
+        .child_tags(&group)
+        .contains(&TagRef::new("Member", TagType::Artist)));
+
+    let album = manager.albums().values().next().unwrap();
+    assert_eq!(album.tags()[0], &group);
+
+    assert_eq!(
+        manager.check_tag_types(),
+        vec![(
+            &TagRef::new("Vocal", TagType::Category),
+            &TagRef::new("Member", TagType::Artist)
+        )]
+    );
+}
+
+#[test]
+fn test_tag_alias_conflict() {
+    let root = std::env::temp_dir().join("anni-repo-test-tag-alias-conflict");
+    let _ = std::fs::remove_dir_all(&root);
+    copy_dir(Path::new("tests/repos/tag-aliases"), &root);
+    let tag_path = root.join("tag/default.toml");
+    let tags = std::fs::read_to_string(&tag_path).unwrap();
+    std::fs::write(&tag_path, tags.replace("Old Group", "Group")).unwrap();
+
+    let result = RepositoryManager::new(&root).unwrap().into_owned_manager();
+    assert!(
+        matches!(result, Err(Error::RepoTagDuplicated(tag)) if tag == TagRef::new("Group", TagType::Group))
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_rename_tag() {
+    let root = std::env::temp_dir().join("anni-repo-test-rename-tag");
+    let _ = std::fs::remove_dir_all(&root);
+    copy_dir(Path::new("tests/repos/tag-aliases"), &root);
+
+    let manager = RepositoryManager::new(&root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    let mut changed = manager
+        .rename_tag(
+            &TagRef::new("Member", TagType::Artist),
+            &TagRef::new("New Member", TagType::Unknown),
+        )
+        .unwrap();
+    changed.sort();
+    assert_eq!(
+        changed,
+        vec![
+            PathBuf::from("album/album.toml"),
+            PathBuf::from("tag/default.toml")
+        ]
+    );
+    drop(manager);
+
+    let manager = RepositoryManager::new(&root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    assert!(manager
+        .tag(&TagRef::new("Member", TagType::Artist))
+        .is_none());
+    let album = manager.albums().values().next().unwrap();
+    assert_eq!(album.tags()[1], &TagRef::new("New Member", TagType::Artist));
+    drop(manager);
+
+    // formatting of untouched fields is kept
+    let album = std::fs::read_to_string(root.join("album/album.toml")).unwrap();
+    assert!(
+        album.contains(r#"tags = ["Old Group", "artist:New Member", "artist:Duplicated Member"]"#)
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_merge_tag() {
+    let root = std::env::temp_dir().join("anni-repo-test-merge-tag");
+    let _ = std::fs::remove_dir_all(&root);
+    copy_dir(Path::new("tests/repos/tag-aliases"), &root);
+
+    let manager = RepositoryManager::new(&root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    manager
+        .merge_tag(
+            &TagRef::new("Duplicated Member", TagType::Artist),
+            &TagRef::new("Member", TagType::Artist),
+        )
+        .unwrap();
+    drop(manager);
+
+    let manager = RepositoryManager::new(&root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    let member = manager
+        .tag(&TagRef::new("Duplicated Member", TagType::Artist))
+        .expect("Merged tag should be an alias");
+    assert_eq!(member.name(), "Member");
+    assert_eq!(member.aliases(), ["Duplicated Member"]);
+    assert_eq!(member.names()["ja"], "メンバー");
+    assert_eq!(member.parents().count(), 2);
+
+    let album = manager.albums().values().next().unwrap();
+    assert_eq!(
+        album.tags(),
+        vec![
+            &TagRef::new("Group", TagType::Group),
+            &TagRef::new("Member", TagType::Artist),
+        ]
+    );
+    let credit = album.credits().next().unwrap();
+    assert_eq!(
+        credit.tag.as_deref(),
+        Some(&TagRef::new("Member", TagType::Artist))
+    );
+    drop(manager);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(all(feature = "db-read", feature = "db-write"))]
+#[test]
+fn test_database_incremental() {
+    use anni_repo::db::RepoDatabaseRead;
+    use uuid::Uuid;
+
+    let root = std::env::temp_dir().join("anni-repo-test-database-incremental");
+    let _ = std::fs::remove_dir_all(&root);
+    copy_dir(Path::new("tests/repos/album-tags"), &root);
+
+    let database_path = root.join("repo.db");
+    let album_id = Uuid::from_str("3e5ff166-f800-4433-a413-6cfa3c2b3cdd").unwrap();
+    let build = || {
+        // albums are read from files by incremental builds
+        RepositoryManager::new(&root)
+            .and_then(OwnedRepositoryManager::tags_only)
+            .unwrap()
+            .to_database_incremental(&database_path)
+            .unwrap();
+        RepoDatabaseRead::new(&database_path).unwrap()
+    };
+
+    // database does not exist, a full build is performed
+    let db = build();
+    assert_eq!(db.get_album(album_id).unwrap().unwrap().title, "Title");
+
+    // changed album is updated, with its tags
+    let album_path = root.join("album/album.toml");
+    let album = std::fs::read_to_string(&album_path).unwrap();
+    std::fs::write(
+        &album_path,
+        album.replace(r#"title = "Title""#, r#"title = "Changed""#),
+    )
+    .unwrap();
+    let db = build();
+    assert_eq!(db.get_album(album_id).unwrap().unwrap().title, "Changed");
+    assert_eq!(db.get_albums_by_tag("Test", false).unwrap().len(), 1);
+
+    // moved album is kept
+    let moved_path = root.join("album/moved.toml");
+    std::fs::rename(&album_path, &moved_path).unwrap();
+    let db = build();
+    assert_eq!(db.get_album(album_id).unwrap().unwrap().title, "Changed");
+    assert_eq!(db.get_albums_by_tag("Test", false).unwrap().len(), 1);
+    std::fs::rename(&moved_path, &album_path).unwrap();
+
+    // removed album is removed from database
+    std::fs::remove_file(&album_path).unwrap();
+    let db = build();
+    assert!(db.get_album(album_id).unwrap().is_none());
+    assert!(db.get_albums_by_tag("Test", false).unwrap().is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(all(feature = "db-read", feature = "db-write"))]
+#[test]
+fn test_database_query() {
+    use anni_metadata::model::{AnniDate, ArtistKind, ArtistRole};
+    use anni_repo::db::{AlbumOrder, AlbumQuery, RepoDatabaseRead};
+    use uuid::Uuid;
+
+    let database_path = std::env::temp_dir().join("anni-repo-test-database-query.db");
+    RepositoryManager::new("tests/repos/query")
+        .unwrap()
+        .into_owned_manager()
+        .unwrap()
+        .to_database(&database_path)
+        .unwrap();
+    let db = RepoDatabaseRead::new(&database_path).unwrap();
+
+    let catalogs = |query: AlbumQuery| -> Vec<String> {
+        db.query_albums(&query)
+            .unwrap()
+            .into_iter()
+            .map(|album| album.catalog)
+            .collect()
+    };
+
+    assert_eq!(
+        catalogs(AlbumQuery::new()),
+        vec!["LACA-0001", "LACA-0002", "KICA-0001"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().order_by(AlbumOrder::Catalog, true)),
+        vec!["LACA-0002", "LACA-0001", "KICA-0001"]
+    );
+
+    // album artist or track artist, `%` is matched literally
+    assert_eq!(
+        catalogs(AlbumQuery::new().artist("Member")),
+        vec!["LACA-0001", "LACA-0002"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().artist("100%")),
+        vec!["KICA-0001"]
+    );
+
+    assert_eq!(
+        catalogs(
+            AlbumQuery::new()
+                .released_after(AnniDate::from_str("2020").unwrap())
+                .released_before(AnniDate::from_str("2021").unwrap())
+        ),
+        vec!["LACA-0002"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().track_type(TrackType::Instrumental)),
+        vec!["LACA-0002"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().tag("Group", false)),
+        vec!["LACA-0001"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().tag("Group", true)),
+        vec!["LACA-0001", "LACA-0002", "KICA-0001"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().catalog_prefix("LACA")),
+        vec!["LACA-0001", "LACA-0002"]
+    );
+
+    // artist credits, with or without role
+    assert_eq!(
+        catalogs(AlbumQuery::new().credit(Some(ArtistRole::Vocal), "Member")),
+        vec!["LACA-0001"]
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().credit(Some(ArtistRole::Vocal), "Group")),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        catalogs(AlbumQuery::new().credit(None, "Group")),
+        vec!["LACA-0001"]
+    );
+
+    let album = db
+        .read_album(Uuid::parse_str("7a3c1b52-0f7e-4c1d-9a51-3b6f0d5e8c01").unwrap())
+        .unwrap()
+        .unwrap();
+    let composer = album
+        .credits()
+        .find(|credit| credit.role == ArtistRole::Composer)
+        .unwrap();
+    assert_eq!(composer.name, "Group");
+    assert_eq!(composer.kind, Some(ArtistKind::Group));
+    assert_eq!(composer.tag.as_ref().unwrap().to_string(), "group:Group");
+
+    // localized titles
+    assert_eq!(album.title_in(&["ja-Latn"]), "Saisho");
+    let disc = album.iter().next().unwrap();
+    let track = disc.iter().next().unwrap();
+    assert_eq!(track.title_in(&["en-US"]), "Song (English)");
+    assert_eq!(track.title_in(&["ja-Latn"]), "Song");
+
+    // external identifiers, inherited from album
+    assert_eq!(album.ids.barcode.as_deref(), Some("4988002000005"));
+    let ids = track.ids();
+    assert_eq!(ids.isrc.as_deref(), Some("JPA000000001"));
+    assert_eq!(
+        ids.musicbrainz_release,
+        Uuid::parse_str("f1a2b3c4-d5e6-4f70-8190-a1b2c3d4e5f6").ok()
+    );
+    assert!(disc.raw().ids.is_empty());
+
+    // pagination
+    let query = AlbumQuery::new().tag("Member", false).limit(1).offset(1);
+    assert_eq!(catalogs(query.clone()), vec!["KICA-0001"]);
+    assert_eq!(db.count_albums(&query).unwrap(), 2);
+
+    std::fs::remove_file(database_path).unwrap();
+}
@@ -0,0 +1,85 @@
+use std::str::FromStr;
+
+use anni_metadata::model::{Album, ArtistRole, ALBUM_SCHEMA_VERSION};
+use anni_repo::schema::{album_schema, upgrade_album};
+
+const LEGACY_ALBUM: &str = r#"# comments are kept
+[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = "夏凪ぎ／宝物になった日"
+artist = "やなぎなぎ"
+date = 2020-12-16
+type = "normal"
+catalog = "KSLA-0178"
+artists = { Vocal = "やなぎなぎ" }
+
+[[discs]]
+catalog = "KSLA-0178"
+artists = { Composer = "A、B", Arranger = { name = "C、D", type = "group" } }
+
+[[discs.tracks]]
+title = "夏凪ぎ"
+
+[discs.tracks.artists]
+Lyricist = "E 、F" # lyrics
+"#;
+
+const UPGRADED_ALBUM: &str = r#"schema = 2
+# comments are kept
+[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = "夏凪ぎ／宝物になった日"
+artist = "やなぎなぎ"
+date = 2020-12-16
+type = "normal"
+catalog = "KSLA-0178"
+artists = { Vocal = "やなぎなぎ" }
+
+[[discs]]
+catalog = "KSLA-0178"
+artists = { Composer = ["A", "B"], Arranger = { name = "C、D", type = "group" } }
+
+[[discs.tracks]]
+title = "夏凪ぎ"
+
+[discs.tracks.artists]
+Lyricist = ["E", "F"] # lyrics
+"#;
+
+#[test]
+fn test_upgrade_album() {
+    assert_eq!(album_schema(LEGACY_ALBUM).unwrap(), 1);
+
+    let upgraded = upgrade_album(LEGACY_ALBUM).unwrap().unwrap();
+    assert_eq!(upgraded, UPGRADED_ALBUM);
+    assert_eq!(album_schema(&upgraded).unwrap(), ALBUM_SCHEMA_VERSION);
+    assert_eq!(upgrade_album(&upgraded).unwrap(), None);
+
+    let album = Album::from_str(&upgraded).unwrap();
+    assert_eq!(album.schema(), ALBUM_SCHEMA_VERSION);
+    let disc = album.iter().next().unwrap();
+    let composers: Vec<_> = disc
+        .artists()
+        .unwrap()
+        .get_all(&ArtistRole::Composer)
+        .map(|credit| credit.name.as_str())
+        .collect();
+    assert_eq!(composers, vec!["A", "B"]);
+}
+
+#[test]
+fn test_newer_album_schema() {
+    // newer albums may contain fields unknown to this version
+    let input = UPGRADED_ALBUM
+        .replacen(
+            "schema = 2",
+            &format!("schema = {}", ALBUM_SCHEMA_VERSION + 1),
+            1,
+        )
+        .replace("[album]", "[album]\nunknown_field = true");
+    let err = upgrade_album(&input).unwrap_err();
+    assert!(err.to_string().contains("Please upgrade anni"));
+
+    let err = Album::from_str(&input).unwrap_err();
+    assert!(err.to_string().contains("Please upgrade anni"));
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "search")]
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anni_metadata::model::{TagType, TrackIdentifier};
+use anni_repo::search::{RepositorySearchManager, SearchHit, SearchResult};
+use anni_repo::RepositoryManager;
+use uuid::Uuid;
+
+const ALBUM_ID: &str = "5d0e8c1a-3b7f-4e2a-9c61-8f4b2d7e1a01";
+
+/// Index of `tests/repos/search`.
+///
+/// It is built only once, as the repository is locked while it is loaded.
+fn searcher() -> &'static RepositorySearchManager {
+    static SEARCHER: OnceLock<RepositorySearchManager> = OnceLock::new();
+    SEARCHER.get_or_init(|| {
+        let index = std::env::temp_dir().join("anni-repo-test-search");
+        let _ = std::fs::remove_dir_all(&index);
+        std::fs::create_dir_all(&index).unwrap();
+
+        RepositoryManager::new("tests/repos/search")
+            .unwrap()
+            .into_owned_manager()
+            .unwrap()
+            .build_search_index(&index)
+            .unwrap();
+        RepositorySearchManager::open(&index).unwrap()
+    })
+}
+
+fn search(query: &str, fuzzy: bool) -> Vec<SearchResult> {
+    searcher().search(query, 10, fuzzy).unwrap()
+}
+
+fn album_id() -> Uuid {
+    Uuid::from_str(ALBUM_ID).unwrap()
+}
+
+#[test]
+fn test_search_album() {
+    let results = search("ヒカリ", false);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title, "ヒカリ");
+    assert!(matches!(results[0].hit, SearchHit::Album(id) if id == album_id()));
+}
+
+#[test]
+fn test_search_kana_folding() {
+    // hiragana query matches katakana title
+    let results = search("ひかり", false);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title, "ヒカリ");
+    assert!(matches!(results[0].hit, SearchHit::Album(id) if id == album_id()));
+}
+
+#[test]
+fn test_search_disc() {
+    let results = search("Overture", false);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title, "Overture");
+    assert!(matches!(
+        results[0].hit,
+        SearchHit::Disc { album_id: id, disc_id: 2 } if id == album_id()
+    ));
+}
+
+#[test]
+fn test_search_track() {
+    let results = search("Interlude", false);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title, "Interlude");
+    assert!(matches!(
+        results[0].hit,
+        SearchHit::Track(TrackIdentifier { album_id: id, disc_id: 2, track_id: 1 }) if id == album_id()
+    ));
+}
+
+#[test]
+fn test_search_tag() {
+    // albums, discs and tracks with the tag or the artist match as well
+    let results = search("Lumina", false);
+    assert!(results.iter().any(|result| matches!(
+        &result.hit,
+        SearchHit::Tag(tag) if tag.name() == "Lumina" && tag.tag_type() == &TagType::Artist
+    )));
+}
+
+#[test]
+fn test_search_fuzzy() {
+    assert!(search("Nocturme", false).is_empty());
+
+    let results = search("Nocturme", true);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title, "Nocturne");
+    assert!(matches!(
+        results[0].hit,
+        SearchHit::Track(TrackIdentifier { album_id: id, disc_id: 1, track_id: 1 }) if id == album_id()
+    ));
+}
@@ -0,0 +1,74 @@
+use anni_repo::diff::FieldPath;
+use anni_repo::span::AlbumSpans;
+
+const ALBUM: &str = r#"[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = " Title"
+artist = "Artist"
+date = 2020-12-16
+type = "normal"
+catalog = "TEST-0001"
+tags = ["tag1"]
+
+[album.localized.ja-Latn]
+title = "Romaji"
+
+[[discs]]
+catalog = "TEST-0001"
+artists = { "Vocal" = "Artist" }
+
+[[discs.tracks]]
+title = "Track 1"
+
+[[discs.tracks]]
+title = "Track 2"
+"#;
+
+fn span_of<'a>(spans: &AlbumSpans, path: &str) -> (&'a str, &'a str) {
+    let path: FieldPath = path.parse().unwrap();
+    let span = spans.get(&path).unwrap();
+    (&ALBUM[span.key.clone()], &ALBUM[span.value.clone()])
+}
+
+#[test]
+fn test_album_spans() {
+    let spans = AlbumSpans::parse(ALBUM).unwrap();
+
+    assert_eq!(span_of(&spans, "album.title"), ("title", "\" Title\""));
+    assert_eq!(span_of(&spans, "album.date"), ("date", "2020-12-16"));
+    assert_eq!(span_of(&spans, "album.tags[0]"), ("\"tag1\"", "\"tag1\""));
+    assert_eq!(
+        span_of(&spans, "album.localized.ja-Latn.title"),
+        ("title", "\"Romaji\"")
+    );
+    assert_eq!(
+        span_of(&spans, "discs[0].artists.Vocal"),
+        ("\"Vocal\"", "\"Artist\"")
+    );
+    assert_eq!(
+        span_of(&spans, "discs[0].tracks[1].title"),
+        ("title", "\"Track 2\"")
+    );
+    assert!(spans
+        .get(&"discs[0].tracks[1].artist".parse().unwrap())
+        .is_none());
+
+    // tables cover their header and fields, implicit tables only have key in header
+    assert_eq!(
+        span_of(&spans, "discs[0].tracks[0]").1,
+        "[[discs.tracks]]\ntitle = \"Track 1\""
+    );
+    assert_eq!(
+        span_of(&spans, "album.localized"),
+        ("localized", "localized")
+    );
+    assert!(AlbumSpans::parse("[album\n").is_err());
+}
+
+#[test]
+fn test_field_path_round_trip() {
+    for path in ["", "album.title", "discs[0].tracks[12].title"] {
+        assert_eq!(path.parse::<FieldPath>().unwrap().to_string(), path);
+    }
+    assert!("discs[x]".parse::<FieldPath>().is_err());
+}
@@ -562,7 +562,7 @@ impl AnniWorkspace {
         let album = repo
             .album(&album_id)
             .expect("Album not found in metadata repository");
-        album.apply_strict(controlled_album_path, detailed)?;
+        album.apply_strict(controlled_album_path, detailed, &[])?;
 
         Ok(())
     }
@@ -43,9 +43,16 @@ repo-album-info-mismatch = Album info mismatch with folder.
 repo-import = Import album to repository.
 repo-import-format = Format of album metadata file.
 
+repo-export = Export albums in repository as json, or export JSON Schema of metadata files.
+repo-export-schema = Export JSON Schema of album, tag or repo.toml files instead of albums.
+repo-export-catalog = Catalogs of albums to export. All albums are exported if omitted. A single album is exported as an object, otherwise an array.
+
 repo-lint-start = Start validating repository.
 repo-lint-end = End validating repository.
 repo-lint-failed = Validation failed.
+repo-lint-fix = Apply suggested fixes to album files.
+repo-lint-fixed = Fixed {$count} problems in {$path}.
+repo-lint-tag-type = Tag {$parent} can not include {$child}.
 repo-catalog-filename-mismatch = Album catalog '{$album_catalog}' does not match filename.
 repo-invalid-artist = Invalid artist: {$artist}
 
@@ -54,6 +61,24 @@ repo-get-print = Print album metadata to stdout.
 repo-get-cue-keyword = If metadata is not enough, search vgmdb with keyword.
 repo-get-cue-catalog = Specify catalog when it does not exist.
 repo-cue-insufficient-information = Insufficient information from CUE file.
+repo-get-id = Identifier of album in the data source. Searching is skipped if provided.
+repo-get-keyword = Keyword to search album with. Defaults to catalog.
+repo-get-catalog = Catalog of album. Overrides the catalog provided by data source.
+repo-get-discogs-token = Personal access token of Discogs, which is required for searching.
+repo-get-itunes-country = Two-letter country code of the iTunes Store to search, such as jp.
+repo-get-missing-query = Either identifier, keyword or catalog is required.
+repo-get-missing-catalog = Catalog is not provided by data source, please specify it.
+repo-get-not-found = No album found on {$source} with keyword '{$keyword}'.
+repo-get-select = Select an album:
+
+repo-update = Update album in repo with metadata from remote data source.
+repo-update-from = Data source to get album metadata from.
+repo-update-yes = Accept all changes without asking.
+repo-update-catalog = Catalog of album to update.
+repo-update-multiple-albums = Multiple albums with catalog {$catalog} were found in repo.
+repo-update-no-change = Album is up to date with data source.
+repo-update-select = Select changes to apply:
+repo-update-done = Applied {$count} changes to {$path}.
 
 repo-edit = Open text editor for an album if metadata exists.
 repo-lint = Check whether data in repository is valid.
@@ -65,13 +90,70 @@ repo-print-input = Target to print. For example, tag name or album catalog. '/{"
 
 repo-db = Generate metadata database from repository.
 
+repo-query = Query albums in repository database.
+repo-query-database = Path of repository database. If not specified, a database is generated from repository.
+repo-query-artist = Albums whose artist, or artist of any track, contains the given text.
+repo-query-after = Albums released on or after the given date.
+repo-query-before = Albums released before the given date.
+repo-query-type = Albums with at least one track of the given type.
+repo-query-tag = Albums tagged by the given tag.
+repo-query-recursive = Include albums tagged by children of the tag.
+repo-query-catalog = Albums whose catalog starts with the given prefix.
+repo-query-sort = Sort albums by.
+repo-query-desc = Sort in descending order.
+repo-query-format = Output format.
+repo-search = Search albums, discs, tracks and tags in repository.
+repo-search-query = Search query. Hiragana and katakana are treated as the same.
+repo-search-fuzzy = Match terms with one typo.
+repo-search-index = Path of search index. It would be built if not exist.
+repo-search-format = Output format.
+
+repo-history = Show changes of an album in repository history.
+repo-history-album = Catalog or album id of the album.
+repo-history-disc = Only show changes of the given disc, starting from 1.
+repo-history-track = Only show changes of the given track in the disc, starting from 1.
+repo-history-blame = Show the last commit which changed each field, instead of all changes.
+
+repo-merge-driver = Merge album files field by field, as a git merge driver.
+repo-merge-driver-install = Register this command as merge driver of album files in git config and .gitattributes.
+repo-merge-driver-marker-size = Length of conflict markers.
+repo-merge-driver-path = Path of the merged file in repository, used in messages.
+repo-merge-driver-installed = Merge driver installed.
+repo-merge-driver-field = Conflicting field: {$field}
+repo-merge-driver-conflict = Failed to merge {$path} automatically.
+
+repo-lsp = Start a language server for album and tag files over stdio.
+repo-lsp-tags-failed = Failed to load tags from repository: {$error}
+repo-lsp-undefined-tag = Undefined tag: {$tag}
+repo-lsp-hover-artist = Artist: {$artist}
+repo-lsp-hover-track-type = Track type: {$track_type}
+repo-lsp-track-type-normal = A song with vocal.
+repo-lsp-track-type-instrumental = Instrumental version of a song, such as off vocal or karaoke.
+repo-lsp-track-type-absolute = Music without vocal by nature, such as BGM.
+repo-lsp-track-type-drama = A drama track.
+repo-lsp-track-type-radio = A radio program.
+repo-lsp-track-type-vocal = Vocal only version of a song.
+
+repo-tag = Manage tags in repository.
+repo-tag-rename = Rename a tag, and rewrite albums and tags which refer to it.
+repo-tag-merge = Merge a tag into another one, and rewrite albums and tags which refer to it.
+repo-tag-from = Tag to be renamed or merged, such as artist:Name.
+repo-tag-rename-to = New name of the tag. Tag type is kept if omitted.
+repo-tag-merge-into = Tag to merge into. Name of the merged tag becomes an alias of it.
+repo-tag-renamed = Renamed {$from} to {$to}, {$count} files changed.
+repo-tag-merged = Merged {$from} into {$into}, {$count} files changed.
+
 repo-migrate = Migrate metadata repository to new version.
 repo-migrate-album-id = Add album_id field to album metadata.
 
+repo-upgrade = Upgrade album files in repository to the latest schema version.
+repo-upgrade-done = Upgraded {$count} albums to schema version {$version}.
+
 
 ## Library
 library = Anni Audio library manager.
 library-tag = Apply metadata from repository to album.
+library-tag-locale = Preferred locale of titles and artists, in order. Original ones are used if not localized.
 library-link = Link library to strict format.
 
 ## Workspace
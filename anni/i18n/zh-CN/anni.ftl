@@ -42,9 +42,16 @@ repo-album-info-mismatch = 专辑信息与专辑目录不一致
 repo-import = 导入专辑
 repo-import-format = 导入专辑的数据格式
 
+repo-export = 以 json 格式导出仓库中的专辑，或导出元数据文件的 JSON Schema
+repo-export-schema = 导出专辑、标签或 repo.toml 文件的 JSON Schema，而非专辑
+repo-export-catalog = 需要导出的专辑的品番，省略时导出所有专辑。仅导出一张专辑时输出对象，否则输出数组
+
 repo-lint-start = 仓库校验开始
 repo-lint-end = 仓库校验结束
 repo-lint-failed = 仓库校验失败
+repo-lint-fix = 将建议的修复应用到专辑文件
+repo-lint-fixed = 已修复 {$path} 中的 {$count} 个问题
+repo-lint-tag-type = 标签 {$parent} 不能包含 {$child}
 repo-catalog-filename-mismatch = 专辑 {$album_catalog} 的品番与文件名不一致
 repo-invalid-artist = 艺术家名称不可用：{$artist}
 
@@ -53,6 +60,24 @@ repo-get-print = 将获取的专辑信息输出到控制台而非导入
 repo-get-cue-keyword = 当元数据缺失时，使用该关键字搜索 VGMdb
 repo-get-cue-catalog = 当 catalog 不存在时，手动指定
 repo-cue-insufficient-information = CUE 文件未能提供足够的信息
+repo-get-id = 专辑在数据源中的标识符，指定时不进行搜索
+repo-get-keyword = 用于搜索专辑的关键字，默认使用 catalog
+repo-get-catalog = 专辑的 catalog，将覆盖数据源提供的 catalog
+repo-get-discogs-token = Discogs 的个人访问令牌，搜索时需要提供
+repo-get-itunes-country = 搜索的 iTunes Store 的两位国家代码，如 jp
+repo-get-missing-query = 需要提供标识符、关键字或 catalog 之一
+repo-get-missing-catalog = 数据源未提供 catalog，请手动指定
+repo-get-not-found = 在 {$source} 上未找到关键字为 '{$keyword}' 的专辑
+repo-get-select = 请选择专辑：
+
+repo-update = 使用远程数据源的专辑信息更新仓库中的专辑
+repo-update-from = 获取专辑信息的数据源
+repo-update-yes = 不进行询问，应用所有修改
+repo-update-catalog = 需要更新的专辑的品番
+repo-update-multiple-albums = 仓库中存在多个品番为 {$catalog} 的专辑
+repo-update-no-change = 专辑信息与数据源一致
+repo-update-select = 请选择需要应用的修改：
+repo-update-done = 已对 {$path} 应用 {$count} 处修改
 
 repo-edit = 当元数据仓库中存在该专辑时，打开仓库中对应的文件
 repo-lint = 检查仓库数据的合法性
@@ -64,13 +89,70 @@ repo-print-input = 需要输出的对象。可以是标签名称或专辑品番
 
 repo-db = 生成元数据仓库对应的数据库文件
 
+repo-query = 查询元数据仓库数据库中的专辑
+repo-query-database = 元数据仓库数据库的路径。未指定时将根据仓库生成数据库
+repo-query-artist = 专辑艺术家或任意音轨艺术家包含给定文本的专辑
+repo-query-after = 在给定日期当天或之后发行的专辑
+repo-query-before = 在给定日期之前发行的专辑
+repo-query-type = 至少包含一首给定类型音轨的专辑
+repo-query-tag = 带有给定标签的专辑
+repo-query-recursive = 同时包含带有该标签子标签的专辑
+repo-query-catalog = 品番以给定前缀开头的专辑
+repo-query-sort = 专辑的排序方式
+repo-query-desc = 按降序排列
+repo-query-format = 输出格式
+repo-search = 在元数据仓库中搜索专辑、碟片、音轨与标签
+repo-search-query = 搜索内容。平假名与片假名视为相同
+repo-search-fuzzy = 允许匹配存在一处拼写错误的词
+repo-search-index = 搜索索引的路径。不存在时将自动构建
+repo-search-format = 输出格式
+
+repo-history = 显示专辑在仓库历史中的变更
+repo-history-album = 专辑的品番或专辑 ID
+repo-history-disc = 仅显示指定碟片的变更，从 1 开始
+repo-history-track = 仅显示碟片中指定音轨的变更，从 1 开始
+repo-history-blame = 显示最后修改每个字段的提交，而非全部变更
+
+repo-merge-driver = 逐字段合并专辑文件，可作为 git 合并驱动使用
+repo-merge-driver-install = 在 git 配置与 .gitattributes 中将此命令注册为专辑文件的合并驱动
+repo-merge-driver-marker-size = 冲突标记的长度
+repo-merge-driver-path = 被合并文件在仓库中的路径，用于输出信息
+repo-merge-driver-installed = 合并驱动已安装
+repo-merge-driver-field = 存在冲突的字段：{$field}
+repo-merge-driver-conflict = 无法自动合并 {$path}
+
+repo-lsp = 通过标准输入输出启动专辑与标签文件的语言服务器
+repo-lsp-tags-failed = 从仓库加载标签失败：{$error}
+repo-lsp-undefined-tag = 未定义的标签：{$tag}
+repo-lsp-hover-artist = 艺术家：{$artist}
+repo-lsp-hover-track-type = 音轨类型：{$track_type}
+repo-lsp-track-type-normal = 有人声的歌曲
+repo-lsp-track-type-instrumental = 歌曲的伴奏版本，如 off vocal 或卡拉 OK
+repo-lsp-track-type-absolute = 本身不含人声的音乐，如 BGM
+repo-lsp-track-type-drama = 广播剧
+repo-lsp-track-type-radio = 电台节目
+repo-lsp-track-type-vocal = 歌曲的纯人声版本
+
+repo-tag = 管理仓库中的标签
+repo-tag-rename = 重命名标签，并改写引用该标签的专辑与标签
+repo-tag-merge = 将标签合并到另一标签，并改写引用该标签的专辑与标签
+repo-tag-from = 需要重命名或合并的标签，如 artist:Name
+repo-tag-rename-to = 标签的新名称，省略类型时保留原类型
+repo-tag-merge-into = 合并的目标标签，被合并标签的名称将成为其别名
+repo-tag-renamed = 已将 {$from} 重命名为 {$to}，修改了 {$count} 个文件
+repo-tag-merged = 已将 {$from} 合并到 {$into}，修改了 {$count} 个文件
+
 repo-migrate = 迁移旧版本元数据仓库到新版本
 repo-migrate-album-id = 为缺少 album_id 字段的专辑添加这一字段
 
+repo-upgrade = 将仓库中的专辑文件升级到最新的格式版本
+repo-upgrade-done = 已将 {$count} 张专辑升级到格式版本 {$version}
+
 
 ## Library
 library = 提供音频仓库的管理功能
 library-tag = 将元数据仓库中的数据应用到专辑
+library-tag-locale = 按顺序指定标题和艺术家的首选语言，未本地化时使用原文
 library-link = 以符号链接将约定目录格式转换为严格目录格式
 
 
@@ -43,6 +43,10 @@ pub enum LibraryAction {
 
 #[derive(Args, Debug, Clone)]
 pub struct LibraryApplyTagAction {
+    #[clap(short, long = "locale")]
+    #[clap(help = ll!("library-tag-locale"))]
+    locales: Vec<String>,
+
     #[clap(required = true)]
     directories: Vec<PathBuf>,
 }
@@ -53,6 +57,7 @@ pub fn library_apply_tag(
     manager: RepositoryManager,
 ) -> anyhow::Result<()> {
     let manager = manager.into_owned_manager()?;
+    let locales: Vec<_> = me.locales.iter().map(String::as_str).collect();
     for path in me.directories {
         if !path.is_dir() {
             anyhow::bail!("{} is not a directory", path.display());
@@ -69,7 +74,7 @@ pub fn library_apply_tag(
                 .albums()
                 .get(&Uuid::parse_str(folder_name.as_ref())?)
                 .ok_or_else(|| anyhow::anyhow!("Album {} not found", folder_name))?;
-            album.apply_strict(&path, true)?;
+            album.apply_strict(&path, true, &locales)?;
         } else if let Ok(AlbumFolderInfo {
             release_date,
             catalog,
@@ -109,7 +114,7 @@ pub fn library_apply_tag(
             if album.discs_len() != disc_count {
                 bail!("discs.len() != disc_count!");
             }
-            album.apply_convention(&path)?;
+            album.apply_convention(&path, &locales)?;
         } else {
             anyhow::bail!("{} is not a valid album id", folder_name);
         }
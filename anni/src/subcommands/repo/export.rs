@@ -0,0 +1,72 @@
+use crate::args::ActionFile;
+use crate::{ball, ll};
+use anni_common::fs;
+use anni_metadata::model::Album;
+use anni_metadata::schema::{album_schema, tag_schema};
+use anni_repo::models::{album_to_json, repository_schema};
+use anni_repo::RepositoryManager;
+use clap::{Args, ValueEnum};
+use clap_handler::handler;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoExportAction {
+    #[clap(long, value_enum)]
+    #[clap(help = ll!("repo-export-schema"))]
+    schema: Option<RepoSchemaType>,
+
+    #[clap(short, long, default_value = "-")]
+    #[clap(help = ll!("export-to"))]
+    output: ActionFile,
+
+    #[clap(help = ll!("repo-export-catalog"))]
+    catalogs: Vec<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+pub enum RepoSchemaType {
+    Album,
+    Tag,
+    Repo,
+}
+
+#[handler(RepoExportAction)]
+fn repo_export(me: RepoExportAction, manager: &RepositoryManager) -> anyhow::Result<()> {
+    let json = match me.schema {
+        Some(RepoSchemaType::Album) => serde_json::to_value(album_schema())?,
+        Some(RepoSchemaType::Tag) => serde_json::to_value(tag_schema())?,
+        Some(RepoSchemaType::Repo) => serde_json::to_value(repository_schema())?,
+        None => {
+            let paths = if me.catalogs.is_empty() {
+                manager.all_album_paths()?
+            } else {
+                let mut paths = Vec::new();
+                for catalog in me.catalogs.iter() {
+                    let found = manager.album_paths(catalog)?;
+                    if found.is_empty() {
+                        ball!("repo-album-not-found", catalog = catalog);
+                    }
+                    paths.extend(found);
+                }
+                paths
+            };
+
+            let mut albums = Vec::with_capacity(paths.len());
+            for path in paths {
+                let album = Album::from_str(&fs::read_to_string(&path)?)?;
+                albums.push(album_to_json(&album));
+            }
+            // a single album is exported as is, so that it can be validated by album schema
+            match albums.len() {
+                1 => albums.pop().unwrap(),
+                _ => albums.into(),
+            }
+        }
+    };
+
+    let mut dst = me.output.to_writer()?;
+    serde_json::to_writer_pretty(&mut dst, &json)?;
+    writeln!(dst)?;
+    Ok(())
+}
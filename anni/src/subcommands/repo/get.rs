@@ -1,4 +1,4 @@
-use crate::{ball, ll};
+use crate::{ball, fl, ll};
 use anni_common::fs;
 use anni_metadata::model::Album;
 use anni_metadata::model::AlbumInfo;
@@ -7,17 +7,17 @@ use anni_metadata::model::Disc;
 use anni_metadata::model::DiscInfo;
 use anni_metadata::model::Track;
 use anni_metadata::model::TrackType;
+use anni_metadata::source::{
+    DiscogsSource, ITunesSource, MetadataSource, MusicBrainzSource, SearchCandidate,
+};
 use anni_repo::RepositoryManager;
 use anni_vgmdb::VGMClient;
-use chrono::Datelike;
+use async_trait::async_trait;
 use clap::{Args, Subcommand};
 use clap_handler::{handler, Handler};
 use cuna::Cuna;
-use musicbrainz_rs::entity::artist_credit::ArtistCredit;
-use musicbrainz_rs::entity::release::Release;
-use musicbrainz_rs::Fetch;
+use inquire::Select;
 use std::path::PathBuf;
-use uuid::Uuid;
 
 #[derive(Args, Handler, Debug, Clone)]
 pub struct RepoGetAction {
@@ -36,72 +36,180 @@ pub enum RepoGetSubcommand {
     Cue(RepoGetCue),
     #[clap(name = "musicbrainz")]
     Musicbrainz(RepoGetMusicbrainz),
+    #[clap(name = "discogs")]
+    Discogs(RepoGetDiscogs),
+    #[clap(name = "itunes")]
+    ITunes(RepoGetITunes),
 }
 
-async fn search_album(keyword: &str) -> anyhow::Result<Album> {
-    let client = VGMClient::default();
-    let search = client.search_albums(keyword).await?;
-    let album_got = search.into_album(None).await?;
-
-    let release_date = {
-        let split = album_got.release_date().split('-').collect::<Vec<_>>();
-        AnniDate::from_parts(
-            split[0],
-            split.get(1).unwrap_or(&"0"),
-            split.get(2).unwrap_or(&"0"),
-        )?
+/// Options shared by subcommands which get album from a [MetadataSource].
+#[derive(Args, Debug, Clone)]
+pub struct RepoGetSourceOptions {
+    #[clap(long, help = ll!("repo-get-id"))]
+    id: Option<String>,
+    #[clap(short = 'k', long, help = ll!("repo-get-keyword"))]
+    keyword: Option<String>,
+
+    #[clap(help = ll!("repo-get-catalog"))]
+    catalog: Option<String>,
+}
+
+impl RepoGetSourceOptions {
+    async fn get(&self, source: &dyn MetadataSource) -> anyhow::Result<Album> {
+        let keyword = self.keyword.as_ref().or(self.catalog.as_ref());
+        fetch_album(source, self.id.as_deref(), keyword.map(String::as_str)).await
+    }
+
+    fn save(
+        &self,
+        mut album: Album,
+        manager: &RepositoryManager,
+        get: &RepoGetAction,
+    ) -> anyhow::Result<()> {
+        if let Some(catalog) = &self.catalog {
+            album.catalog = catalog.to_string();
+        }
+        // sources may not provide catalog of discs
+        let catalog = album.catalog().to_string();
+        for disc in album.discs.iter_mut() {
+            if disc.catalog.is_empty() {
+                disc.catalog = catalog.clone();
+            }
+        }
+
+        if get.print {
+            println!("{}", album.format_to_string());
+        } else {
+            if album.catalog().is_empty() {
+                ball!("repo-get-missing-catalog");
+            }
+            manager.add_album(album, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fetch album from `source` by `id`, or search with `keyword` if `id` is not provided.
+///
+/// If several albums are found, the user is asked to select one of them.
+pub(super) async fn fetch_album(
+    source: &dyn MetadataSource,
+    id: Option<&str>,
+    keyword: Option<&str>,
+) -> anyhow::Result<Album> {
+    if let Some(id) = id {
+        return source.fetch(id).await;
+    }
+
+    let Some(keyword) = keyword else {
+        ball!("repo-get-missing-query");
+    };
+    let mut candidates = source.search(keyword).await?;
+    let candidate = match candidates.len() {
+        0 => ball!(
+            "repo-get-not-found",
+            source = source.name(),
+            keyword = keyword
+        ),
+        1 => candidates.remove(0),
+        _ => select_candidate(candidates)?,
     };
+    source.fetch(&candidate.id).await
+}
 
-    let discs = album_got
-        .discs
-        .iter()
-        .map(|disc_got| {
-            let disc = DiscInfo::new(
-                album_got.catalog().unwrap_or("").to_string(),
-                Some(disc_got.title.to_string()),
-                None,
-                None,
-                None,
-                Default::default(),
-            );
+fn select_candidate(candidates: Vec<SearchCandidate>) -> anyhow::Result<SearchCandidate> {
+    match Select::new(&fl!("repo-get-select"), candidates).prompt() {
+        Ok(candidate) => Ok(candidate),
+        Err(_) => bail!("Aborted"),
+    }
+}
 
-            let tracks = disc_got
-                .tracks
-                .iter()
-                .map(|track| {
-                    let title = track.get().unwrap().to_string();
-                    let track_type = TrackType::guess(&title);
-                    Track::new(
-                        title,
-                        Some("".to_string()),
-                        None,
-                        track_type,
-                        Default::default(),
-                    )
-                })
-                .collect();
-
-            Disc::new(disc, tracks)
-        })
-        .collect();
-
-    Ok(Album::new(
-        AlbumInfo {
-            title: album_got.title().unwrap().to_string().into(),
-            release_date,
-            catalog: album_got.catalog().unwrap_or("").to_string(),
-            ..Default::default()
-        },
-        discs,
-    ))
+/// [VGMdb](https://vgmdb.net) albums, identified by their album id.
+#[derive(Default)]
+pub(super) struct VGMdbSource(VGMClient);
+
+#[async_trait]
+impl MetadataSource for VGMdbSource {
+    fn name(&self) -> &'static str {
+        "vgmdb"
+    }
+
+    async fn fetch(&self, id: &str) -> anyhow::Result<Album> {
+        let album_got = self.0.album(id).await?;
+
+        let release_date = {
+            let split = album_got.release_date().split('-').collect::<Vec<_>>();
+            AnniDate::from_parts(
+                split[0],
+                split.get(1).unwrap_or(&"0"),
+                split.get(2).unwrap_or(&"0"),
+            )?
+        };
+
+        let discs = album_got
+            .discs
+            .iter()
+            .map(|disc_got| {
+                let disc = DiscInfo::new(
+                    album_got.catalog().unwrap_or("").to_string(),
+                    Some(disc_got.title.to_string()),
+                    None,
+                    None,
+                    None,
+                    Default::default(),
+                );
+
+                let tracks = disc_got
+                    .tracks
+                    .iter()
+                    .map(|track| {
+                        let title = track.get().unwrap().to_string();
+                        let track_type = TrackType::guess(&title);
+                        Track::new(
+                            title,
+                            Some("".to_string()),
+                            None,
+                            track_type,
+                            Default::default(),
+                        )
+                    })
+                    .collect();
+
+                Disc::new(disc, tracks)
+            })
+            .collect();
+
+        Ok(Album::new(
+            AlbumInfo {
+                title: album_got.title().unwrap().to_string().into(),
+                release_date,
+                catalog: album_got.catalog().unwrap_or("").to_string(),
+                ..Default::default()
+            },
+            discs,
+        ))
+    }
+
+    async fn search(&self, keyword: &str) -> anyhow::Result<Vec<SearchCandidate>> {
+        let search = self.0.search_albums(keyword).await?;
+        Ok(search
+            .albums()
+            .iter()
+            .map(|album| SearchCandidate {
+                id: album.id().to_string(),
+                title: album.title.get().unwrap_or_default().to_string(),
+                artist: None,
+                catalog: Some(album.catalog.to_string()).filter(|catalog| !catalog.is_empty()),
+                release_date: Some(album.release_date.to_string()),
+            })
+            .collect())
+    }
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct RepoGetVGMdb {
-    #[clap(short = 'k', long)]
-    keyword: Option<String>,
-
-    catalog: String,
+    #[clap(flatten)]
+    source: RepoGetSourceOptions,
 }
 
 #[handler(RepoGetVGMdb)]
@@ -110,17 +218,9 @@ async fn repo_get_vgmdb(
     manager: &RepositoryManager,
     get: &RepoGetAction,
 ) -> anyhow::Result<()> {
-    let catalog = &options.catalog;
-
-    let mut album = search_album(&options.keyword.as_deref().unwrap_or(catalog)).await?;
-
-    if get.print {
-        println!("{}", album.format_to_string());
-    } else {
-        album.catalog = options.catalog;
-        manager.add_album(album, false)?;
-    }
-    Ok(())
+    let source = VGMdbSource::default();
+    let album = options.source.get(&source).await?;
+    options.source.save(album, manager, get)
 }
 
 #[derive(Args, Debug, Clone)]
@@ -140,25 +240,26 @@ async fn repo_get_cue(
     get: &RepoGetAction,
 ) -> anyhow::Result<()> {
     let path = &options.path;
+    let source = VGMdbSource::default();
 
     let s = fs::read_to_string(path)?;
     let cue = Cuna::new(&s)?;
     let mut album = match (cue.catalog(), options.keyword.as_ref()) {
-        // if catalog is found, fetch metadata from vgmdb
-        (Some(catalog), _) => search_album(&catalog.to_string()).await?,
+        // if catalog is found, search vgmdb with it
+        (Some(catalog), _) => fetch_album(&source, None, Some(&catalog.to_string())).await?,
         // otherwise try to search with keyword
         (None, Some(keyword)) => {
             warn!(
                 "catalog is unavailable, trying to search vgmdb with keyword `{}`",
                 keyword
             );
-            search_album(&keyword.to_string()).await?
+            fetch_album(&source, None, Some(keyword)).await?
         }
         // if none is available, try to search with `TITLE` filed in the cue file
         (None, None) => match cue.title().first() {
             Some(title) => {
                 warn!("catalog is unavailable, trying to search vgmdb with title `{}`, which may be inaccurate", title);
-                search_album(&title.to_string()).await?
+                fetch_album(&source, None, Some(title)).await?
             }
             None => ball!("repo-cue-insufficient-information"),
         },
@@ -198,14 +299,13 @@ async fn repo_get_cue(
 pub struct RepoGetMusicbrainz {
     #[clap(
         env = "MUSICBRAINZ_BASE_URL",
-        default_value = "https://musicbrainz.org/ws/2"
+        default_value = MusicBrainzSource::BASE_URL
     )]
     #[clap(long)]
     base_url: String,
 
-    #[clap(long)]
-    id: String,
-    catalog: String,
+    #[clap(flatten)]
+    source: RepoGetSourceOptions,
 }
 
 #[handler(RepoGetMusicbrainz)]
@@ -214,85 +314,47 @@ async fn repo_get_musicbrainz(
     manager: &RepositoryManager,
     get: &RepoGetAction,
 ) -> anyhow::Result<()> {
-    musicbrainz_rs::config::set_base_url(options.base_url);
-
-    let release = Release::fetch()
-        .id(&options.id)
-        .with_release_groups()
-        .with_recordings()
-        .with_artist_credits()
-        .execute()
-        .await?;
-    let release_date = release
-        .date
-        .map(|date| AnniDate::new(date.year() as u16, date.month() as u8, date.day() as u8))
-        .unwrap_or(AnniDate::UNKNOWN);
-    let to_artist = |artists: Vec<ArtistCredit>| {
-        artists
-            .iter()
-            .fold(String::new(), |acc, artist| {
-                format!("{}{}、", acc, artist.name)
-            })
-            .trim_end_matches('、')
-            .to_string()
-    };
-    let artist = release
-        .release_group
-        .and_then(|rg| rg.artist_credit)
-        .map(to_artist)
-        .unwrap_or_default();
-
-    let discs = release
-        .media
-        .into_iter()
-        .flatten()
-        .map(|media| {
-            let disc = DiscInfo::new(
-                options.catalog.to_owned(),
-                media.title,
-                None,
-                None,
-                None,
-                Default::default(),
-            );
+    let source = MusicBrainzSource::new(&options.base_url);
+    let album = options.source.get(&source).await?;
+    options.source.save(album, manager, get)
+}
 
-            let tracks = media
-                .tracks
-                .into_iter()
-                .flatten()
-                .map(|track| {
-                    let track_type = TrackType::guess(&track.title);
-                    Track::new(
-                        track.title,
-                        track.recording.artist_credit.map(to_artist),
-                        None,
-                        track_type,
-                        Default::default(),
-                    )
-                })
-                .collect();
-            Disc::new(disc, tracks)
-        })
-        .collect();
-
-    let mut album = Album::new(
-        AlbumInfo {
-            album_id: Uuid::parse_str(&options.id)
-                .ok()
-                .unwrap_or_else(Uuid::new_v4),
-            title: release.title,
-            artist,
-            release_date,
-            catalog: options.catalog,
-            ..Default::default()
-        },
-        discs,
-    );
+#[derive(Args, Debug, Clone)]
+pub struct RepoGetDiscogs {
+    #[clap(long, env = "DISCOGS_TOKEN", help = ll!("repo-get-discogs-token"))]
+    token: Option<String>,
 
-    if get.print {
-        println!("{}", album.format_to_string());
-    } else {
-        manager.add_album(album, false)?;
-    }
-    Ok(())
+    #[clap(flatten)]
+    source: RepoGetSourceOptions,
+}
+
+#[handler(RepoGetDiscogs)]
+async fn repo_get_discogs(
+    options: RepoGetDiscogs,
+    manager: &RepositoryManager,
+    get: &RepoGetAction,
+) -> anyhow::Result<()> {
+    let source = DiscogsSource::new(options.token);
+    let album = options.source.get(&source).await?;
+    options.source.save(album, manager, get)
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoGetITunes {
+    #[clap(long, help = ll!("repo-get-itunes-country"))]
+    country: Option<String>,
+
+    #[clap(flatten)]
+    source: RepoGetSourceOptions,
+}
+
+#[handler(RepoGetITunes)]
+async fn repo_get_itunes(
+    options: RepoGetITunes,
+    manager: &RepositoryManager,
+    get: &RepoGetAction,
+) -> anyhow::Result<()> {
+    let source = ITunesSource::new(options.country);
+    let album = options.source.get(&source).await?;
+    options.source.save(album, manager, get)
 }
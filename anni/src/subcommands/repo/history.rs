@@ -0,0 +1,143 @@
+use crate::{ball, ll};
+use anni_common::fs;
+use anni_metadata::model::Album;
+use anni_repo::diff::{FieldPath, PathSegment};
+use anni_repo::history::{blame, AlbumCommit};
+use anni_repo::RepositoryManager;
+use chrono::{Local, TimeZone};
+use clap::Args;
+use clap_handler::handler;
+use std::str::FromStr;
+use toml::Value;
+use uuid::Uuid;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoHistoryAction {
+    #[clap(short, long)]
+    #[clap(help = ll!("repo-history-disc"))]
+    disc: Option<usize>,
+
+    #[clap(short, long, requires = "disc")]
+    #[clap(help = ll!("repo-history-track"))]
+    track: Option<usize>,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-history-blame"))]
+    blame: bool,
+
+    #[clap(required = true)]
+    #[clap(help = ll!("repo-history-album"))]
+    album: String,
+}
+
+impl RepoHistoryAction {
+    /// Path of selected disc or track. Disc and track ids start from 1.
+    fn field_path(&self) -> FieldPath {
+        let mut segments = Vec::new();
+        if let Some(disc) = self.disc {
+            segments.push(PathSegment::Key("discs".to_string()));
+            segments.push(PathSegment::Index(disc.saturating_sub(1)));
+            if let Some(track) = self.track {
+                segments.push(PathSegment::Key("tracks".to_string()));
+                segments.push(PathSegment::Index(track.saturating_sub(1)));
+            }
+        }
+        FieldPath::new(segments)
+    }
+}
+
+#[handler(RepoHistoryAction)]
+fn repo_history(me: RepoHistoryAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let albums: Vec<_> = match Uuid::from_str(&me.album) {
+        Ok(album_id) => {
+            // album may have been removed, but its history is still available
+            let album = manager
+                .all_album_paths()?
+                .into_iter()
+                .filter_map(|path| Album::from_str(&fs::read_to_string(path).ok()?).ok())
+                .find(|album| album.album_id() == album_id);
+            vec![(album_id, album)]
+        }
+        Err(_) => manager
+            .load_albums(&me.album)?
+            .into_iter()
+            .map(|album| (album.album_id(), Some(album)))
+            .collect(),
+    };
+    if albums.is_empty() {
+        ball!("repo-album-not-found", catalog = me.album);
+    }
+
+    let path = me.field_path();
+    for (album_id, album) in albums {
+        let history: Vec<_> = manager
+            .album_history(&album_id)?
+            .into_iter()
+            .filter(|commit| commit.touches(&path))
+            .collect();
+
+        if me.blame {
+            let Some(album) = album else {
+                ball!("repo-album-not-found", catalog = me.album);
+            };
+            for (field, value, commit) in blame(&album, &history) {
+                if field.starts_with(&path) {
+                    let commit = match commit {
+                        Some(commit) => format!(
+                            "{} {} {}",
+                            &commit.id[..8],
+                            commit.author,
+                            format_time(commit.time)
+                        ),
+                        None => "00000000 Not Committed Yet".to_string(),
+                    };
+                    println!("{commit}\t{field} = {value}");
+                }
+            }
+            continue;
+        }
+
+        for commit in history {
+            print_commit(&commit, &path);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_commit(commit: &AlbumCommit, path: &FieldPath) {
+    println!("commit {}", commit.id);
+    println!("Author: {} <{}>", commit.author, commit.email);
+    println!("Date:   {}", format_time(commit.time));
+    println!();
+    println!("    {}", commit.summary);
+    println!();
+    for change in commit.changes.iter() {
+        if !change.path.starts_with(path) && !path.starts_with(&change.path) {
+            continue;
+        }
+        match (&change.old, &change.new) {
+            (None, _) if change.path.segments().is_empty() => println!("    + album"),
+            (_, None) if change.path.segments().is_empty() => println!("    - album"),
+            (old, new) => println!(
+                "    {}: {} -> {}",
+                change.path,
+                format_value(old.as_ref()),
+                format_value(new.as_ref())
+            ),
+        }
+    }
+    println!();
+}
+
+fn format_value(value: Option<&Value>) -> String {
+    value.map_or_else(|| "(none)".to_string(), Value::to_string)
+}
+
+fn format_time(time: i64) -> String {
+    Local
+        .timestamp_opt(time, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S %z").to_string())
+        .unwrap_or_default()
+}
@@ -1,13 +1,16 @@
-use crate::{ball, fl};
+use crate::{ball, fl, ll};
 use anni_common::diagnostic::*;
 use anni_common::lint::{AnniLinter, AnniLinterReviewDogJsonLineFormat, AnniLinterTextFormat};
 use anni_common::validator::{ValidateResult, ValidatorList};
 use anni_metadata::model::{Album, DiscRef, UNKNOWN_ARTIST};
+use anni_repo::diff::FieldPath;
+use anni_repo::span::AlbumSpans;
 use anni_repo::RepositoryManager;
 use clap::{Args, ValueEnum};
 use clap_handler::handler;
 use std::collections::HashSet;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 #[derive(Args, Debug, Clone)]
 pub struct RepoLintAction {
@@ -15,6 +18,10 @@ pub struct RepoLintAction {
     #[clap(value_enum, default_value = "text")]
     format: RepoLintFormat,
 
+    #[clap(long)]
+    #[clap(help = ll!("repo-lint-fix"))]
+    fix: bool,
+
     albums: Vec<String>,
 }
 
@@ -38,10 +45,13 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
     if me.albums.is_empty() {
         // initialize owned manager
         let manager = manager.into_owned_manager()?;
+        let root = manager.repo.root();
         // validate all albums
         for album in manager.albums_iter() {
             let album_path = manager.album_path(&album.album_id()).unwrap();
-            validate_album(album, album_path, report.as_mut());
+            let mut source = AlbumSource::open(root, album_path, me.fix)?;
+            validate_album(album, &mut source, report.as_mut());
+            source.save()?;
         }
         // check tag loop
         if let Some(path) = manager.check_tags_loop() {
@@ -55,6 +65,20 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
                 ),
             ));
         }
+        // check types of tag relations
+        for (parent, child) in manager.check_tag_types() {
+            report.add(Diagnostic::error(
+                DiagnosticMessage {
+                    message: fl!(
+                        "repo-lint-tag-type",
+                        parent = parent.to_string(),
+                        child = child.to_string()
+                    ),
+                    target: MetadataDiagnosticTarget::Tag(child.to_string()),
+                },
+                DiagnosticLocation::simple(manager.tag_path(child).unwrap().display().to_string()),
+            ));
+        }
     } else {
         // validate selected albums
         for album in me.albums.iter() {
@@ -64,7 +88,9 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
                 .iter()
                 .zip(manager.album_paths(album)?)
             {
-                validate_album(&album, &path, report.as_mut());
+                let mut source = AlbumSource::open(manager.root(), &path, me.fix)?;
+                validate_album(album, &mut source, report.as_mut());
+                source.save()?;
             }
         }
     }
@@ -77,19 +103,121 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
     Ok(())
 }
 
-fn validate_album<P>(album: &Album, path: P, report: &mut dyn AnniLinter<MetadataDiagnosticTarget>)
-where
-    P: AsRef<Path>,
-{
+/// Album file being validated, with locations of its fields.
+pub(super) struct AlbumSource {
+    /// Path shown in diagnostics
+    path: PathBuf,
+    /// Path of album file on disk
+    file: PathBuf,
+    input: String,
+    spans: AlbumSpans,
+    /// Replacements to be written back to file, only collected in `--fix` mode.
+    fixes: Option<Vec<(Range<usize>, String)>>,
+}
+
+impl AlbumSource {
+    /// Open album file at `path`, which may be relative to repository `root`.
+    fn open(root: &Path, path: &Path, fix: bool) -> anyhow::Result<Self> {
+        let file = root.join(path);
+        let input = std::fs::read_to_string(&file)?;
+        let spans = AlbumSpans::parse(&input)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            input,
+            spans,
+            fixes: fix.then(Vec::new),
+        })
+    }
+
+    /// Album which is not read from disk, such as an unsaved document in editor.
+    pub(super) fn new(path: PathBuf, input: String, spans: AlbumSpans) -> Self {
+        Self {
+            file: path.clone(),
+            path,
+            input,
+            spans,
+            fixes: None,
+        }
+    }
+
+    pub(super) fn spans(&self) -> &AlbumSpans {
+        &self.spans
+    }
+
+    /// Location of `field`, e.g. `discs[0].title`, or the whole file if it does not exist.
+    fn location(&self, field: &str) -> DiagnosticLocation {
+        DiagnosticLocation {
+            path: self.path.display().to_string(),
+            range: self
+                .range(field)
+                .map(|span| DiagnosticRange::from_span(&self.input, span)),
+        }
+    }
+
+    fn range(&self, field: &str) -> Option<Range<usize>> {
+        let field: FieldPath = field.parse().ok()?;
+        self.spans.get(&field).map(|span| span.full())
+    }
+
+    fn value_range(&self, field: &str) -> Option<Range<usize>> {
+        let field: FieldPath = field.parse().ok()?;
+        self.spans.get(&field).map(|span| span.value.clone())
+    }
+
+    /// Write collected fixes back to file.
+    fn save(self) -> anyhow::Result<()> {
+        let Some(mut fixes) = self.fixes else {
+            return Ok(());
+        };
+        if fixes.is_empty() {
+            return Ok(());
+        }
+
+        // apply from the end of file, so that earlier ranges are not shifted
+        fixes.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut output = self.input;
+        let mut applied = 0;
+        let mut last_start = usize::MAX;
+        for (range, text) in fixes {
+            // the same value may be fixed by multiple findings
+            if range.end > last_start {
+                continue;
+            }
+            last_start = range.start;
+            output.replace_range(range, &text);
+            applied += 1;
+        }
+        std::fs::write(&self.file, output)?;
+        info!(
+            target: "anni",
+            "{}",
+            fl!(
+                "repo-lint-fixed",
+                count = applied,
+                path = self.path.display().to_string()
+            )
+        );
+        Ok(())
+    }
+}
+
+pub(super) fn validate_album(
+    album: &Album,
+    source: &mut AlbumSource,
+    report: &mut dyn AnniLinter<MetadataDiagnosticTarget>,
+) {
     let album_id = album.album_id().to_string();
 
     let string_validator = ValidatorList::new(&["trim", "dot", "tidle"]).unwrap();
     let artist_validator = ValidatorList::new(&["trim", "dot", "tidle", "artist"]).unwrap();
+    let barcode_validator = ValidatorList::new(&["barcode"]).unwrap();
+    let isrc_validator = ValidatorList::new(&["isrc"]).unwrap();
 
     validate_string(
-        path.as_ref(),
+        source,
         MetadataDiagnosticTarget::album(album_id.clone()),
-        Some("title".to_string()),
+        "album.title",
         &string_validator,
         album.title_raw().as_ref(),
         report,
@@ -97,9 +225,9 @@ where
 
     if let Some(edition) = album.edition() {
         validate_string(
-            path.as_ref(),
+            source,
             MetadataDiagnosticTarget::album(album_id.clone()),
-            Some("edition".to_string()),
+            "album.edition",
             &string_validator,
             edition,
             report,
@@ -107,9 +235,9 @@ where
     }
 
     validate_string(
-        path.as_ref(),
+        source,
         MetadataDiagnosticTarget::album(album_id.clone()),
-        Some("artist".to_string()),
+        "album.artist",
         &artist_validator,
         album.artist(),
         report,
@@ -121,101 +249,150 @@ where
                 message: "Unknown artist".to_string(),
                 target: MetadataDiagnosticTarget::album(album_id.clone()),
             },
-            DiagnosticLocation::simple(path.as_ref().display().to_string()),
+            source.location("album.artist"),
         ));
     }
 
-    validate_disc_catalog(album.iter().collect(), &album_id, path.as_ref(), report);
+    if let Some(barcode) = &album.ids.barcode {
+        validate_string(
+            source,
+            MetadataDiagnosticTarget::album(album_id.clone()),
+            "album.ids.barcode",
+            &barcode_validator,
+            barcode,
+            report,
+        );
+    }
+
+    validate_disc_catalog(album.iter().collect(), &album_id, source, report);
 
-    for (disc_id, disc) in album.iter().enumerate() {
-        let disc_id = (disc_id + 1) as u8;
+    for (disc_index, disc) in album.iter().enumerate() {
+        let disc_id = (disc_index + 1) as u8;
 
         validate_string(
-            path.as_ref(),
+            source,
             MetadataDiagnosticTarget::disc(album_id.clone(), disc_id),
-            Some("title".to_string()),
+            &format!("discs[{disc_index}].title"),
             &string_validator,
             disc.title(),
             report,
         );
         validate_string(
-            path.as_ref(),
+            source,
             MetadataDiagnosticTarget::disc(album_id.clone(), disc_id),
-            Some("artist".to_string()),
+            &format!("discs[{disc_index}].artist"),
             &artist_validator,
             disc.artist(),
             report,
         );
+        if let Some(barcode) = &disc.raw().ids.barcode {
+            validate_string(
+                source,
+                MetadataDiagnosticTarget::disc(album_id.clone(), disc_id),
+                &format!("discs[{disc_index}].ids.barcode"),
+                &barcode_validator,
+                barcode,
+                report,
+            );
+        }
 
-        for (track_id, track) in disc.iter().enumerate() {
-            let track_id = (track_id + 1) as u8;
+        for (track_index, track) in disc.iter().enumerate() {
+            let track_id = (track_index + 1) as u8;
 
             validate_string(
-                path.as_ref(),
+                source,
                 MetadataDiagnosticTarget::track(album_id.clone(), disc_id, track_id),
-                Some("title".to_string()),
+                &format!("discs[{disc_index}].tracks[{track_index}].title"),
                 &string_validator,
                 track.title().as_ref(),
                 report,
             );
             validate_string(
-                path.as_ref(),
+                source,
                 MetadataDiagnosticTarget::track(album_id.clone(), disc_id, track_id),
-                Some("artist".to_string()),
+                &format!("discs[{disc_index}].tracks[{track_index}].artist"),
                 &artist_validator,
                 track.artist(),
                 report,
             );
+            if let Some(isrc) = &track.raw().ids.isrc {
+                validate_string(
+                    source,
+                    MetadataDiagnosticTarget::track(album_id.clone(), disc_id, track_id),
+                    &format!("discs[{disc_index}].tracks[{track_index}].ids.isrc"),
+                    &isrc_validator,
+                    isrc,
+                    report,
+                );
+            }
         }
     }
 }
 
-fn validate_string<P>(
-    path: P,
+fn validate_string(
+    source: &mut AlbumSource,
     target: MetadataDiagnosticTarget,
-    _field: Option<String>,
+    field: &str,
     validator: &ValidatorList,
     value: &str,
     report: &mut dyn AnniLinter<MetadataDiagnosticTarget>,
-) where
-    P: AsRef<Path>,
-{
-    validator
-        .validate(value)
-        .into_iter()
-        .for_each(|(ty, result)| {
-            let severity = match result {
-                ValidateResult::Warning(_) => DiagnosticSeverity::Warning,
-                ValidateResult::Error(_) => DiagnosticSeverity::Error,
-                _ => DiagnosticSeverity::Information,
-            };
-            match result {
-                ValidateResult::Warning(message) | ValidateResult::Error(message) => {
-                    report.add(Diagnostic {
-                        severity,
-                        message: DiagnosticMessage {
-                            message,
-                            target: target.clone(),
-                        },
-                        location: DiagnosticLocation::simple(path.as_ref().display().to_string()),
-                        code: Some(DiagnosticCode::new(format!("{}", ty))),
-                        source: None,
-                        suggestions: vec![],
-                    });
-                }
-                _ => {}
+) {
+    let Some(value_range) = source.value_range(field) else {
+        // value is inherited from album or disc, and would be reported there
+        return;
+    };
+    let fixed = validator.fix(value);
+
+    for (ty, result) in validator.validate(value) {
+        // only suggest fixes which actually resolve this problem
+        let fixed = fixed
+            .as_ref()
+            .filter(|fixed| validator.validate(fixed).iter().all(|(t, _)| *t != ty));
+        if let (Some(fixed), Some(fixes)) = (fixed, source.fixes.as_mut()) {
+            fixes.push((
+                value_range.clone(),
+                toml::Value::from(fixed.as_str()).to_string(),
+            ));
+            continue;
+        }
+
+        let severity = match result {
+            ValidateResult::Warning(_) => DiagnosticSeverity::Warning,
+            ValidateResult::Error(_) => DiagnosticSeverity::Error,
+            _ => DiagnosticSeverity::Information,
+        };
+        match result {
+            ValidateResult::Warning(message) | ValidateResult::Error(message) => {
+                let suggestions = fixed
+                    .map(|fixed| DiagnosticSuggestion {
+                        range: DiagnosticRange::from_span(&source.input, value_range.clone()),
+                        text: toml::Value::from(fixed.as_str()).to_string(),
+                    })
+                    .into_iter()
+                    .collect();
+                report.add(Diagnostic {
+                    severity,
+                    message: DiagnosticMessage {
+                        message,
+                        target: target.clone(),
+                    },
+                    location: source.location(field),
+                    code: Some(DiagnosticCode::new(format!("{}", ty))),
+                    source: None,
+                    suggestions,
+                });
             }
-        });
+            _ => {}
+        }
+    }
 }
 
-fn validate_disc_catalog<P>(
+fn validate_disc_catalog(
     discs: Vec<DiscRef>,
     album_id: &str,
-    path: P,
+    source: &AlbumSource,
     report: &mut dyn AnniLinter<MetadataDiagnosticTarget>,
-) where
-    P: AsRef<Path>,
-{
+) {
     let mut catalogs = HashSet::new();
     discs.iter().zip(1..).for_each(|(disc, disc_id)| {
         if !catalogs.insert(disc.catalog()) {
@@ -224,7 +401,7 @@ fn validate_disc_catalog<P>(
                     target: MetadataDiagnosticTarget::disc(album_id.to_string(), disc_id),
                     message: format!("Duplicate catalog {}", disc.catalog()),
                 },
-                DiagnosticLocation::simple(path.as_ref().display().to_string()),
+                source.location(&format!("discs[{}].catalog", disc_id - 1)),
             ))
         }
     });
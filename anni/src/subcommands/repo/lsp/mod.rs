@@ -0,0 +1,560 @@
+mod protocol;
+
+use super::lint::{validate_album, AlbumSource};
+use crate::fl;
+use anni_common::diagnostic::{
+    self, DiagnosticPosition, DiagnosticSeverity, MetadataDiagnosticTarget,
+};
+use anni_common::lint::AnniLinter;
+use anni_metadata::model::{Album, TagRef, TagType, TrackType};
+use anni_repo::diff::{FieldPath, PathSegment};
+use anni_repo::prelude::Error as RepoError;
+use anni_repo::span::{AlbumSpans, FieldSpan};
+use anni_repo::{OwnedRepositoryManager, RepositoryManager};
+use clap::Args;
+use clap_handler::handler;
+use protocol::*;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use url::Url;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoLspAction {
+    /// Language server always communicates over stdio.
+    /// This flag is accepted because some clients pass it by default.
+    #[clap(long, hide = true)]
+    #[allow(dead_code)]
+    stdio: bool,
+}
+
+#[handler(RepoLspAction)]
+fn repo_lsp(manager: RepositoryManager) -> anyhow::Result<()> {
+    // paths are converted from and to uris, which must be absolute
+    let mut server = LanguageServer::new(manager.root().canonicalize()?);
+    server.run(&mut std::io::stdin().lock(), &mut std::io::stdout().lock())
+}
+
+/// A tag defined in repository.
+struct TagEntry {
+    tag: TagRef<'static>,
+    names: HashMap<String, String>,
+    aliases: Vec<String>,
+    /// Absolute path of the file which defines this tag
+    path: PathBuf,
+}
+
+struct LanguageServer {
+    root: PathBuf,
+    /// Text of opened documents
+    documents: HashMap<Url, String>,
+    /// Tags defined in repository, reloaded when a tag file is saved
+    tags: Option<Vec<TagEntry>>,
+}
+
+impl LanguageServer {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            documents: HashMap::new(),
+            tags: None,
+        }
+    }
+
+    fn run<R, W>(&mut self, reader: &mut R, writer: &mut W) -> anyhow::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        self.load_tags();
+
+        while let Some(message) = read_message(reader)? {
+            let Some(method) = message.method else {
+                continue;
+            };
+
+            match message.id {
+                Some(id) => {
+                    let response = match self.handle_request(&method, message.params) {
+                        Ok(result) => response(id, result),
+                        Err(error) => error_response(id, error),
+                    };
+                    write_message(writer, &response)?;
+                }
+                None if method == "exit" => break,
+                None => {
+                    for notification in self.handle_notification(&method, message.params) {
+                        write_message(writer, &notification)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, ResponseError> {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": {
+                        "openClose": true,
+                        // full
+                        "change": 1,
+                        "save": { "includeText": false },
+                    },
+                    "completionProvider": { "triggerCharacters": ["\"", ":"] },
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                },
+                "serverInfo": {
+                    "name": "anni",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/completion" => {
+                let params: TextDocumentPositionParams = parse_params(params)?;
+                Ok(serde_json::to_value(self.completion(&params))?)
+            }
+            "textDocument/definition" => {
+                let params: TextDocumentPositionParams = parse_params(params)?;
+                Ok(serde_json::to_value(self.definition(&params))?)
+            }
+            "textDocument/hover" => {
+                let params: TextDocumentPositionParams = parse_params(params)?;
+                Ok(serde_json::to_value(self.hover(&params))?)
+            }
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method {method}"),
+            }),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Value) -> Vec<Value> {
+        match method {
+            "textDocument/didOpen" => {
+                let Ok(params) = parse_params::<DidOpenTextDocumentParams>(params) else {
+                    return Vec::new();
+                };
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                vec![self.publish_diagnostics(&uri)]
+            }
+            "textDocument/didChange" => {
+                let Ok(params) = parse_params::<DidChangeTextDocumentParams>(params) else {
+                    return Vec::new();
+                };
+                // only full document sync is supported
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri, change.text);
+                }
+                Vec::new()
+            }
+            "textDocument/didSave" => {
+                let Ok(params) = parse_params::<DidSaveTextDocumentParams>(params) else {
+                    return Vec::new();
+                };
+                let uri = params.text_document.uri;
+                if let Some(text) = params.text {
+                    self.documents.insert(uri.clone(), text);
+                }
+
+                let is_tag_file = uri
+                    .to_file_path()
+                    .is_ok_and(|path| path.starts_with(self.root.join("tag")));
+                if is_tag_file {
+                    // tags may be used by any opened album
+                    self.load_tags();
+                    let uris: Vec<_> = self.documents.keys().cloned().collect();
+                    uris.iter()
+                        .map(|uri| self.publish_diagnostics(uri))
+                        .collect()
+                } else {
+                    vec![self.publish_diagnostics(&uri)]
+                }
+            }
+            "textDocument/didClose" => {
+                let Ok(params) = parse_params::<DidCloseTextDocumentParams>(params) else {
+                    return Vec::new();
+                };
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                vec![notification(
+                    "textDocument/publishDiagnostics",
+                    PublishDiagnosticsParams {
+                        uri,
+                        diagnostics: Vec::new(),
+                    },
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Reload tags from repository.
+    ///
+    /// Albums are not loaded, so that albums referring to undefined tags do not block tags.
+    /// Previously loaded tags are kept if tags can not be loaded,
+    /// which is common when the user is adding a new tag.
+    fn load_tags(&mut self) {
+        let manager =
+            RepositoryManager::new(&self.root).and_then(OwnedRepositoryManager::tags_only);
+        let manager = match manager {
+            Ok(manager) => manager,
+            Err(e) => {
+                log::warn!("{}", fl!("repo-lsp-tags-failed", error = e.to_string()));
+                return;
+            }
+        };
+
+        let mut tags: Vec<_> = manager
+            .tags_iter()
+            .map(|tag| TagEntry {
+                tag: tag.get_owned_ref(),
+                names: tag.names().clone(),
+                aliases: tag.aliases().to_vec(),
+                path: manager
+                    .tag_path(tag.as_ref())
+                    .map(|path| self.root.join(path))
+                    .unwrap_or_default(),
+            })
+            .collect();
+        tags.sort_by_key(|entry| entry.tag.to_string());
+        self.tags = Some(tags);
+    }
+
+    fn publish_diagnostics(&self, uri: &Url) -> Value {
+        let diagnostics = self
+            .documents
+            .get(uri)
+            .map(|text| self.diagnostics(uri, text))
+            .unwrap_or_default();
+        notification(
+            "textDocument/publishDiagnostics",
+            PublishDiagnosticsParams {
+                uri: uri.clone(),
+                diagnostics,
+            },
+        )
+    }
+
+    /// Run `anni repo lint` checks on an album document, and check whether its tags are defined.
+    fn diagnostics(&self, uri: &Url, text: &str) -> Vec<Diagnostic> {
+        let parse_error = |message: String, span: Option<std::ops::Range<usize>>| Diagnostic {
+            range: Range::from_span(text, span.unwrap_or(0..0)),
+            severity: 1,
+            code: None,
+            source: "anni",
+            message,
+        };
+
+        let spans = match AlbumSpans::parse(text) {
+            Ok(spans) => spans,
+            Err(RepoError::TomlEditError(err)) => {
+                return vec![parse_error(err.message().to_string(), err.span())]
+            }
+            Err(e) => return vec![parse_error(e.to_string(), None)],
+        };
+        if spans.get(&field_path("album")).is_none() {
+            // not an album file
+            return Vec::new();
+        }
+        let album = match Album::from_str(text) {
+            Ok(album) => album,
+            Err(anni_metadata::error::Error::TomlParseError { err, .. }) => {
+                return vec![parse_error(err.message().to_string(), err.span())]
+            }
+            Err(e) => return vec![parse_error(e.to_string(), None)],
+        };
+
+        let path = uri.to_file_path().unwrap_or_default();
+        let path = path
+            .strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .unwrap_or(path);
+        let mut source = AlbumSource::new(path, text.to_string(), spans);
+        let mut collector = DiagnosticCollector::default();
+        validate_album(&album, &mut source, &mut collector);
+
+        let mut diagnostics: Vec<_> = collector
+            .0
+            .into_iter()
+            .map(|diagnostic| {
+                let range = diagnostic.location.range.as_ref();
+                let start = range.map_or(Position::from_offset(text, 0), |range| {
+                    lint_position(text, &range.start)
+                });
+                // lints without an end point at their start
+                let end = range
+                    .and_then(|range| range.end.as_ref())
+                    .map_or(start, |end| lint_position(text, end));
+                Diagnostic {
+                    range: Range { start, end },
+                    severity: match diagnostic.severity {
+                        DiagnosticSeverity::Error => 1,
+                        _ => 2,
+                    },
+                    code: diagnostic.code.map(|code| code.value),
+                    source: "anni",
+                    message: diagnostic.message.message,
+                }
+            })
+            .collect();
+
+        if let Some(tags) = &self.tags {
+            for (path, span) in source.spans().iter() {
+                let Some(tag) = tag_at(text, path, span) else {
+                    continue;
+                };
+                if find_tag(tags, &tag).is_none() {
+                    diagnostics.push(Diagnostic {
+                        range: Range::from_span(text, span.value.clone()),
+                        severity: 1,
+                        code: Some("tag".to_string()),
+                        source: "anni",
+                        message: fl!("repo-lsp-undefined-tag", tag = tag.to_string()),
+                    });
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Complete tag names in `tags` arrays.
+    fn completion(&self, params: &TextDocumentPositionParams) -> Vec<CompletionItem> {
+        let Some(text) = self.documents.get(&params.text_document.uri) else {
+            return Vec::new();
+        };
+        let Some(tags) = &self.tags else {
+            return Vec::new();
+        };
+
+        // document is usually incomplete while typing, so look at text before cursor instead of parsing it
+        let before = &text[..params.position.to_offset(text)];
+        let Some(array_start) = before.rfind("tags").and_then(|key| {
+            let rest = before[key + "tags".len()..].trim_start();
+            let rest = rest.strip_prefix('=')?.trim_start();
+            rest.strip_prefix('[')?;
+            Some(before.len() - rest.len() + 1)
+        }) else {
+            return Vec::new();
+        };
+        let array = &before[array_start..];
+        if array.contains(']') {
+            return Vec::new();
+        }
+        let in_string = array.matches('"').count() % 2 == 1;
+
+        tags.iter()
+            .map(|entry| {
+                let label = entry.tag.to_string();
+                let mut names: Vec<_> = entry.names.values().cloned().collect();
+                names.sort();
+                CompletionItem {
+                    insert_text: if in_string {
+                        label.clone()
+                    } else {
+                        format!("\"{label}\"")
+                    },
+                    label,
+                    kind: 12,
+                    detail: entry.tag.tag_type().to_string(),
+                    documentation: (!names.is_empty()).then(|| names.join(", ")),
+                }
+            })
+            .collect()
+    }
+
+    /// Go to the file which defines tag under cursor.
+    fn definition(&self, params: &TextDocumentPositionParams) -> Option<Location> {
+        let text = self.documents.get(&params.text_document.uri)?;
+        let offset = params.position.to_offset(text);
+        let spans = AlbumSpans::parse(text).ok()?;
+        let (path, span) = field_at(&spans, offset)?;
+        let tag = tag_at(text, path, span)?;
+        let entry = find_tag(self.tags.as_ref()?, &tag)?;
+
+        // point at `name = "..."` of tag, or where it's defined as a child of another tag
+        let tag_text = std::fs::read_to_string(&entry.path).unwrap_or_default();
+        let spans = AlbumSpans::parse(&tag_text).unwrap_or_default();
+        let mut names: Vec<_> = spans
+            .iter()
+            .filter(|(_, span)| {
+                string_value(&tag_text[span.value.clone()]).as_deref() == Some(entry.tag.name())
+            })
+            .collect();
+        names.sort_by_key(|(path, _)| {
+            path.segments().last() != Some(&PathSegment::Key("name".to_string()))
+        });
+        let range = names.first().map_or(0..0, |(_, span)| span.full());
+
+        Some(Location {
+            uri: Url::from_file_path(&entry.path).ok()?,
+            range: Range::from_span(&tag_text, range),
+        })
+    }
+
+    /// Describe track type or artists of the album, disc or track under cursor.
+    fn hover(&self, params: &TextDocumentPositionParams) -> Option<Hover> {
+        let text = self.documents.get(&params.text_document.uri)?;
+        let offset = params.position.to_offset(text);
+        let spans = AlbumSpans::parse(text).ok()?;
+        let album = Album::from_str(text).ok()?;
+        let (path, span) = field_at(&spans, offset)?;
+
+        // scope is the album, disc or track which contains the field
+        let (scope, field) = match path.segments() {
+            [PathSegment::Key(album), PathSegment::Key(field), ..] if album == "album" => {
+                ((None, None), field)
+            }
+            [PathSegment::Key(discs), PathSegment::Index(disc), PathSegment::Key(field), ..]
+                if discs == "discs" && field != "tracks" =>
+            {
+                ((Some(*disc), None), field)
+            }
+            [PathSegment::Key(discs), PathSegment::Index(disc), PathSegment::Key(tracks), PathSegment::Index(track), PathSegment::Key(field), ..]
+                if discs == "discs" && tracks == "tracks" =>
+            {
+                ((Some(*disc), Some(*track)), field)
+            }
+            _ => return None,
+        };
+
+        let (track_type, artist, artists) = match scope {
+            (None, _) => (
+                album.track_type().clone(),
+                album.artist().to_string(),
+                album.artists.clone(),
+            ),
+            (Some(disc), None) => {
+                let disc = album.iter().nth(disc)?;
+                (
+                    disc.track_type().clone(),
+                    disc.artist().to_string(),
+                    disc.artists().cloned(),
+                )
+            }
+            (Some(disc), Some(track)) => {
+                let disc = album.iter().nth(disc)?;
+                let track = disc.iter().nth(track)?;
+                (
+                    track.track_type().clone(),
+                    track.artist().to_string(),
+                    track.artists().cloned(),
+                )
+            }
+        };
+
+        let value = match field.as_str() {
+            "type" => format!(
+                "**{}**\n\n{}",
+                fl!(
+                    "repo-lsp-hover-track-type",
+                    track_type = track_type.as_ref()
+                ),
+                track_type_description(&track_type)
+            ),
+            "artist" | "artists" => {
+                let mut value = format!("**{}**", fl!("repo-lsp-hover-artist", artist = artist));
+                for credit in artists.unwrap_or_default() {
+                    value.push_str(&format!("\n- {}: {}", credit.role, credit.name));
+                    if let Some(tag) = &credit.tag {
+                        value.push_str(&format!(" (`{tag}`)"));
+                    }
+                }
+                value
+            }
+            _ => return None,
+        };
+
+        Some(Hover {
+            contents: MarkupContent::markdown(value),
+            range: Range::from_span(text, span.full()),
+        })
+    }
+}
+
+fn parse_params<P>(params: Value) -> Result<P, ResponseError>
+where
+    P: DeserializeOwned,
+{
+    Ok(serde_json::from_value(params)?)
+}
+
+fn field_path(path: &str) -> FieldPath {
+    path.parse().expect("field path should be valid")
+}
+
+/// Innermost field which contains byte `offset`.
+fn field_at(spans: &AlbumSpans, offset: usize) -> Option<(&FieldPath, &FieldSpan)> {
+    spans
+        .iter()
+        .filter(|(_, span)| span.full().contains(&offset))
+        .max_by_key(|(path, _)| path.segments().len())
+}
+
+/// Tag referenced by field at `path`, if it's an item of `tags` array.
+fn tag_at(text: &str, path: &FieldPath, span: &FieldSpan) -> Option<TagRef<'static>> {
+    match path.segments() {
+        [.., PathSegment::Key(key), PathSegment::Index(_)] if key == "tags" => {
+            string_value(&text[span.value.clone()]).map(TagRef::from_cow_str)
+        }
+        _ => None,
+    }
+}
+
+/// Find tag definition by name or alias. Tags without type match any tag with the same name.
+fn find_tag<'a>(tags: &'a [TagEntry], tag: &TagRef) -> Option<&'a TagEntry> {
+    tags.iter().find(|entry| {
+        (entry.tag.name() == tag.name() || entry.aliases.iter().any(|alias| alias == tag.name()))
+            && (tag.tag_type() == &TagType::Unknown || entry.tag.tag_type() == tag.tag_type())
+    })
+}
+
+/// Value of toml string literal `raw`, including quotes.
+fn string_value(raw: &str) -> Option<String> {
+    let value: toml::Value = toml::from_str(&format!("value = {raw}")).ok()?;
+    value.get("value")?.as_str().map(str::to_string)
+}
+
+/// Convert position reported by `anni repo lint`, which starts from 1 and counts column in bytes.
+fn lint_position(text: &str, position: &DiagnosticPosition) -> Position {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(position.line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    let column = position.column.unwrap_or(1).saturating_sub(1) as usize;
+    Position::from_offset(text, line_start + column)
+}
+
+fn track_type_description(track_type: &TrackType) -> String {
+    match track_type {
+        TrackType::Normal => fl!("repo-lsp-track-type-normal"),
+        TrackType::Instrumental => fl!("repo-lsp-track-type-instrumental"),
+        TrackType::Absolute => fl!("repo-lsp-track-type-absolute"),
+        TrackType::Drama => fl!("repo-lsp-track-type-drama"),
+        TrackType::Radio => fl!("repo-lsp-track-type-radio"),
+        TrackType::Vocal => fl!("repo-lsp-track-type-vocal"),
+    }
+}
+
+#[derive(Default)]
+struct DiagnosticCollector(Vec<diagnostic::Diagnostic<MetadataDiagnosticTarget>>);
+
+impl AnniLinter<MetadataDiagnosticTarget> for DiagnosticCollector {
+    fn add(&mut self, msg: diagnostic::Diagnostic<MetadataDiagnosticTarget>) {
+        self.0.push(msg);
+    }
+
+    fn flush(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,263 @@
+//! JSON-RPC transport and the subset of LSP types used by `anni repo lsp`.
+//!
+//! https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use url::Url;
+
+pub const INVALID_PARAMS: i64 = -32602;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A request or notification from client.
+///
+/// Responses to server requests also arrive here, but they have no `method` and are ignored.
+#[derive(Debug, Deserialize)]
+pub struct Message {
+    #[serde(default)]
+    pub id: Option<Value>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// Read a message framed by `Content-Length` header. Returns `None` when input is closed.
+pub fn read_message<R>(reader: &mut R) -> io::Result<Option<Message>>
+where
+    R: BufRead,
+{
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+pub fn write_message<W>(writer: &mut W, message: &Value) -> io::Result<()>
+where
+    W: Write,
+{
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+pub fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: Value, error: ResponseError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+pub fn notification<P>(method: &str, params: P) -> Value
+where
+    P: Serialize,
+{
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+#[derive(Debug)]
+pub struct ResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl From<serde_json::Error> for ResponseError {
+    fn from(err: serde_json::Error) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Position in a text document. Both line and character start from 0,
+/// and character is counted in UTF-16 code units.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+impl Position {
+    /// Position of byte `offset` in `text`.
+    pub fn from_offset(text: &str, offset: usize) -> Self {
+        let before = &text[..offset.min(text.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() as u32,
+            character: before[line_start..].encode_utf16().count() as u32,
+        }
+    }
+
+    /// Byte offset of this position in `text`.
+    ///
+    /// Positions after the end of a line are clamped to the end of that line.
+    pub fn to_offset(self, text: &str) -> usize {
+        let line_start = match self.line {
+            0 => 0,
+            line => match text.match_indices('\n').nth(line as usize - 1) {
+                Some((i, _)) => i + 1,
+                None => return text.len(),
+            },
+        };
+        let line = text[line_start..].split('\n').next().unwrap_or_default();
+
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= self.character as usize {
+                return line_start + i;
+            }
+            units += c.len_utf16();
+        }
+        line_start + line.len()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    /// Range of byte offsets `span` in `text`.
+    pub fn from_span(text: &str, span: std::ops::Range<usize>) -> Self {
+        Self {
+            start: Position::from_offset(text, span.start),
+            end: Position::from_offset(text, span.end),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Location {
+    pub uri: Url,
+    pub range: Range,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Diagnostic {
+    pub range: Range,
+    /// 1 for error, 2 for warning
+    pub severity: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub source: &'static str,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishDiagnosticsParams {
+    pub uri: Url,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+    pub label: String,
+    /// 12 for value
+    pub kind: u8,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+    pub insert_text: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Hover {
+    pub contents: MarkupContent,
+    pub range: Range,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MarkupContent {
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl MarkupContent {
+    pub fn markdown(value: String) -> Self {
+        Self {
+            kind: "markdown",
+            value,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TextDocumentIdentifier {
+    pub uri: Url,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TextDocumentItem {
+    pub uri: Url,
+    pub text: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DidOpenTextDocumentParams {
+    pub text_document: TextDocumentItem,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TextDocumentContentChangeEvent {
+    pub text: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DidChangeTextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+    pub content_changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DidSaveTextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DidCloseTextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentPositionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
@@ -0,0 +1,91 @@
+use crate::{ball, fl, ll};
+use anni_metadata::model::Album;
+use anni_repo::merge::merge_album;
+use anni_repo::RepositoryManager;
+use clap::Args;
+use clap_handler::handler;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoMergeDriverAction {
+    #[clap(long, conflicts_with_all = ["base", "ours", "theirs"])]
+    #[clap(help = ll!("repo-merge-driver-install"))]
+    install: bool,
+
+    #[clap(short = 'L', long, default_value = "7")]
+    #[clap(help = ll!("repo-merge-driver-marker-size"))]
+    marker_size: usize,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-merge-driver-path"))]
+    path: Option<String>,
+
+    #[clap(required_unless_present = "install")]
+    base: Option<PathBuf>,
+    #[clap(required_unless_present = "install")]
+    ours: Option<PathBuf>,
+    #[clap(required_unless_present = "install")]
+    theirs: Option<PathBuf>,
+}
+
+#[handler(RepoMergeDriverAction)]
+fn repo_merge_driver(me: RepoMergeDriverAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    if me.install {
+        let root = manager.root().canonicalize()?;
+        let driver = format!(
+            "anni repo --root \"{}\" merge-driver --marker-size %L --path %P %O %A %B",
+            root.display()
+        );
+        manager.install_merge_driver(&driver)?;
+        log::info!("{}", fl!("repo-merge-driver-installed"));
+        return Ok(());
+    }
+
+    let (Some(base), Some(ours), Some(theirs)) = (me.base, me.ours, me.theirs) else {
+        unreachable!("base, ours and theirs are required without --install");
+    };
+    // ours is a temporary file, use original path in messages if provided
+    let path = me
+        .path
+        .clone()
+        .unwrap_or_else(|| ours.display().to_string());
+    let read_album = |path: &Path| -> anyhow::Result<Album> {
+        Ok(Album::from_str(&std::fs::read_to_string(path)?)?)
+    };
+
+    let (ours_album, theirs_album) = match (read_album(&ours), read_album(&theirs)) {
+        (Ok(ours), Ok(theirs)) => (ours, theirs),
+        (Err(e), _) | (_, Err(e)) => {
+            // not a valid album file, fallback to textual merge
+            log::warn!("{e}");
+            let status = Command::new("git")
+                .arg("merge-file")
+                .arg(format!("--marker-size={}", me.marker_size))
+                .args(["-L", "ours", "-L", "base", "-L", "theirs"])
+                .args([&ours, &base, &theirs])
+                .status()?;
+            if !status.success() {
+                ball!("repo-merge-driver-conflict", path = path);
+            }
+            return Ok(());
+        }
+    };
+    // base does not exist if the album was added on both sides
+    let base_album = read_album(&base).ok();
+
+    let merged = merge_album(base_album.as_ref(), &ours_album, &theirs_album)?;
+    std::fs::write(&ours, merged.to_toml_string(me.marker_size))?;
+    if !merged.is_clean() {
+        for conflict in merged.conflicts.iter() {
+            log::warn!(
+                "{}",
+                fl!("repo-merge-driver-field", field = conflict.path.to_string())
+            );
+        }
+        ball!("repo-merge-driver-conflict", path = path);
+    }
+
+    Ok(())
+}
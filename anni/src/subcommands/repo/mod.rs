@@ -1,27 +1,45 @@
 mod add;
+mod export;
 mod get;
+mod history;
 mod lint;
+mod lsp;
+mod merge;
 mod migrate;
 mod print;
+mod query;
+#[cfg(feature = "search")]
+mod search;
+mod tag;
+mod update;
 mod watch;
 
 use crate::args::ActionFile;
 use crate::{ball, fl, ll};
 use add::*;
-use anni_metadata::model::Album;
+use anni_metadata::model::{Album, ALBUM_SCHEMA_VERSION};
 use anni_repo::library::{file_name, AlbumFolderInfo};
-use anni_repo::models::JsonAlbum;
+use anni_repo::models::albums_from_json;
 use anni_repo::RepositoryManager;
 use anni_workspace::AnniWorkspace;
 use clap::{Args, Subcommand, ValueEnum};
 use clap_handler::{handler, Context, Handler};
+use export::RepoExportAction;
 use get::RepoGetAction;
+use history::RepoHistoryAction;
 use lint::*;
+use lsp::RepoLspAction;
+use merge::RepoMergeDriverAction;
 use migrate::RepoMigrateAction;
 use print::*;
+use query::*;
+#[cfg(feature = "search")]
+use search::*;
 use std::io::Read;
 use std::path::PathBuf;
 use std::str::FromStr;
+use tag::RepoTagAction;
+use update::RepoUpdateAction;
 use watch::*;
 
 #[derive(Args, Debug, Clone, Handler)]
@@ -62,19 +80,38 @@ pub enum RepoAction {
     Add(RepoAddAction),
     #[clap(about = ll!("repo-import"))]
     Import(RepoImportAction),
+    #[clap(about = ll!("repo-export"))]
+    Export(RepoExportAction),
     #[clap(about = ll!("repo-get"))]
     Get(RepoGetAction),
+    #[clap(about = ll!("repo-update"))]
+    Update(RepoUpdateAction),
     #[clap(about = ll!("repo-edit"))]
     Edit(RepoEditAction),
     #[clap(about = ll!("repo-lint"))]
     Lint(RepoLintAction),
     #[clap(about = ll!("repo-print"))]
     Print(RepoPrintAction),
+    #[clap(about = ll!("repo-query"))]
+    Query(RepoQueryAction),
+    #[clap(about = ll!("repo-history"))]
+    History(RepoHistoryAction),
+    #[clap(about = ll!("repo-tag"))]
+    Tag(RepoTagAction),
+    #[cfg(feature = "search")]
+    #[clap(about = ll!("repo-search"))]
+    Search(RepoSearchAction),
     #[clap(name = "db")]
     #[clap(about = ll!("repo-db"))]
     Database(RepoDatabaseAction),
     Watch(RepoWatchAction),
     Migrate(RepoMigrateAction),
+    #[clap(about = ll!("repo-upgrade"))]
+    Upgrade(RepoUpgradeAction),
+    #[clap(about = ll!("repo-merge-driver"))]
+    MergeDriver(RepoMergeDriverAction),
+    #[clap(about = ll!("repo-lsp"))]
+    Lsp(RepoLspAction),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -96,6 +133,26 @@ fn repo_clone(me: RepoCloneAction) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct RepoUpgradeAction {}
+
+#[handler(RepoUpgradeAction)]
+fn repo_upgrade(manager: &RepositoryManager) -> anyhow::Result<()> {
+    let upgraded = manager.upgrade_albums()?;
+    for path in upgraded.iter() {
+        log::debug!("Upgraded {}", path.display());
+    }
+    log::info!(
+        "{}",
+        fl!(
+            "repo-upgrade-done",
+            count = upgraded.len(),
+            version = ALBUM_SCHEMA_VERSION
+        )
+    );
+    Ok(())
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct RepoImportAction {
     #[clap(short = 'D', long = "duplicate")]
@@ -121,14 +178,13 @@ fn repo_import(me: &RepoImportAction, manager: &RepositoryManager) -> anyhow::Re
         let mut result = String::new();
         reader.read_to_string(&mut result)?;
 
-        let album = match me.format {
-            RepoImportFormat::Toml => Album::from_str(&result)?,
-            RepoImportFormat::Json => {
-                let album = JsonAlbum::from_str(&result)?;
-                album.try_into()?
-            }
+        let albums = match me.format {
+            RepoImportFormat::Toml => vec![Album::from_str(&result)?],
+            RepoImportFormat::Json => albums_from_json(&result)?,
         };
-        manager.add_album(album, me.allow_duplicate)?;
+        for album in albums {
+            manager.add_album(album, me.allow_duplicate)?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,221 @@
+use crate::{args::ActionFile, ll};
+use anni_metadata::model::{AnniDate, TrackType};
+use anni_repo::db::{AlbumOrder, AlbumQuery, RepoDatabaseRead};
+use anni_repo::{OwnedRepositoryManager, RepositoryManager};
+use clap::{Args, ValueEnum};
+use clap_handler::handler;
+use std::io::Write;
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+use uuid::Uuid;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoQueryAction {
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-database"))]
+    database: Option<PathBuf>,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-artist"))]
+    artist: Option<String>,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-after"))]
+    after: Option<AnniDate>,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-before"))]
+    before: Option<AnniDate>,
+
+    #[clap(long = "type")]
+    #[clap(help = ll!("repo-query-type"))]
+    track_type: Option<TrackType>,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-tag"))]
+    tag: Option<String>,
+
+    #[clap(short, long, requires = "tag")]
+    #[clap(help = ll!("repo-query-recursive"))]
+    recursive: bool,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-catalog"))]
+    catalog: Option<String>,
+
+    #[clap(value_enum)]
+    #[clap(short, long, default_value = "release-date")]
+    #[clap(help = ll!("repo-query-sort"))]
+    sort: RepoQuerySort,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-query-desc"))]
+    desc: bool,
+
+    #[clap(short = 'n', long)]
+    limit: Option<usize>,
+
+    #[clap(long, default_value = "0")]
+    offset: usize,
+
+    #[clap(value_enum)]
+    #[clap(short, long, default_value = "table")]
+    #[clap(help = ll!("repo-query-format"))]
+    format: RepoQueryFormat,
+
+    #[clap(short, long, default_value = "-")]
+    #[clap(help = ll!("export-to"))]
+    output: ActionFile,
+}
+
+impl RepoQueryAction {
+    fn to_query(&self) -> AlbumQuery {
+        let mut query = AlbumQuery::new()
+            .order_by(self.sort.into(), self.desc)
+            .offset(self.offset);
+        if let Some(artist) = &self.artist {
+            query = query.artist(artist);
+        }
+        if let Some(after) = &self.after {
+            query = query.released_after(after.clone());
+        }
+        if let Some(before) = &self.before {
+            query = query.released_before(before.clone());
+        }
+        if let Some(track_type) = &self.track_type {
+            query = query.track_type(track_type.clone());
+        }
+        if let Some(tag) = &self.tag {
+            query = query.tag(tag, self.recursive);
+        }
+        if let Some(catalog) = &self.catalog {
+            query = query.catalog_prefix(catalog);
+        }
+        if let Some(limit) = self.limit {
+            query = query.limit(limit);
+        }
+        query
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum RepoQuerySort {
+    ReleaseDate,
+    Title,
+    Catalog,
+}
+
+impl From<RepoQuerySort> for AlbumOrder {
+    fn from(sort: RepoQuerySort) -> Self {
+        match sort {
+            RepoQuerySort::ReleaseDate => AlbumOrder::ReleaseDate,
+            RepoQuerySort::Title => AlbumOrder::Title,
+            RepoQuerySort::Catalog => AlbumOrder::Catalog,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum RepoQueryFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[handler(RepoQueryAction)]
+fn repo_query(me: RepoQueryAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let database = match &me.database {
+        Some(database) => database.clone(),
+        None => {
+            // keep database in temp dir, so that following queries only need to update changed albums.
+            // each repository has its own directory, keyed by its root
+            let root = std::fs::canonicalize(manager.root())?;
+            let key = Uuid::new_v5(&Uuid::NAMESPACE_URL, root.as_os_str().as_encoded_bytes());
+            let database = std::env::temp_dir()
+                .join("anni-repo-query")
+                .join(key.to_string())
+                .join("repo.db");
+            std::fs::create_dir_all(database.parent().unwrap())?;
+            OwnedRepositoryManager::tags_only(manager)?.to_database_incremental(&database)?;
+            database
+        }
+    };
+
+    let db = RepoDatabaseRead::new(database)?;
+    let albums = db.query_albums(&me.to_query())?;
+
+    let mut dst = me.output.to_writer()?;
+    if let RepoQueryFormat::Json = me.format {
+        serde_json::to_writer_pretty(&mut dst, &albums)?;
+        writeln!(dst)?;
+        return Ok(());
+    }
+
+    let header = [
+        "album_id",
+        "catalog",
+        "title",
+        "edition",
+        "artist",
+        "release_date",
+        "type",
+    ];
+    let rows: Vec<[String; 7]> = albums
+        .into_iter()
+        .map(|album| {
+            [
+                album.album_id.0.to_string(),
+                album.catalog,
+                album.title,
+                album.edition.unwrap_or_default(),
+                album.artist,
+                album.release_date,
+                album.album_type,
+            ]
+        })
+        .collect();
+
+    match me.format {
+        RepoQueryFormat::Table => {
+            let mut widths = header.map(|h| h.width());
+            for row in rows.iter() {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.width());
+                }
+            }
+
+            let write_row = |dst: &mut dyn Write, row: &[&str]| -> std::io::Result<()> {
+                let line = row
+                    .iter()
+                    .zip(widths)
+                    .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.width())))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                writeln!(dst, "{}", line.trim_end())
+            };
+            write_row(&mut dst, &header)?;
+            for row in rows.iter() {
+                write_row(&mut dst, &row.each_ref().map(String::as_str))?;
+            }
+        }
+        RepoQueryFormat::Csv => {
+            writeln!(dst, "{}", header.join(","))?;
+            for row in rows.iter() {
+                let line = row.iter().map(|cell| csv_escape(cell)).collect::<Vec<_>>();
+                writeln!(dst, "{}", line.join(","))?;
+            }
+        }
+        RepoQueryFormat::Json => unreachable!(),
+    }
+
+    Ok(())
+}
+
+/// Quote `cell` if it contains `,`, `"` or line breaks, as described in RFC 4180.
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{args::ActionFile, ll};
+use anni_repo::search::{RepositorySearchManager, SearchHit, SearchResult};
+use anni_repo::RepositoryManager;
+use clap::{Args, ValueEnum};
+use clap_handler::handler;
+use serde_json::json;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoSearchAction {
+    #[clap(required = true)]
+    #[clap(help = ll!("repo-search-query"))]
+    query: String,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-search-fuzzy"))]
+    fuzzy: bool,
+
+    #[clap(short = 'n', long, default_value = "20")]
+    limit: usize,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-search-index"))]
+    index: Option<PathBuf>,
+
+    #[clap(value_enum)]
+    #[clap(short, long, default_value = "table")]
+    #[clap(help = ll!("repo-search-format"))]
+    format: RepoSearchFormat,
+
+    #[clap(short, long, default_value = "-")]
+    #[clap(help = ll!("export-to"))]
+    output: ActionFile,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum RepoSearchFormat {
+    Table,
+    Json,
+}
+
+#[handler(RepoSearchAction)]
+fn repo_search(me: RepoSearchAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let searcher = match &me.index {
+        Some(index) if index.exists() => RepositorySearchManager::open(index)?,
+        index => {
+            // index is rebuilt on each run if no persistent index path is given
+            let index = match index {
+                Some(index) => index.clone(),
+                None => {
+                    let index = std::env::temp_dir().join("anni-repo-search");
+                    if index.exists() {
+                        std::fs::remove_dir_all(&index)?;
+                    }
+                    index
+                }
+            };
+            std::fs::create_dir_all(&index)?;
+            manager.into_owned_manager()?.build_search_index(&index)?;
+            RepositorySearchManager::open(&index)?
+        }
+    };
+
+    let results = searcher.search(&me.query, me.limit, me.fuzzy)?;
+
+    let mut dst = me.output.to_writer()?;
+    match me.format {
+        RepoSearchFormat::Table => {
+            for result in results {
+                writeln!(
+                    dst,
+                    "{:<6} {:<42} {}",
+                    hit_kind(&result.hit),
+                    hit_id(&result.hit),
+                    result.title
+                )?;
+            }
+        }
+        RepoSearchFormat::Json => {
+            let results: Vec<_> = results.iter().map(result_to_json).collect();
+            serde_json::to_writer_pretty(&mut dst, &results)?;
+            writeln!(dst)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn hit_kind(hit: &SearchHit) -> &'static str {
+    match hit {
+        SearchHit::Album(_) => "album",
+        SearchHit::Disc { .. } => "disc",
+        SearchHit::Track(_) => "track",
+        SearchHit::Tag(_) => "tag",
+    }
+}
+
+/// Identifier of hit, in the form of `album_id[/disc_id[/track_id]]` or `tag_type:tag_name`.
+fn hit_id(hit: &SearchHit) -> String {
+    match hit {
+        SearchHit::Album(album_id) => album_id.to_string(),
+        SearchHit::Disc { album_id, disc_id } => format!("{album_id}/{disc_id}"),
+        SearchHit::Track(track) => {
+            format!("{}/{}/{}", track.album_id, track.disc_id, track.track_id)
+        }
+        SearchHit::Tag(tag) => tag.to_string(),
+    }
+}
+
+fn result_to_json(result: &SearchResult) -> serde_json::Value {
+    json!({
+        "kind": hit_kind(&result.hit),
+        "id": hit_id(&result.hit),
+        "title": result.title,
+        "score": result.score,
+    })
+}
@@ -0,0 +1,79 @@
+use crate::{fl, ll};
+use anni_metadata::model::TagRef;
+use anni_repo::RepositoryManager;
+use clap::{Args, Subcommand};
+use clap_handler::{handler, Handler};
+
+#[derive(Args, Debug, Clone, Handler)]
+pub struct RepoTagAction {
+    #[clap(subcommand)]
+    action: RepoTagSubcommand,
+}
+
+#[derive(Subcommand, Handler, Debug, Clone)]
+pub enum RepoTagSubcommand {
+    #[clap(about = ll!("repo-tag-rename"))]
+    Rename(RepoTagRenameAction),
+    #[clap(about = ll!("repo-tag-merge"))]
+    Merge(RepoTagMergeAction),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoTagRenameAction {
+    #[clap(help = ll!("repo-tag-from"))]
+    from: String,
+    #[clap(help = ll!("repo-tag-rename-to"))]
+    to: String,
+}
+
+#[handler(RepoTagRenameAction)]
+fn repo_tag_rename(me: RepoTagRenameAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let manager = manager.into_owned_manager()?;
+    let changed = manager.rename_tag(
+        &TagRef::from_cow_str(me.from.as_str()),
+        &TagRef::from_cow_str(me.to.as_str()),
+    )?;
+    for path in changed.iter() {
+        log::debug!("Rewritten {}", path.display());
+    }
+    log::info!(
+        "{}",
+        fl!(
+            "repo-tag-renamed",
+            from = me.from,
+            to = me.to,
+            count = changed.len()
+        )
+    );
+    Ok(())
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoTagMergeAction {
+    #[clap(help = ll!("repo-tag-from"))]
+    from: String,
+    #[clap(help = ll!("repo-tag-merge-into"))]
+    into: String,
+}
+
+#[handler(RepoTagMergeAction)]
+fn repo_tag_merge(me: RepoTagMergeAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let manager = manager.into_owned_manager()?;
+    let changed = manager.merge_tag(
+        &TagRef::from_cow_str(me.from.as_str()),
+        &TagRef::from_cow_str(me.into.as_str()),
+    )?;
+    for path in changed.iter() {
+        log::debug!("Rewritten {}", path.display());
+    }
+    log::info!(
+        "{}",
+        fl!(
+            "repo-tag-merged",
+            from = me.from,
+            into = me.into,
+            count = changed.len()
+        )
+    );
+    Ok(())
+}
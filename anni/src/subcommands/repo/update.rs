@@ -0,0 +1,171 @@
+use super::get::{fetch_album, VGMdbSource};
+use crate::{ball, fl, ll};
+use anni_common::fs;
+use anni_metadata::model::Album;
+use anni_metadata::source::{DiscogsSource, ITunesSource, MetadataSource, MusicBrainzSource};
+use anni_repo::diff::{align_album, apply_changes, diff_album, FieldChange};
+use anni_repo::RepositoryManager;
+use clap::{Args, ValueEnum};
+use clap_handler::handler;
+use inquire::MultiSelect;
+use std::str::FromStr;
+use toml::Value;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoUpdateAction {
+    #[clap(long, value_enum)]
+    #[clap(help = ll!("repo-update-from"))]
+    from: RepoUpdateSource,
+
+    #[clap(long, help = ll!("repo-get-id"))]
+    id: Option<String>,
+    #[clap(short = 'k', long, help = ll!("repo-get-keyword"))]
+    keyword: Option<String>,
+
+    #[clap(short = 'y', long = "yes")]
+    #[clap(help = ll!("repo-update-yes"))]
+    accept_all: bool,
+
+    #[clap(required = true)]
+    #[clap(help = ll!("repo-update-catalog"))]
+    catalog: String,
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+pub enum RepoUpdateSource {
+    Vgmdb,
+    Musicbrainz,
+    Discogs,
+    Itunes,
+}
+
+impl RepoUpdateSource {
+    fn source(&self) -> Box<dyn MetadataSource> {
+        match self {
+            RepoUpdateSource::Vgmdb => Box::<VGMdbSource>::default(),
+            RepoUpdateSource::Musicbrainz => {
+                let base_url = std::env::var("MUSICBRAINZ_BASE_URL")
+                    .unwrap_or_else(|_| MusicBrainzSource::BASE_URL.to_string());
+                Box::new(MusicBrainzSource::new(&base_url))
+            }
+            RepoUpdateSource::Discogs => {
+                Box::new(DiscogsSource::new(std::env::var("DISCOGS_TOKEN").ok()))
+            }
+            RepoUpdateSource::Itunes => Box::new(ITunesSource::new(None)),
+        }
+    }
+}
+
+#[handler(RepoUpdateAction)]
+async fn repo_update(me: RepoUpdateAction, manager: &RepositoryManager) -> anyhow::Result<()> {
+    let paths = manager.album_paths(&me.catalog)?;
+    let path = match paths.as_slice() {
+        [path] => path,
+        [] => ball!("repo-album-not-found", catalog = me.catalog),
+        _ => ball!("repo-update-multiple-albums", catalog = me.catalog),
+    };
+    let album = Album::from_str(&fs::read_to_string(path)?)?;
+
+    let source = me.from.source();
+    let keyword = me.keyword.as_deref().unwrap_or(&me.catalog);
+    let remote = fetch_album(source.as_ref(), me.id.as_deref(), Some(keyword)).await?;
+
+    let (old, new) = align_album(&album, &remote);
+    let changes = diff_album(Some(&old), Some(&new));
+    if changes.is_empty() {
+        info!("{}", fl!("repo-update-no-change"));
+        return Ok(());
+    }
+    print_changes(&changes, source.name());
+
+    let accepted = if me.accept_all {
+        changes.iter().collect()
+    } else {
+        select_changes(&changes)?
+    };
+    if accepted.is_empty() {
+        return Ok(());
+    }
+
+    let count = accepted.len();
+    let mut updated = apply_changes(&old, accepted)?;
+    fs::write(path, updated.format_to_string())?;
+    info!(
+        "{}",
+        fl!(
+            "repo-update-done",
+            count = count,
+            path = path.display().to_string()
+        )
+    );
+    Ok(())
+}
+
+/// Print changes side by side, in columns of field, value in repo and value from source.
+fn print_changes(changes: &[FieldChange], source: &str) {
+    let header = ["field".to_string(), "repo".to_string(), source.to_string()];
+    let rows: Vec<_> = std::iter::once(header)
+        .chain(changes.iter().map(|change| {
+            [
+                change.path.to_string(),
+                format_value(change.old.as_ref()),
+                format_value(change.new.as_ref()),
+            ]
+        }))
+        .collect();
+    let mut widths = [0; 3];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    for row in rows.iter() {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.width())))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        println!("{}", line.trim_end());
+    }
+}
+
+fn select_changes(changes: &[FieldChange]) -> anyhow::Result<Vec<&FieldChange>> {
+    let options = changes
+        .iter()
+        .map(|change| {
+            format!(
+                "{}: {} -> {}",
+                change.path,
+                format_value(change.old.as_ref()),
+                format_value(change.new.as_ref())
+            )
+        })
+        .collect();
+    let default: Vec<_> = (0..changes.len()).collect();
+    match MultiSelect::new(&fl!("repo-update-select"), options)
+        .with_default(&default)
+        .raw_prompt()
+    {
+        Ok(selected) => Ok(selected
+            .into_iter()
+            .map(|option| &changes[option.index])
+            .collect()),
+        Err(_) => bail!("Aborted"),
+    }
+}
+
+/// Format value in a single line. Tables are printed as lines of `key = value` joined by commas.
+fn format_value(value: Option<&Value>) -> String {
+    match value {
+        Some(value) => value
+            .to_string()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        None => "(none)".to_string(),
+    }
+}
@@ -197,6 +197,7 @@ fn handle_workspace_add(me: WorkspaceAddAction) -> anyhow::Result<()> {
                     month: month,
                     day: day,
                     extra: Some(serde_json::json!({ "rawdir": folder_name })),
+                    localized: None,
                     discs: discs.iter().map(Into::into).collect(),
                 };
                 client.add_album_input(input, true).await?;
@@ -0,0 +1,174 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{ChildStdin, ChildStdout, Stdio};
+
+mod common;
+
+const TAGS: &str = r#"[[tag]]
+name = "Test"
+type = "artist"
+"#;
+
+const ALBUM: &str = r#"[album]
+album_id = "15006392-e2ae-4204-b7db-e59211f3cdcf"
+title = " Title"
+artist = "Artist"
+date = 2020-12-16
+type = "normal"
+catalog = "TEST-0001"
+tags = ["artist:Test", "Undefined"]
+
+[[discs]]
+catalog = "TEST-0001"
+
+[[discs.tracks]]
+title = "Track 1"
+type = "instrumental"
+"#;
+
+/// A scripted LSP client talking to `anni repo lsp`.
+struct Client {
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn request(&mut self, id: u64, method: &str, params: Value) -> Value {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let response = self.receive();
+        assert_eq!(response["id"], id);
+        response["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+}
+
+fn file_uri(path: &Path) -> String {
+    format!("file://{}", path.canonicalize().unwrap().display())
+}
+
+#[test]
+#[cfg(unix)]
+fn repo_lsp_scripted_session() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(
+        root.path().join("repo.toml"),
+        "[repo]\nname = \"Test\"\nedition = \"1.3\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(root.path().join("tag")).unwrap();
+    std::fs::write(root.path().join("tag/default.toml"), TAGS).unwrap();
+    std::fs::create_dir_all(root.path().join("album")).unwrap();
+    let album_path = root.path().join("album/TEST-0001.toml");
+    std::fs::write(&album_path, ALBUM).unwrap();
+
+    let mut child = common::run(&["repo", "--root", root.path().to_str().unwrap(), "lsp"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut client = Client {
+        stdin: child.stdin.take().unwrap(),
+        stdout: BufReader::new(child.stdout.take().unwrap()),
+    };
+
+    let result = client.request(1, "initialize", json!({ "capabilities": {} }));
+    assert_eq!(result["capabilities"]["hoverProvider"], true);
+    client.notify("initialized", json!({}));
+
+    // diagnostics are published on open
+    let uri = file_uri(&album_path);
+    client.notify(
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": uri, "languageId": "toml", "version": 1, "text": ALBUM } }),
+    );
+    let diagnostics = client.receive();
+    assert_eq!(diagnostics["method"], "textDocument/publishDiagnostics");
+    let diagnostics = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["code"], "trim");
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 2, "character": 0 }, "end": { "line": 2, "character": 16 } })
+    );
+    assert_eq!(diagnostics[1]["code"], "tag");
+    assert_eq!(diagnostics[1]["range"]["start"]["character"], 23);
+
+    // complete tag names inside `tags`
+    let position = json!({ "line": 7, "character": 9 });
+    let result = client.request(
+        2,
+        "textDocument/completion",
+        json!({ "textDocument": { "uri": uri }, "position": position }),
+    );
+    assert_eq!(result[0]["label"], "artist:Test");
+    assert_eq!(result[0]["insertText"], "artist:Test");
+
+    // go to definition of tag
+    let result = client.request(
+        3,
+        "textDocument/definition",
+        json!({ "textDocument": { "uri": uri }, "position": position }),
+    );
+    assert_eq!(
+        result["uri"],
+        file_uri(&root.path().join("tag/default.toml"))
+    );
+    assert_eq!(
+        result["range"]["start"],
+        json!({ "line": 1, "character": 0 })
+    );
+
+    // hover on track type
+    let result = client.request(
+        4,
+        "textDocument/hover",
+        json!({ "textDocument": { "uri": uri }, "position": { "line": 14, "character": 9 } }),
+    );
+    assert!(result["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("instrumental"));
+
+    // hover on artist
+    let result = client.request(
+        5,
+        "textDocument/hover",
+        json!({ "textDocument": { "uri": uri }, "position": { "line": 3, "character": 1 } }),
+    );
+    assert!(result["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("Artist"));
+
+    assert_eq!(client.request(6, "shutdown", Value::Null), Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(child.wait().unwrap().success());
+}
@@ -18,7 +18,7 @@ fn default_true() -> bool {
 #[cfg(feature = "metadata")]
 impl MetadataConfig {
     pub fn init(&self) -> anyhow::Result<PathBuf> {
-        use anni_repo::RepositoryManager;
+        use anni_repo::{OwnedRepositoryManager, RepositoryManager};
 
         log::info!("Fetching metadata repository...");
 
@@ -35,9 +35,10 @@ impl MetadataConfig {
         };
 
         log::debug!("Generating metadata database...");
-        let repo = repo.into_owned_manager()?;
+        // only changed albums are loaded by incremental builds
+        let repo = OwnedRepositoryManager::tags_only(repo)?;
         let database_path = self.base.join("repo.db");
-        repo.to_database(&database_path)?;
+        repo.to_database_incremental(&database_path)?;
 
         log::info!("Metadata repository fetched.");
         Ok(database_path)
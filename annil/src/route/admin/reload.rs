@@ -15,15 +15,15 @@ pub async fn reload<P>(
 {
     #[cfg(feature = "metadata")]
     if let Some(metadata) = &data.metadata {
-        use anni_repo::RepositoryManager;
+        use anni_repo::{OwnedRepositoryManager, RepositoryManager};
 
         if metadata.pull {
             let repo =
                 RepositoryManager::pull(metadata.base.join("repo"), &metadata.branch).unwrap();
-            let repo = repo.into_owned_manager().unwrap();
+            let repo = OwnedRepositoryManager::tags_only(repo).unwrap();
 
             let database_path = metadata.base.join("repo.db");
-            repo.to_database(&database_path).unwrap();
+            repo.to_database_incremental(&database_path).unwrap();
         }
     }
 
@@ -1,3 +1,6 @@
+use anni_metadata::model::{ArtistCredits, Localized};
+use serde::Deserialize;
+
 #[cfg(feature = "postgres")]
 mod postgres;
 #[cfg(feature = "postgres")]
@@ -7,3 +10,22 @@ pub use postgres::*;
 mod sqlite;
 #[cfg(feature = "sqlite")]
 pub use sqlite::*;
+
+/// Artist credits stored in `artists` of a track.
+///
+/// The json has the same shape as `artists` in album toml, so credits written as plain
+/// role-to-name maps are still readable.
+pub fn track_credits(artists: Option<&serde_json::Value>) -> serde_json::Result<ArtistCredits> {
+    match artists {
+        None | Some(serde_json::Value::Null) => Ok(ArtistCredits::new()),
+        Some(artists) => ArtistCredits::deserialize(artists),
+    }
+}
+
+/// Localized titles and artists stored in `localized` of an album, a disc or a track.
+pub fn localized_texts(localized: Option<&serde_json::Value>) -> serde_json::Result<Localized> {
+    match localized {
+        None | Some(serde_json::Value::Null) => Ok(Localized::new()),
+        Some(localized) => Localized::deserialize(localized),
+    }
+}
@@ -22,6 +22,7 @@ pub struct Model {
     pub updated_at: DateTime,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub extra: Option<Json>,
+    pub localized: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -17,6 +17,7 @@ pub struct Model {
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub r#type: TrackType,
+    pub localized: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -23,6 +23,18 @@ impl Cursor {
         writer.into_inner()
     }
 
+    /// Cursor of search results, which is the offset of the next result.
+    pub(crate) fn from_offset(offset: usize) -> Self {
+        Cursor(vec![CursorValue::BigUnsigned(Some(offset as u64))])
+    }
+
+    pub(crate) fn into_offset(self) -> anyhow::Result<usize> {
+        match self.0.as_slice() {
+            [CursorValue::BigUnsigned(Some(offset))] => Ok(usize::try_from(*offset)?),
+            _ => anyhow::bail!("Invalid search cursor"),
+        }
+    }
+
     pub(crate) fn into_value_tuple(self) -> ValueTuple {
         ValueTuple::Many(self.into_inner())
     }
@@ -1,11 +1,15 @@
 use async_graphql::{InputObject, InputType, OneofObject, ID};
 use sea_orm::{
-    prelude::Uuid, ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection, DbErr,
+    prelude::Uuid, ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection, EntityTrait,
 };
 
-use super::types::{MetadataOrganizeLevel, TrackType};
+use std::str::FromStr;
+
+use anni_metadata::model;
+
+use super::types::{ArtistKind, ArtistRole, MetadataOrganizeLevel, TagType, TrackType};
 use crate::{
-    entities::{album, disc, helper::now, track},
+    entities::{album, disc, helper::now, tag_info, track},
     search::SearchWriter,
 };
 
@@ -39,6 +43,7 @@ pub struct AddAlbumInput {
     #[graphql(name = "day")]
     pub release_day: Option<i16>,
     pub extra: Option<serde_json::Value>,
+    pub localized: Option<Vec<LocalizedTextInput>>,
     pub discs: Vec<CreateAlbumDiscInput>,
 }
 
@@ -47,6 +52,7 @@ pub struct CreateAlbumDiscInput {
     pub title: Option<String>,
     pub catalog: Option<String>,
     pub artist: Option<String>,
+    pub localized: Option<Vec<LocalizedTextInput>>,
     pub tracks: Vec<CreateAlbumTrackInput>,
 }
 
@@ -64,6 +70,7 @@ impl CreateAlbumDiscInput {
             title: ActiveValue::set(self.title),
             catalog: ActiveValue::set(self.catalog),
             artist: ActiveValue::set(self.artist),
+            localized: ActiveValue::set(localized_json(self.localized)?),
             ..Default::default()
         };
         let disc = disc.insert(txn).await?;
@@ -85,6 +92,8 @@ pub struct CreateAlbumTrackInput {
     pub title: String,
     pub artist: String,
     pub r#type: TrackType,
+    pub artists: Option<Vec<ArtistCreditInput>>,
+    pub localized: Option<Vec<LocalizedTextInput>>,
 }
 
 impl CreateAlbumTrackInput {
@@ -103,6 +112,8 @@ impl CreateAlbumTrackInput {
             title: ActiveValue::set(self.title),
             artist: ActiveValue::set(self.artist),
             r#type: ActiveValue::set(self.r#type.into()),
+            artists: ActiveValue::set(credits_json(self.artists.unwrap_or_default(), txn).await?),
+            localized: ActiveValue::set(localized_json(self.localized)?),
             ..Default::default()
         };
         let track = track.insert(txn).await?;
@@ -127,6 +138,8 @@ pub struct UpdateAlbumInfoInput {
     #[graphql(name = "day")]
     pub release_day: Option<UpdateI16>,
     pub extra: Option<UpdateJson>,
+    /// Replace localized titles and artists. An empty list removes all of them.
+    pub localized: Option<Vec<LocalizedTextInput>>,
 }
 
 impl UpdateAlbumInfoInput {
@@ -134,7 +147,7 @@ impl UpdateAlbumInfoInput {
         self,
         mut model: album::ActiveModel,
         db: &DatabaseConnection,
-    ) -> Result<album::Model, DbErr> {
+    ) -> anyhow::Result<album::Model> {
         may_update_required!(self, model, title);
         may_update_optional!(self, model, edition);
         may_update_optional!(self, model, catalog);
@@ -143,9 +156,12 @@ impl UpdateAlbumInfoInput {
         may_update_optional!(self, model, release_month);
         may_update_optional!(self, model, release_day);
         may_update_optional!(self, model, extra);
+        if let Some(localized) = self.localized {
+            model.localized = ActiveValue::set(localized_json(Some(localized))?);
+        }
 
         model.updated_at = ActiveValue::set(now());
-        model.update(db).await
+        Ok(model.update(db).await?)
     }
 }
 
@@ -156,6 +172,8 @@ pub struct UpdateDiscInfoInput {
     pub title: Option<UpdateString>,
     pub catalog: Option<UpdateString>,
     pub artist: Option<UpdateString>,
+    /// Replace localized titles and artists. An empty list removes all of them.
+    pub localized: Option<Vec<LocalizedTextInput>>,
 }
 
 impl UpdateDiscInfoInput {
@@ -163,13 +181,16 @@ impl UpdateDiscInfoInput {
         self,
         mut model: disc::ActiveModel,
         db: &DatabaseConnection,
-    ) -> Result<disc::Model, DbErr> {
+    ) -> anyhow::Result<disc::Model> {
         may_update_optional!(self, model, title);
         may_update_optional!(self, model, catalog);
         may_update_optional!(self, model, artist);
+        if let Some(localized) = self.localized {
+            model.localized = ActiveValue::set(localized_json(Some(localized))?);
+        }
 
         model.updated_at = ActiveValue::set(now());
-        model.update(db).await
+        Ok(model.update(db).await?)
     }
 }
 
@@ -180,6 +201,10 @@ pub struct UpdateTrackInfoInput {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub r#type: Option<TrackType>,
+    /// Replace artist credits of the track. An empty list removes all credits.
+    pub artists: Option<Vec<ArtistCreditInput>>,
+    /// Replace localized titles and artists. An empty list removes all of them.
+    pub localized: Option<Vec<LocalizedTextInput>>,
 }
 
 impl UpdateTrackInfoInput {
@@ -187,15 +212,111 @@ impl UpdateTrackInfoInput {
         self,
         mut model: track::ActiveModel,
         db: &DatabaseConnection,
-    ) -> Result<track::Model, DbErr> {
+    ) -> anyhow::Result<track::Model> {
         may_update_required!(self, model, title);
         may_update_required!(self, model, artist);
         if let Some(r#type) = self.r#type {
             model.r#type = sea_orm::ActiveValue::set(r#type.into());
         }
+        if let Some(artists) = self.artists {
+            model.artists = ActiveValue::set(credits_json(artists, db).await?);
+        }
+        if let Some(localized) = self.localized {
+            model.localized = ActiveValue::set(localized_json(Some(localized))?);
+        }
 
         model.updated_at = ActiveValue::set(now());
-        model.update(db).await
+        Ok(model.update(db).await?)
+    }
+}
+
+/// Artist credited for a role.
+#[derive(InputObject)]
+pub struct ArtistCreditInput {
+    pub role: ArtistRole,
+    /// Name of the role, required if `role` is `CUSTOM`.
+    pub custom_role: Option<String>,
+    pub name: String,
+    pub kind: Option<ArtistKind>,
+    /// Tag which describes the artist.
+    pub tag: Option<ID>,
+}
+
+impl ArtistCreditInput {
+    async fn into_credit<C: ConnectionTrait>(self, db: &C) -> anyhow::Result<model::ArtistCredit> {
+        let role = self
+            .role
+            .into_model(self.custom_role)
+            .ok_or_else(|| anyhow::anyhow!("customRole is required for CUSTOM role"))?;
+        let tag = match self.tag {
+            Some(id) => {
+                let tag = tag_info::Entity::find_by_id(id.parse::<i32>()?)
+                    .one(db)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("tag {} not found", id.as_str()))?;
+                let tag_type = TagType::from(&tag.r#type).to_string();
+                let tag_type =
+                    model::TagType::from_str(&tag_type).unwrap_or(model::TagType::Unknown);
+                Some(model::TagString::new(tag.name, tag_type))
+            }
+            None => None,
+        };
+
+        Ok(model::ArtistCredit {
+            role,
+            name: self.name,
+            kind: self.kind.map(Into::into),
+            tag,
+        })
+    }
+}
+
+/// Convert `credits` to json stored in `artists` of a track.
+async fn credits_json<C: ConnectionTrait>(
+    credits: Vec<ArtistCreditInput>,
+    db: &C,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    let mut result = model::ArtistCredits::new();
+    for credit in credits {
+        result.push(credit.into_credit(db).await?);
+    }
+    if result.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(serde_json::to_value(result)?))
+    }
+}
+
+/// Title and artist in a locale.
+#[derive(InputObject)]
+pub struct LocalizedTextInput {
+    /// BCP 47 language tag, such as `en`, `zh-Hans` or `ja-Latn`.
+    pub locale: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+/// Convert `localized` to json stored in `localized` of an album, a disc or a track.
+pub(crate) fn localized_json(
+    localized: Option<Vec<LocalizedTextInput>>,
+) -> serde_json::Result<Option<serde_json::Value>> {
+    let localized: model::Localized = localized
+        .unwrap_or_default()
+        .into_iter()
+        .map(|input| {
+            (
+                input.locale,
+                model::LocalizedText {
+                    title: input.title,
+                    artist: input.artist,
+                },
+            )
+        })
+        .collect();
+    if localized.is_empty() {
+        Ok(None)
+    } else {
+        serde_json::to_value(localized).map(Some)
     }
 }
 
@@ -1,13 +1,14 @@
 mod cursor;
 mod input;
+mod subscription;
 pub mod types;
 
-use std::{i64, str::FromStr};
+use std::{collections::HashMap, i64, str::FromStr};
 
 use anyhow::Ok;
 use async_graphql::{
     connection::{Connection, Edge},
-    Context, EmptySubscription, Object, Schema, ID,
+    Context, Object, Schema, ID,
 };
 use cursor::Cursor;
 use input::{AlbumsBy, MetadataIDInput};
@@ -16,6 +17,10 @@ use sea_orm::{
     DatabaseConnection, EntityTrait, Identity, ModelTrait, QueryFilter, QueryOrder, QuerySelect,
     Related, TransactionTrait,
 };
+use subscription::{
+    album_tag_ids, AlbumEventKind, DiscEventKind, MetadataEvent, TagEventKind, TrackEventKind,
+};
+pub use subscription::{MetadataEvents, MetadataSubscription};
 use tantivy::{
     collector::TopDocs,
     query::{BooleanQuery, Occur, PhraseQuery, QueryClone, TermQuery},
@@ -32,11 +37,17 @@ use crate::{
     search::RepositorySearchManager,
 };
 
-pub type MetadataSchema = Schema<MetadataQuery, MetadataMutation, EmptySubscription>;
+pub type MetadataSchema = Schema<MetadataQuery, MetadataMutation, MetadataSubscription>;
+
+/// Maximum number of albums in a page of keyword search. Larger `first` is clamped to it.
+const MAX_PAGE_SIZE: u64 = 100;
+/// Maximum offset of search results. Ranking deeper results costs memory for all results before them.
+const MAX_SEARCH_OFFSET: usize = 10_000;
 
 pub fn build_schema(db: DatabaseConnection) -> MetadataSchema {
-    Schema::build(MetadataQuery, MetadataMutation, EmptySubscription)
+    Schema::build(MetadataQuery, MetadataMutation, MetadataSubscription)
         .data(db)
+        .data(MetadataEvents::new())
         .finish()
 }
 
@@ -67,6 +78,7 @@ impl MetadataQuery {
         // last: Option<i32>,
     ) -> anyhow::Result<Connection<String, AlbumInfo>> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
+        let limit = first.unwrap_or(20);
 
         let (query, columns, desc) = match by {
             AlbumsBy::AlbumIds(album_ids) => (
@@ -98,7 +110,7 @@ impl MetadataQuery {
                 ],
                 true,
             ),
-            AlbumsBy::Keyword(_) => unimplemented!(),
+            AlbumsBy::Keyword(keyword) => return search_albums(ctx, &keyword, after, limit).await,
             AlbumsBy::OrganizeLevel(level) => (
                 album::Entity::find().filter(album::Column::Level.eq(level.to_string())),
                 vec![album::Column::Id],
@@ -114,7 +126,6 @@ impl MetadataQuery {
             ),
         };
 
-        let limit = first.unwrap_or(20);
         let mut query = query.cursor_by(Identity::Many(
             columns.iter().map(|r| r.into_iden()).collect(),
         ));
@@ -219,6 +230,85 @@ impl MetadataQuery {
     }
 }
 
+/// Search albums by keyword, ranked by score.
+///
+/// Cursors of search results are offsets in the ranked list.
+async fn search_albums(
+    ctx: &Context<'_>,
+    keyword: &str,
+    after: Option<String>,
+    limit: u64,
+) -> anyhow::Result<Connection<String, AlbumInfo>> {
+    let db = ctx.data::<DatabaseConnection>().unwrap();
+    let search_manager = ctx.data::<RepositorySearchManager>().unwrap();
+
+    let offset = match after {
+        Some(cursor) => Cursor::from_str(&cursor)?.into_offset()?,
+        None => 0,
+    };
+    if offset > MAX_SEARCH_OFFSET {
+        anyhow::bail!("Search offset {offset} exceeds the maximum of {MAX_SEARCH_OFFSET}");
+    }
+    let limit = usize::try_from(limit.min(MAX_PAGE_SIZE))?;
+    // fetch one more result to tell whether there is a next page
+    let fetch_limit = limit
+        .checked_add(1)
+        .filter(|fetch_limit| offset.checked_add(*fetch_limit).is_some())
+        .ok_or_else(|| anyhow::anyhow!("Search page is out of range"))?;
+
+    let query = search_manager.album_query_parser().parse_query(keyword)?;
+    let query_disc = TermQuery::new(
+        Term::from_field_i64(search_manager.fields.disc_db_id, i64::MAX),
+        Default::default(),
+    );
+    let query_track = TermQuery::new(
+        Term::from_field_i64(search_manager.fields.track_db_id, i64::MAX),
+        Default::default(),
+    );
+    let query = BooleanQuery::new(vec![
+        (Occur::Must, query),
+        // AND disc_db_id:9223372036854775807 AND track_db_id:9223372036854775807
+        (Occur::Must, Box::new(query_disc)),
+        (Occur::Must, Box::new(query_track)),
+    ]);
+
+    let searcher = search_manager.searcher();
+    let top_docs = searcher.search(&query, &TopDocs::with_limit(fetch_limit).and_offset(offset))?;
+    let has_next_page = top_docs.len() > limit;
+
+    let album_db_ids: Vec<_> = top_docs
+        .into_iter()
+        .take(limit)
+        .map(|(_, addr)| {
+            let doc = searcher.doc(addr)?;
+            let (album_db_id, _, _) = search_manager.deserialize_document(doc);
+            Ok(album_db_id as i32)
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let mut albums: HashMap<_, _> = album::Entity::find()
+        .filter(album::Column::Id.is_in(album_db_ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model))
+        .collect();
+
+    // keep the order of scores, and skip albums removed after indexing
+    let edges = album_db_ids
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, album_db_id)| {
+            let model = albums.remove(&album_db_id)?;
+            let cursor = Cursor::from_offset(offset + index + 1);
+            Some(Edge::new(cursor.to_string(), AlbumInfo(model)))
+        });
+
+    let mut connection = Connection::new(offset > 0, has_next_page);
+    connection.edges.extend(edges);
+    Ok(connection)
+}
+
 pub struct MetadataMutation;
 
 #[Object(guard = "AdminGuard")]
@@ -245,6 +335,7 @@ impl MetadataMutation {
             release_month: ActiveValue::set(input.release_month),
             release_day: ActiveValue::set(input.release_day),
             extra: ActiveValue::set(input.extra),
+            localized: ActiveValue::set(input::localized_json(input.localized)?),
             ..Default::default()
         };
         let album = album.insert(&txn).await?;
@@ -264,6 +355,13 @@ impl MetadataMutation {
             index_writer.commit().await?;
         }
 
+        // tags can not be added together with the album
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::album(
+            AlbumEventKind::Added,
+            &album,
+            Vec::new(),
+        ));
         Ok(AlbumInfo(album))
     }
 
@@ -285,7 +383,11 @@ impl MetadataMutation {
             return Ok(None);
         };
 
-        let need_update_search_index = input.title.is_some() || input.artist.is_some();
+        let need_update_search_index = input.title.is_some()
+            || input.edition.is_some()
+            || input.catalog.is_some()
+            || input.artist.is_some()
+            || input.localized.is_some();
         let album: album::ActiveModel = model.into();
         let album = input.update(album, db).await?;
 
@@ -298,16 +400,17 @@ impl MetadataMutation {
                 Term::from_field_i64(searcher.fields.track_db_id, i64::MAX),
             ]);
             index_writer.delete_query(Box::new(query))?;
-            index_writer.add_document(searcher.build_track_document(
-                &album.title,
-                &album.artist,
-                album.id as i64,
-                None,
-                None,
-            ))?;
+            index_writer.add_album_info(&album)?;
             index_writer.commit().await?;
         }
 
+        let tag_db_ids = album_tag_ids(db, album.id).await?;
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::album(
+            AlbumEventKind::Updated,
+            &album,
+            tag_db_ids,
+        ));
         Ok(Some(AlbumInfo(album)))
     }
 
@@ -325,7 +428,8 @@ impl MetadataMutation {
             return Ok(None);
         };
 
-        let need_update_search_index = input.title.is_some() || input.artist.is_some();
+        let need_update_search_index =
+            input.title.is_some() || input.artist.is_some() || input.localized.is_some();
         let disc: disc::ActiveModel = model.into();
         let disc = input.update(disc, db).await?;
 
@@ -338,15 +442,17 @@ impl MetadataMutation {
                 Term::from_field_i64(searcher.fields.track_db_id, i64::MAX),
             ]);
             index_writer.delete_query(Box::new(query))?;
-            index_writer.add_document(searcher.build_track_document(
-                disc.title.as_deref().unwrap_or_default(),
-                disc.artist.as_deref().unwrap_or_default(),
-                disc.album_db_id as i64,
-                Some(disc.id as i64),
-                None,
-            ))?;
+            index_writer.add_disc_info(&disc)?;
             index_writer.commit().await?;
         }
+
+        let tag_db_ids = album_tag_ids(db, disc.album_db_id).await?;
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::disc(
+            DiscEventKind::Updated,
+            &disc,
+            tag_db_ids,
+        ));
         Ok(Some(DiscInfo(disc)))
     }
 
@@ -364,7 +470,10 @@ impl MetadataMutation {
             return Ok(None);
         };
 
-        let need_update_search_index = input.title.is_some() || input.artist.is_some();
+        let need_update_search_index = input.title.is_some()
+            || input.artist.is_some()
+            || input.artists.is_some()
+            || input.localized.is_some();
         let track: track::ActiveModel = model.into();
         let track = input.update(track, db).await?;
 
@@ -377,15 +486,17 @@ impl MetadataMutation {
                 Term::from_field_i64(searcher.fields.track_db_id, track.id as i64),
             ]);
             index_writer.delete_query(Box::new(query))?;
-            index_writer.add_document(searcher.build_track_document(
-                &track.title,
-                &track.artist,
-                track.album_db_id as i64,
-                Some(track.disc_db_id as i64),
-                Some(track.id as i64),
-            ))?;
+            index_writer.add_track_info(&track)?;
             index_writer.commit().await?;
         }
+
+        let tag_db_ids = album_tag_ids(db, track.album_db_id).await?;
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::track(
+            TrackEventKind::Updated,
+            &track,
+            tag_db_ids,
+        ));
         Ok(Some(TrackInfo(track)))
     }
 
@@ -414,6 +525,8 @@ impl MetadataMutation {
         }
 
         let album_db_id = album.id;
+        // tags of removed discs and tracks are removed with them
+        let mut tag_db_ids = album_tag_ids(db, album_db_id).await?;
         let txn = db.begin().await?;
 
         // 1. remove old discs
@@ -452,6 +565,13 @@ impl MetadataMutation {
         txn.commit().await?;
         index_writer.commit().await?;
 
+        tag_db_ids.extend(album_tag_ids(db, album_db_id).await?);
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::album(
+            AlbumEventKind::DiscsReplaced,
+            &album,
+            tag_db_ids,
+        ));
         Ok(Some(AlbumInfo(album)))
     }
 
@@ -489,6 +609,8 @@ impl MetadataMutation {
         }
 
         let disc_db_id = disc.id;
+        // tags of removed tracks are removed with them
+        let mut tag_db_ids = album_tag_ids(db, album_db_id).await?;
         let txn = db.begin().await?;
 
         // 1. remove old tracks
@@ -522,6 +644,13 @@ impl MetadataMutation {
         txn.commit().await?;
         index_writer.commit().await?;
 
+        tag_db_ids.extend(album_tag_ids(db, album_db_id).await?);
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::disc(
+            DiscEventKind::TracksReplaced,
+            &disc,
+            tag_db_ids,
+        ));
         Ok(Some(DiscInfo(disc)))
     }
 
@@ -608,6 +737,15 @@ impl MetadataMutation {
         album.updated_at = ActiveValue::set(now());
 
         let album = album.update(db).await?;
+
+        let tag_db_ids = album_tag_ids(db, album.id).await?;
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::organize_level(
+            &album,
+            original_level,
+            input.level,
+            tag_db_ids,
+        ));
         Ok(Some(AlbumInfo(album)))
     }
 
@@ -627,6 +765,9 @@ impl MetadataMutation {
             ..Default::default()
         };
         let tag = tag.insert(db).await?;
+
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(MetadataEvent::tag(TagEventKind::Added, tag.id, None));
         Ok(TagInfo(tag))
     }
 
@@ -638,6 +779,9 @@ impl MetadataMutation {
         remove: Option<bool>,
     ) -> anyhow::Result<Option<TagRelation>> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        let tag_db_id = tag_id.parse::<i32>()?;
+        let parent_db_id = parent_id.parse::<i32>()?;
 
         let remove = remove.unwrap_or(false);
         if remove {
@@ -645,20 +789,30 @@ impl MetadataMutation {
             tag_relation::Entity::delete_many()
                 .filter(
                     tag_relation::Column::TagDbId
-                        .eq(tag_id.parse::<i32>()?)
-                        .and(tag_relation::Column::ParentTagDbId.eq(parent_id.parse::<i32>()?)),
+                        .eq(tag_db_id)
+                        .and(tag_relation::Column::ParentTagDbId.eq(parent_db_id)),
                 )
                 .exec(db)
                 .await?;
+            events.publish(MetadataEvent::tag(
+                TagEventKind::RelationRemoved,
+                tag_db_id,
+                Some(parent_db_id),
+            ));
             Ok(None)
         } else {
             // create relation
             let relation = tag_relation::ActiveModel {
-                tag_db_id: ActiveValue::set(tag_id.parse::<i32>()?),
-                parent_tag_db_id: ActiveValue::set(parent_id.parse::<i32>()?),
+                tag_db_id: ActiveValue::set(tag_db_id),
+                parent_tag_db_id: ActiveValue::set(parent_db_id),
                 ..Default::default()
             };
             let relation = relation.insert(db).await?;
+            events.publish(MetadataEvent::tag(
+                TagEventKind::RelationAdded,
+                tag_db_id,
+                Some(parent_db_id),
+            ));
             Ok(Some(TagRelation(relation)))
         }
     }
@@ -676,9 +830,17 @@ impl MetadataMutation {
             .map(|id| id.parse::<i32>())
             .collect::<Result<Vec<_>, _>>()?;
 
-        let album_db_id = match input {
+        enum Target {
+            Album,
+            Disc(disc::Model),
+            Track(track::Model),
+        }
+
+        // tags before the change are collected, so that subscribers of removed tags are notified
+        let (album_db_id, mut tag_db_ids, target) = match input {
             MetadataIDInput::Album(album_db_id) => {
                 let album_db_id = album_db_id.parse::<i32>()?;
+                let tag_db_ids = album_tag_ids(db, album_db_id).await?;
                 album_tag_relation::Entity::delete_many()
                     .filter(
                         album_tag_relation::Column::AlbumDbId
@@ -698,10 +860,12 @@ impl MetadataMutation {
                 .exec(db)
                 .await?;
 
-                album_db_id
+                (album_db_id, tag_db_ids, Target::Album)
             }
             MetadataIDInput::Disc(disc_db_id) => {
                 let disc_db_id = disc_db_id.parse::<i32>()?;
+                let disc = disc::Entity::find_by_id(disc_db_id).one(db).await?.unwrap();
+                let tag_db_ids = album_tag_ids(db, disc.album_db_id).await?;
                 album_tag_relation::Entity::delete_many()
                     .filter(
                         album_tag_relation::Column::DiscDbId
@@ -711,7 +875,6 @@ impl MetadataMutation {
                     .exec(db)
                     .await?;
 
-                let disc = disc::Entity::find_by_id(disc_db_id).one(db).await?.unwrap();
                 album_tag_relation::Entity::insert_many(tags_id.into_iter().map(|tag_id| {
                     album_tag_relation::ActiveModel {
                         album_db_id: ActiveValue::set(disc.album_db_id),
@@ -723,19 +886,19 @@ impl MetadataMutation {
                 .exec(db)
                 .await?;
 
-                disc.album_db_id
+                (disc.album_db_id, tag_db_ids, Target::Disc(disc))
             }
             MetadataIDInput::Track(track_db_id) => {
                 let track_db_id = track_db_id.parse::<i32>()?;
-                album_tag_relation::Entity::delete_many()
-                    .filter(album_tag_relation::Column::TrackDbId.eq(track_db_id))
-                    .exec(db)
-                    .await?;
-
                 let track = track::Entity::find_by_id(track_db_id)
                     .one(db)
                     .await?
                     .unwrap();
+                let tag_db_ids = album_tag_ids(db, track.album_db_id).await?;
+                album_tag_relation::Entity::delete_many()
+                    .filter(album_tag_relation::Column::TrackDbId.eq(track_db_id))
+                    .exec(db)
+                    .await?;
                 album_tag_relation::Entity::insert_many(tags_id.into_iter().map(|tag_id| {
                     album_tag_relation::ActiveModel {
                         album_db_id: ActiveValue::set(track.album_db_id),
@@ -748,14 +911,27 @@ impl MetadataMutation {
                 .exec(db)
                 .await?;
 
-                track.album_db_id
+                (track.album_db_id, tag_db_ids, Target::Track(track))
             }
         };
+        tag_db_ids.extend(album_tag_ids(db, album_db_id).await?);
 
         let album = album::Entity::find_by_id(album_db_id)
             .one(db)
             .await?
             .unwrap();
+
+        let event = match target {
+            Target::Album => MetadataEvent::album(AlbumEventKind::TagsUpdated, &album, tag_db_ids),
+            Target::Disc(disc) => {
+                MetadataEvent::disc(DiscEventKind::TagsUpdated, &disc, tag_db_ids)
+            }
+            Target::Track(track) => {
+                MetadataEvent::track(TrackEventKind::TagsUpdated, &track, tag_db_ids)
+            }
+        };
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        events.publish(event);
         Ok(AlbumInfo(album))
     }
 
@@ -768,63 +944,93 @@ impl MetadataMutation {
         writer.delete_all()?;
 
         // 2. insert albums
-        let albums: Vec<(i32, String, String)> = album::Entity::find()
+        let albums: Vec<(
+            i32,
+            String,
+            Option<String>,
+            Option<String>,
+            String,
+            Option<serde_json::Value>,
+        )> = album::Entity::find()
             .select_only()
             .column(album::Column::Id)
             .column(album::Column::Title)
+            .column(album::Column::Edition)
+            .column(album::Column::Catalog)
             .column(album::Column::Artist)
+            .column(album::Column::Localized)
             .into_tuple()
             .all(db)
             .await?;
-        for (album_db_id, title, artist) in albums {
-            writer.add_document(searcher.build_track_document(
-                &title,
-                &artist,
-                album_db_id as i64,
-                None,
-                None,
-            ))?;
+        for (album_db_id, title, edition, catalog, artist, localized) in albums {
+            let mut document =
+                searcher.build_track_document(&title, &artist, album_db_id as i64, None, None);
+            searcher.add_edition_and_catalog(&mut document, edition.as_deref(), catalog.as_deref());
+            searcher.add_localized(&mut document, localized.as_ref());
+            writer.add_document(document)?;
         }
 
         // 3. insert discs
-        let discs: Vec<(i32, i32, Option<String>, Option<String>)> = disc::Entity::find()
+        let discs: Vec<(
+            i32,
+            i32,
+            Option<String>,
+            Option<String>,
+            Option<serde_json::Value>,
+        )> = disc::Entity::find()
             .select_only()
             .column(disc::Column::AlbumDbId)
             .column(disc::Column::Id)
             .column(disc::Column::Title)
             .column(disc::Column::Artist)
+            .column(disc::Column::Localized)
             .into_tuple()
             .all(db)
             .await?;
-        for (album_db_id, disc_db_id, title, artist) in discs {
-            writer.add_document(searcher.build_track_document(
+        for (album_db_id, disc_db_id, title, artist, localized) in discs {
+            let mut document = searcher.build_track_document(
                 &title.unwrap_or_default(),
                 &artist.unwrap_or_default(),
                 album_db_id as i64,
                 Some(disc_db_id as i64),
                 None,
-            ))?;
+            );
+            searcher.add_localized(&mut document, localized.as_ref());
+            writer.add_document(document)?;
         }
 
         // 4. insert tracks
-        let tracks: Vec<(i32, i32, i32, String, String)> = track::Entity::find()
+        let tracks: Vec<(
+            i32,
+            i32,
+            i32,
+            String,
+            String,
+            Option<serde_json::Value>,
+            Option<serde_json::Value>,
+        )> = track::Entity::find()
             .select_only()
             .column(track::Column::AlbumDbId)
             .column(track::Column::DiscDbId)
             .column(track::Column::Id)
             .column(track::Column::Title)
             .column(track::Column::Artist)
+            .column(track::Column::Artists)
+            .column(track::Column::Localized)
             .into_tuple()
             .all(db)
             .await?;
-        for (album_db_id, disc_db_id, track_db_id, title, artist) in tracks {
-            writer.add_document(searcher.build_track_document(
+        for (album_db_id, disc_db_id, track_db_id, title, artist, artists, localized) in tracks {
+            let mut document = searcher.build_track_document(
                 &title,
                 &artist,
                 album_db_id as i64,
                 Some(disc_db_id as i64),
                 Some(track_db_id as i64),
-            ))?;
+            );
+            searcher.add_credits(&mut document, artists.as_ref());
+            searcher.add_localized(&mut document, localized.as_ref());
+            writer.add_document(document)?;
         }
 
         // 5. commit
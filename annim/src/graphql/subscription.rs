@@ -0,0 +1,326 @@
+use async_graphql::{Context, Enum, Object, Subscription, Union, ID};
+use futures::{Stream, StreamExt};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+use super::types::{AlbumInfo, DiscInfo, MetadataOrganizeLevel, TagInfo, TrackInfo};
+use crate::entities::{album, album_tag_relation, disc, tag_info, track};
+
+/// In-process bus which delivers changes made by mutations to subscribers.
+#[derive(Clone)]
+pub struct MetadataEvents(broadcast::Sender<MetadataEvent>);
+
+impl MetadataEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self(sender)
+    }
+
+    /// Send an event to current subscribers. Events are dropped if there is no subscriber.
+    pub(crate) fn publish(&self, event: MetadataEvent) {
+        let _ = self.0.send(event);
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<MetadataEvent> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for MetadataEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct MetadataSubscription;
+
+#[Subscription]
+impl MetadataSubscription {
+    /// Changes of albums, discs, tracks, tags and organize levels made by mutations.
+    ///
+    /// If `albumId` is given, only changes of the album are sent.
+    /// If `tagId` is given, only changes of the tag and changes of albums with the tag are sent,
+    /// including changes which add or remove the tag.
+    async fn metadata_changed<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        album_id: Option<ID>,
+        tag_id: Option<ID>,
+    ) -> anyhow::Result<impl Stream<Item = MetadataEvent> + 'ctx> {
+        let events = ctx.data::<MetadataEvents>().unwrap();
+        let album_db_id = album_id.map(|id| id.parse::<i32>()).transpose()?;
+        let tag_db_id = tag_id.map(|id| id.parse::<i32>()).transpose()?;
+
+        let stream = BroadcastStream::new(events.subscribe()).filter_map(move |event| async move {
+            // events missed by lagging subscribers are skipped
+            let event = event.ok()?;
+            if album_db_id.is_some_and(|id| event.album_db_id() != Some(id)) {
+                return None;
+            }
+            if tag_db_id.is_some_and(|id| !event.tag_db_ids().contains(&id)) {
+                return None;
+            }
+            Some(event)
+        });
+        Ok(stream)
+    }
+}
+
+/// Ids of tags on an album, its discs and tracks.
+///
+/// Mutations collect them before and after a change, so that subscribers of a tag receive
+/// changes which add or remove it as well.
+pub(crate) async fn album_tag_ids<C: ConnectionTrait>(
+    db: &C,
+    album_db_id: i32,
+) -> anyhow::Result<Vec<i32>> {
+    let tag_db_ids = album_tag_relation::Entity::find()
+        .select_only()
+        .column(album_tag_relation::Column::TagDbId)
+        .filter(album_tag_relation::Column::AlbumDbId.eq(album_db_id))
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(tag_db_ids)
+}
+
+/// A change made by mutations.
+#[derive(Union, Clone)]
+pub enum MetadataEvent {
+    Album(AlbumEvent),
+    Disc(DiscEvent),
+    Track(TrackEvent),
+    Tag(TagEvent),
+    OrganizeLevel(OrganizeLevelEvent),
+}
+
+impl MetadataEvent {
+    /// `tag_db_ids` are tags of the album before and after the change, see [album_tag_ids].
+    pub(crate) fn album(kind: AlbumEventKind, album: &album::Model, tag_db_ids: Vec<i32>) -> Self {
+        MetadataEvent::Album(AlbumEvent {
+            kind,
+            album: album.clone(),
+            tag_db_ids,
+        })
+    }
+
+    pub(crate) fn disc(kind: DiscEventKind, disc: &disc::Model, tag_db_ids: Vec<i32>) -> Self {
+        MetadataEvent::Disc(DiscEvent {
+            kind,
+            disc: disc.clone(),
+            tag_db_ids,
+        })
+    }
+
+    pub(crate) fn track(kind: TrackEventKind, track: &track::Model, tag_db_ids: Vec<i32>) -> Self {
+        MetadataEvent::Track(TrackEvent {
+            kind,
+            track: track.clone(),
+            tag_db_ids,
+        })
+    }
+
+    pub(crate) fn tag(kind: TagEventKind, tag_db_id: i32, parent_db_id: Option<i32>) -> Self {
+        MetadataEvent::Tag(TagEvent {
+            kind,
+            tag_db_id,
+            parent_db_id,
+            tag_db_ids: [Some(tag_db_id), parent_db_id]
+                .into_iter()
+                .flatten()
+                .collect(),
+        })
+    }
+
+    pub(crate) fn organize_level(
+        album: &album::Model,
+        from: MetadataOrganizeLevel,
+        to: MetadataOrganizeLevel,
+        tag_db_ids: Vec<i32>,
+    ) -> Self {
+        MetadataEvent::OrganizeLevel(OrganizeLevelEvent {
+            album: album.clone(),
+            from,
+            to,
+            tag_db_ids,
+        })
+    }
+
+    fn album_db_id(&self) -> Option<i32> {
+        match self {
+            MetadataEvent::Album(event) => Some(event.album.id),
+            MetadataEvent::Disc(event) => Some(event.disc.album_db_id),
+            MetadataEvent::Track(event) => Some(event.track.album_db_id),
+            MetadataEvent::Tag(_) => None,
+            MetadataEvent::OrganizeLevel(event) => Some(event.album.id),
+        }
+    }
+
+    /// Tags the event is about. For changes of albums, discs and tracks, they are tags of the album,
+    /// its discs or tracks before and after the change.
+    fn tag_db_ids(&self) -> &[i32] {
+        match self {
+            MetadataEvent::Album(event) => &event.tag_db_ids,
+            MetadataEvent::Disc(event) => &event.tag_db_ids,
+            MetadataEvent::Track(event) => &event.tag_db_ids,
+            MetadataEvent::Tag(event) => &event.tag_db_ids,
+            MetadataEvent::OrganizeLevel(event) => &event.tag_db_ids,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum AlbumEventKind {
+    Added,
+    Updated,
+    DiscsReplaced,
+    TagsUpdated,
+}
+
+#[derive(Clone)]
+pub struct AlbumEvent {
+    kind: AlbumEventKind,
+    album: album::Model,
+    tag_db_ids: Vec<i32>,
+}
+
+#[Object]
+impl AlbumEvent {
+    async fn kind(&self) -> AlbumEventKind {
+        self.kind
+    }
+
+    /// The album after the change.
+    async fn album(&self) -> AlbumInfo {
+        AlbumInfo(self.album.clone())
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum DiscEventKind {
+    Updated,
+    TracksReplaced,
+    TagsUpdated,
+}
+
+#[derive(Clone)]
+pub struct DiscEvent {
+    kind: DiscEventKind,
+    disc: disc::Model,
+    tag_db_ids: Vec<i32>,
+}
+
+#[Object]
+impl DiscEvent {
+    async fn kind(&self) -> DiscEventKind {
+        self.kind
+    }
+
+    async fn album_id(&self) -> ID {
+        ID(self.disc.album_db_id.to_string())
+    }
+
+    /// The disc after the change.
+    async fn disc(&self) -> DiscInfo {
+        DiscInfo(self.disc.clone())
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum TrackEventKind {
+    Updated,
+    TagsUpdated,
+}
+
+#[derive(Clone)]
+pub struct TrackEvent {
+    kind: TrackEventKind,
+    track: track::Model,
+    tag_db_ids: Vec<i32>,
+}
+
+#[Object]
+impl TrackEvent {
+    async fn kind(&self) -> TrackEventKind {
+        self.kind
+    }
+
+    async fn album_id(&self) -> ID {
+        ID(self.track.album_db_id.to_string())
+    }
+
+    async fn disc_id(&self) -> ID {
+        ID(self.track.disc_db_id.to_string())
+    }
+
+    /// The track after the change.
+    async fn track(&self) -> TrackInfo {
+        TrackInfo(self.track.clone())
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum TagEventKind {
+    Added,
+    RelationAdded,
+    RelationRemoved,
+}
+
+#[derive(Clone)]
+pub struct TagEvent {
+    kind: TagEventKind,
+    tag_db_id: i32,
+    parent_db_id: Option<i32>,
+    tag_db_ids: Vec<i32>,
+}
+
+#[Object]
+impl TagEvent {
+    async fn kind(&self) -> TagEventKind {
+        self.kind
+    }
+
+    async fn tag<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Option<TagInfo>> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let model = tag_info::Entity::find_by_id(self.tag_db_id).one(db).await?;
+        Ok(model.map(TagInfo))
+    }
+
+    /// Parent tag of the relation. Only available for relation changes.
+    async fn parent<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Option<TagInfo>> {
+        let Some(parent_db_id) = self.parent_db_id else {
+            return Ok(None);
+        };
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let model = tag_info::Entity::find_by_id(parent_db_id).one(db).await?;
+        Ok(model.map(TagInfo))
+    }
+}
+
+#[derive(Clone)]
+pub struct OrganizeLevelEvent {
+    album: album::Model,
+    from: MetadataOrganizeLevel,
+    to: MetadataOrganizeLevel,
+    tag_db_ids: Vec<i32>,
+}
+
+#[Object]
+impl OrganizeLevelEvent {
+    /// The album after the change.
+    async fn album(&self) -> AlbumInfo {
+        AlbumInfo(self.album.clone())
+    }
+
+    async fn from(&self) -> MetadataOrganizeLevel {
+        self.from
+    }
+
+    async fn to(&self) -> MetadataOrganizeLevel {
+        self.to
+    }
+}
@@ -7,8 +7,12 @@ use sea_orm::{
     QueryOrder, QuerySelect, RelationTrait,
 };
 
+use anni_metadata::model;
+
 use crate::entities::{
-    album, album_tag_relation, disc, helper::timestamp, tag_info, tag_relation, track,
+    album, album_tag_relation, disc,
+    helper::{localized_texts, timestamp, track_credits},
+    tag_info, tag_relation, track,
 };
 
 pub struct AlbumInfo(pub(crate) album::Model);
@@ -25,8 +29,11 @@ impl AlbumInfo {
     }
 
     /// Title of the album.
-    async fn title(&self) -> &str {
-        self.0.title.as_str()
+    ///
+    /// If `locales` is given, title in the first available locale is returned instead.
+    async fn title(&self, locales: Option<Vec<String>>) -> anyhow::Result<String> {
+        let title = find_localized(self.0.localized.as_ref(), locales, model::Localized::title)?;
+        Ok(title.unwrap_or_else(|| self.0.title.clone()))
     }
 
     /// Optional edition of the album.
@@ -40,8 +47,16 @@ impl AlbumInfo {
     }
 
     /// Artist of the album.
-    async fn artist(&self) -> &str {
-        self.0.artist.as_str()
+    ///
+    /// If `locales` is given, artist in the first available locale is returned instead.
+    async fn artist(&self, locales: Option<Vec<String>>) -> anyhow::Result<String> {
+        let artist = find_localized(self.0.localized.as_ref(), locales, model::Localized::artist)?;
+        Ok(artist.unwrap_or_else(|| self.0.artist.clone()))
+    }
+
+    /// Localized titles and artists of the album.
+    async fn localized(&self) -> anyhow::Result<Vec<LocalizedText>> {
+        localized_list(self.0.localized.as_ref())
     }
 
     /// Release year of the album.
@@ -125,16 +140,23 @@ impl DiscInfo {
         self.0.index
     }
 
-    async fn title(&self) -> Option<&str> {
-        self.0.title.as_deref()
+    async fn title(&self, locales: Option<Vec<String>>) -> anyhow::Result<Option<String>> {
+        let title = find_localized(self.0.localized.as_ref(), locales, model::Localized::title)?;
+        Ok(title.or_else(|| self.0.title.clone()))
     }
 
     async fn catalog(&self) -> Option<&str> {
         self.0.catalog.as_deref()
     }
 
-    async fn artist(&self) -> Option<&str> {
-        self.0.artist.as_deref()
+    async fn artist(&self, locales: Option<Vec<String>>) -> anyhow::Result<Option<String>> {
+        let artist = find_localized(self.0.localized.as_ref(), locales, model::Localized::artist)?;
+        Ok(artist.or_else(|| self.0.artist.clone()))
+    }
+
+    /// Localized titles and artists of the disc.
+    async fn localized(&self) -> anyhow::Result<Vec<LocalizedText>> {
+        localized_list(self.0.localized.as_ref())
     }
 
     async fn tags<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Vec<TagInfo>> {
@@ -190,20 +212,29 @@ impl TrackInfo {
         self.0.index
     }
 
-    async fn title(&self) -> &str {
-        self.0.title.as_str()
+    async fn title(&self, locales: Option<Vec<String>>) -> anyhow::Result<String> {
+        let title = find_localized(self.0.localized.as_ref(), locales, model::Localized::title)?;
+        Ok(title.unwrap_or_else(|| self.0.title.clone()))
     }
 
-    async fn artist(&self) -> &str {
-        self.0.artist.as_str()
+    async fn artist(&self, locales: Option<Vec<String>>) -> anyhow::Result<String> {
+        let artist = find_localized(self.0.localized.as_ref(), locales, model::Localized::artist)?;
+        Ok(artist.unwrap_or_else(|| self.0.artist.clone()))
+    }
+
+    /// Localized titles and artists of the track.
+    async fn localized(&self) -> anyhow::Result<Vec<LocalizedText>> {
+        localized_list(self.0.localized.as_ref())
     }
 
     async fn r#type(&self) -> TrackType {
         (&self.0.r#type).into()
     }
 
-    async fn artists(&self) -> Option<&serde_json::Value> {
-        self.0.artists.as_ref()
+    /// Artists credited on the track, with their roles.
+    async fn artists(&self) -> anyhow::Result<Vec<ArtistCredit>> {
+        let credits = track_credits(self.0.artists.as_ref())?;
+        Ok(credits.into_iter().map(ArtistCredit).collect())
     }
 
     async fn tags<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Vec<TagInfo>> {
@@ -229,6 +260,164 @@ impl TrackInfo {
     }
 }
 
+/// Title and artist in a locale.
+pub struct LocalizedText(String, model::LocalizedText);
+
+#[Object]
+impl LocalizedText {
+    /// BCP 47 language tag, such as `en`, `zh-Hans` or `ja-Latn`.
+    async fn locale(&self) -> &str {
+        self.0.as_str()
+    }
+
+    async fn title(&self) -> Option<&str> {
+        self.1.title.as_deref()
+    }
+
+    async fn artist(&self) -> Option<&str> {
+        self.1.artist.as_deref()
+    }
+}
+
+fn localized_list(localized: Option<&serde_json::Value>) -> anyhow::Result<Vec<LocalizedText>> {
+    Ok(localized_texts(localized)?
+        .iter()
+        .map(|(locale, text)| LocalizedText(locale.to_string(), text.clone()))
+        .collect())
+}
+
+/// Find a localized value in the first available locale of `locales`.
+///
+/// `zh-Hans-CN` falls back to `zh-Hans` and `zh` before the next locale is tried.
+fn find_localized(
+    localized: Option<&serde_json::Value>,
+    locales: Option<Vec<String>>,
+    find: for<'a> fn(&'a model::Localized, &[&str]) -> Option<&'a str>,
+) -> anyhow::Result<Option<String>> {
+    let Some(locales) = locales else {
+        return Ok(None);
+    };
+    let localized = localized_texts(localized)?;
+    let locales: Vec<_> = locales.iter().map(String::as_str).collect();
+    Ok(find(&localized, &locales).map(ToString::to_string))
+}
+
+pub struct ArtistCredit(pub(crate) model::ArtistCredit);
+
+#[Object]
+impl ArtistCredit {
+    async fn role(&self) -> ArtistRole {
+        (&self.0.role).into()
+    }
+
+    /// Name of the role if `role` is `CUSTOM`.
+    async fn custom_role(&self) -> Option<&str> {
+        match &self.0.role {
+            model::ArtistRole::Custom(role) => Some(role),
+            _ => None,
+        }
+    }
+
+    /// Credited name of the artist.
+    async fn name(&self) -> &str {
+        self.0.name.as_str()
+    }
+
+    async fn kind(&self) -> Option<ArtistKind> {
+        self.0.kind.map(Into::into)
+    }
+
+    /// Tag which describes the artist.
+    async fn tag<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Option<TagInfo>> {
+        let Some(tag) = &self.0.tag else {
+            return Ok(None);
+        };
+
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let model = tag_info::Entity::find()
+            .filter(match tag.tag_type() {
+                model::TagType::Unknown => tag_info::Column::Name.eq(tag.name()),
+                tag_type => tag_info::Column::Name
+                    .eq(tag.name())
+                    .and(tag_info::Column::Type.eq(tag_type.to_string())),
+            })
+            .one(db)
+            .await?;
+        Ok(model.map(TagInfo))
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum ArtistRole {
+    Vocal,
+    Composer,
+    Lyricist,
+    Arranger,
+    Performer,
+    Producer,
+    Engineer,
+    Illustrator,
+    /// Role not listed above, whose name is in `customRole`.
+    Custom,
+}
+
+impl From<&model::ArtistRole> for ArtistRole {
+    fn from(value: &model::ArtistRole) -> Self {
+        match value {
+            model::ArtistRole::Vocal => ArtistRole::Vocal,
+            model::ArtistRole::Composer => ArtistRole::Composer,
+            model::ArtistRole::Lyricist => ArtistRole::Lyricist,
+            model::ArtistRole::Arranger => ArtistRole::Arranger,
+            model::ArtistRole::Performer => ArtistRole::Performer,
+            model::ArtistRole::Producer => ArtistRole::Producer,
+            model::ArtistRole::Engineer => ArtistRole::Engineer,
+            model::ArtistRole::Illustrator => ArtistRole::Illustrator,
+            model::ArtistRole::Custom(_) => ArtistRole::Custom,
+        }
+    }
+}
+
+impl ArtistRole {
+    /// Convert to role in metadata. `custom_role` is required for [ArtistRole::Custom].
+    pub(crate) fn into_model(self, custom_role: Option<String>) -> Option<model::ArtistRole> {
+        Some(match self {
+            ArtistRole::Vocal => model::ArtistRole::Vocal,
+            ArtistRole::Composer => model::ArtistRole::Composer,
+            ArtistRole::Lyricist => model::ArtistRole::Lyricist,
+            ArtistRole::Arranger => model::ArtistRole::Arranger,
+            ArtistRole::Performer => model::ArtistRole::Performer,
+            ArtistRole::Producer => model::ArtistRole::Producer,
+            ArtistRole::Engineer => model::ArtistRole::Engineer,
+            ArtistRole::Illustrator => model::ArtistRole::Illustrator,
+            ArtistRole::Custom => model::ArtistRole::Custom(custom_role?),
+        })
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum ArtistKind {
+    Person,
+    Group,
+}
+
+impl From<model::ArtistKind> for ArtistKind {
+    fn from(value: model::ArtistKind) -> Self {
+        match value {
+            model::ArtistKind::Person => ArtistKind::Person,
+            model::ArtistKind::Group => ArtistKind::Group,
+        }
+    }
+}
+
+impl From<ArtistKind> for model::ArtistKind {
+    fn from(value: ArtistKind) -> Self {
+        match value {
+            ArtistKind::Person => model::ArtistKind::Person,
+            ArtistKind::Group => model::ArtistKind::Group,
+        }
+    }
+}
+
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 pub enum TrackType {
     Normal,
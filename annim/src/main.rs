@@ -1,12 +1,11 @@
 use annim::{
     auth::{on_connection_init, AuthToken},
-    graphql::{MetadataMutation, MetadataQuery, MetadataSchema},
+    graphql::{
+        MetadataEvents, MetadataMutation, MetadataQuery, MetadataSchema, MetadataSubscription,
+    },
     search::RepositorySearchManager,
 };
-use async_graphql::{
-    http::{graphiql_source, ALL_WEBSOCKET_PROTOCOLS},
-    EmptySubscription,
-};
+use async_graphql::http::{graphiql_source, ALL_WEBSOCKET_PROTOCOLS};
 use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
 use axum::{
     extract::{State, WebSocketUpgrade},
@@ -75,9 +74,10 @@ async fn main() -> anyhow::Result<()> {
     std::fs::create_dir_all(&searcher_directory)?;
     let searcher = RepositorySearchManager::open_or_create(searcher_directory)?;
 
-    let schema = MetadataSchema::build(MetadataQuery, MetadataMutation, EmptySubscription)
+    let schema = MetadataSchema::build(MetadataQuery, MetadataMutation, MetadataSubscription)
         .data(database)
         .data(searcher)
+        .data(MetadataEvents::new())
         .finish();
 
     let app = Router::new()
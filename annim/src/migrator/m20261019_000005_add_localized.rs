@@ -0,0 +1,57 @@
+use sea_orm::DbErr;
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20240817_000001_create_basic_tables::{Album, Disc, Track};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261019_000005_add_localized"
+    }
+}
+
+/// Localized titles and artists, keyed by locale.
+#[derive(Iden)]
+struct Localized;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            Album::Table.into_iden(),
+            Disc::Table.into_iden(),
+            Track::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(json_null(Localized))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            Album::Table.into_iden(),
+            Disc::Table.into_iden(),
+            Track::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(Localized)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ mod m20240817_000001_create_basic_tables;
 mod m20240824_000002_create_tag_tables;
 mod m20240905_000003_add_tag_type_category;
 mod m20240905_000004_album_extra_jsonb;
+mod m20261019_000005_add_localized;
 
 pub struct Migrator;
 
@@ -16,6 +17,7 @@ impl MigratorTrait for Migrator {
             Box::new(m20240824_000002_create_tag_tables::Migration),
             Box::new(m20240905_000003_add_tag_type_category::Migration),
             Box::new(m20240905_000004_album_extra_jsonb::Migration),
+            Box::new(m20261019_000005_add_localized::Migration),
         ]
     }
 }
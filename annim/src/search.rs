@@ -10,17 +10,23 @@ use tantivy::{
     schema::{
         Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, INDEXED, STORED,
     },
-    Index, IndexReader, IndexWriter, Opstamp, Searcher, TantivyDocument, TantivyError,
+    Index, IndexReader, IndexSettings, IndexWriter, Opstamp, Searcher, TantivyDocument,
+    TantivyError,
 };
 use tokio::sync::{RwLock, RwLockReadGuard};
 
-use crate::entities::{album, disc, track};
+use crate::entities::{
+    album, disc,
+    helper::{localized_texts, track_credits},
+    track,
+};
 
 pub struct RepositorySearchManager {
     index: Index,
     index_reader: IndexReader,
     index_writer: Arc<RwLock<IndexWriter>>,
     query_parser: QueryParser,
+    album_query_parser: QueryParser,
 
     pub fields: SearchFields,
 }
@@ -32,17 +38,30 @@ impl RepositorySearchManager {
     {
         let (schema, fields) = SearchFields::new();
         let directory = MmapDirectory::open(directory_path)?;
-        let index = Index::open_or_create(directory, schema)?;
+        let index = match Index::open_or_create(directory.clone(), schema.clone()) {
+            Err(TantivyError::SchemaError(_)) => {
+                tracing::warn!(
+                    "Search index was created with an older schema, recreating it. Run rebuildSearchIndex to index existing metadata."
+                );
+                Index::create(directory, schema, IndexSettings::default())?
+            }
+            index => index?,
+        };
 
         let reader = index.reader()?;
         let writer = index.writer(50_000_000)?;
         let query_parser = QueryParser::for_index(&index, vec![fields.title, fields.artist]);
+        let album_query_parser = QueryParser::for_index(
+            &index,
+            vec![fields.title, fields.edition, fields.catalog, fields.artist],
+        );
 
         let me = Self {
             index,
             index_reader: reader,
             index_writer: Arc::new(RwLock::new(writer)),
             query_parser,
+            album_query_parser,
             fields,
         };
         me.register_tokenizers();
@@ -60,6 +79,8 @@ impl RepositorySearchManager {
         let mut writer = self.index_writer.write().await;
         writer.commit()?;
         writer.garbage_collect_files().await?;
+        // make committed documents searchable immediately, instead of after the reload delay
+        self.index_reader.reload()?;
 
         Ok(())
     }
@@ -94,6 +115,44 @@ impl RepositorySearchManager {
         )
     }
 
+    /// Index names credited in `artists` of a track, in addition to its artist.
+    pub fn add_credits(&self, document: &mut TantivyDocument, artists: Option<&serde_json::Value>) {
+        for credit in track_credits(artists).unwrap_or_default() {
+            document.add_text(self.fields.artist, &credit.name);
+        }
+    }
+
+    /// Index edition and catalog of an album.
+    pub fn add_edition_and_catalog(
+        &self,
+        document: &mut TantivyDocument,
+        edition: Option<&str>,
+        catalog: Option<&str>,
+    ) {
+        if let Some(edition) = edition {
+            document.add_text(self.fields.edition, edition);
+        }
+        if let Some(catalog) = catalog {
+            document.add_text(self.fields.catalog, catalog);
+        }
+    }
+
+    /// Index localized titles and artists of an album, a disc or a track.
+    pub fn add_localized(
+        &self,
+        document: &mut TantivyDocument,
+        localized: Option<&serde_json::Value>,
+    ) {
+        for (_, text) in localized_texts(localized).unwrap_or_default().iter() {
+            if let Some(title) = &text.title {
+                document.add_text(self.fields.title, title);
+            }
+            if let Some(artist) = &text.artist {
+                document.add_text(self.fields.artist, artist);
+            }
+        }
+    }
+
     pub fn searcher(&self) -> Searcher {
         self.index_reader.searcher()
     }
@@ -102,6 +161,11 @@ impl RepositorySearchManager {
         &self.query_parser
     }
 
+    /// Query parser over title, edition, catalog and artist, used to search albums.
+    pub fn album_query_parser(&self) -> &QueryParser {
+        &self.album_query_parser
+    }
+
     pub fn deserialize_document(&self, doc: TantivyDocument) -> (i64, Option<i64>, Option<i64>) {
         let album_db_id = doc.get_first(self.fields.album_db_id).unwrap();
         let disc_db_id = doc.get_first(self.fields.disc_db_id).unwrap();
@@ -130,33 +194,49 @@ impl SearchWriter<'_> {
     }
 
     pub fn add_album_info(&self, album: &album::Model) -> tantivy::Result<Opstamp> {
-        self.add_document(self.manager.build_track_document(
+        let mut document = self.manager.build_track_document(
             &album.title,
             &album.artist,
             album.id as i64,
             None,
             None,
-        ))
+        );
+        self.manager.add_edition_and_catalog(
+            &mut document,
+            album.edition.as_deref(),
+            album.catalog.as_deref(),
+        );
+        self.manager
+            .add_localized(&mut document, album.localized.as_ref());
+        self.add_document(document)
     }
 
     pub fn add_disc_info(&self, disc: &disc::Model) -> tantivy::Result<Opstamp> {
-        self.lock.add_document(self.manager.build_track_document(
+        let mut document = self.manager.build_track_document(
             disc.title.as_deref().unwrap_or_default(),
             disc.artist.as_deref().unwrap_or_default(),
             disc.album_db_id as i64,
             Some(disc.id as i64),
             None,
-        ))
+        );
+        self.manager
+            .add_localized(&mut document, disc.localized.as_ref());
+        self.lock.add_document(document)
     }
 
     pub fn add_track_info(&self, track: &track::Model) -> tantivy::Result<Opstamp> {
-        self.lock.add_document(self.manager.build_track_document(
+        let mut document = self.manager.build_track_document(
             &track.title,
             &track.artist,
             track.album_db_id as i64,
             Some(track.disc_db_id as i64),
             Some(track.id as i64),
-        ))
+        );
+        self.manager
+            .add_credits(&mut document, track.artists.as_ref());
+        self.manager
+            .add_localized(&mut document, track.localized.as_ref());
+        self.lock.add_document(document)
     }
 
     pub fn delete_query(&self, query: Box<dyn Query>) -> tantivy::Result<Opstamp> {
@@ -181,6 +261,10 @@ pub struct SearchFields {
 
     pub title: Field,
     pub artist: Field,
+    /// Only indexed for albums.
+    pub edition: Field,
+    /// Only indexed for albums.
+    pub catalog: Field,
 }
 
 impl SearchFields {
@@ -207,6 +291,24 @@ impl SearchFields {
             ),
         );
 
+        let edition = schema_builder.add_text_field(
+            "edition",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer("lang_ja")
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+        // catalogs like `KSLA-0178` are split on punctuation and searched as phrases
+        let catalog = schema_builder.add_text_field(
+            "catalog",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer("default")
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+
         (
             schema_builder.build(),
             Self {
@@ -215,6 +317,8 @@ impl SearchFields {
                 track_db_id,
                 title,
                 artist,
+                edition,
+                catalog,
             },
         )
     }
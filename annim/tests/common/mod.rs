@@ -0,0 +1,104 @@
+// not every test uses all of the helpers
+#![allow(dead_code)]
+
+use annim::{
+    entities::{
+        album, album_tag_relation, disc,
+        sea_orm_active_enums::{MetadataOrganizeLevel, TagType},
+        tag_info, tag_relation, track,
+    },
+    graphql::{
+        MetadataEvents, MetadataMutation, MetadataQuery, MetadataSchema, MetadataSubscription,
+    },
+    search::RepositorySearchManager,
+};
+use sea_orm::{
+    prelude::Uuid, ActiveModelTrait, ActiveValue, ConnectionTrait, Database, DatabaseConnection,
+    DbBackend, EntityTrait, Schema,
+};
+
+/// Connects to an in-memory sqlite database with tables created from entities.
+///
+/// Migrations are written for postgres, so they are not used here.
+pub async fn connect() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let schema = Schema::new(DbBackend::Sqlite);
+    create_table(&db, &schema, album::Entity).await;
+    create_table(&db, &schema, disc::Entity).await;
+    create_table(&db, &schema, track::Entity).await;
+    create_table(&db, &schema, tag_info::Entity).await;
+    create_table(&db, &schema, tag_relation::Entity).await;
+    create_table(&db, &schema, album_tag_relation::Entity).await;
+    db
+}
+
+async fn create_table<E: EntityTrait>(db: &DatabaseConnection, schema: &Schema, entity: E) {
+    let statement = db
+        .get_database_backend()
+        .build(&schema.create_table_from_entity(entity));
+    db.execute(statement).await.unwrap();
+}
+
+/// Opens an empty search index in a temporary directory named after `name`.
+pub fn open_searcher(name: &str) -> RepositorySearchManager {
+    let directory = std::env::temp_dir().join(format!("annim-test-{name}"));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    RepositorySearchManager::open_or_create(directory).unwrap()
+}
+
+pub fn build_schema(db: DatabaseConnection, searcher: RepositorySearchManager) -> MetadataSchema {
+    MetadataSchema::build(MetadataQuery, MetadataMutation, MetadataSubscription)
+        .data(db)
+        .data(searcher)
+        .data(MetadataEvents::new())
+        .finish()
+}
+
+/// Inserts an album without discs, and adds it to the search index.
+pub async fn insert_album(
+    db: &DatabaseConnection,
+    searcher: &RepositorySearchManager,
+    title: &str,
+    artist: &str,
+) -> album::Model {
+    let now = chrono::Utc::now().naive_utc();
+    let album = album::ActiveModel {
+        album_id: ActiveValue::set(Uuid::new_v4()),
+        title: ActiveValue::set(title.to_string()),
+        edition: ActiveValue::set(None),
+        catalog: ActiveValue::set(None),
+        artist: ActiveValue::set(artist.to_string()),
+        release_year: ActiveValue::set(2024),
+        release_month: ActiveValue::set(None),
+        release_day: ActiveValue::set(None),
+        level: ActiveValue::set(MetadataOrganizeLevel::Initial),
+        created_at: ActiveValue::set(now),
+        updated_at: ActiveValue::set(now),
+        extra: ActiveValue::set(None),
+        localized: ActiveValue::set(None),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    let writer = searcher.writer().await;
+    writer.add_album_info(&album).unwrap();
+    writer.commit().await.unwrap();
+    album
+}
+
+pub async fn insert_tag(db: &DatabaseConnection, name: &str) -> tag_info::Model {
+    let now = chrono::Utc::now().naive_utc();
+    tag_info::ActiveModel {
+        name: ActiveValue::set(name.to_string()),
+        r#type: ActiveValue::set(TagType::Artist),
+        created_at: ActiveValue::set(now),
+        updated_at: ActiveValue::set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+const QUERY: &str = r#"
+query ($keyword: String!, $first: Int, $after: String) {
+    albums(by: { keyword: $keyword }, first: $first, after: $after) {
+        pageInfo {
+            hasPreviousPage
+            hasNextPage
+            endCursor
+        }
+        edges {
+            node {
+                title
+            }
+        }
+    }
+}
+"#;
+
+async fn search(
+    schema: &annim::graphql::MetadataSchema,
+    keyword: &str,
+    first: u64,
+    after: Option<&str>,
+) -> Value {
+    let request =
+        async_graphql::Request::new(QUERY).variables(async_graphql::Variables::from_json(
+            serde_json::json!({ "keyword": keyword, "first": first, "after": after }),
+        ));
+    let response = schema.execute(request).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    response.data.into_json().unwrap()["albums"].take()
+}
+
+fn titles(page: &Value) -> Vec<String> {
+    page["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| edge["node"]["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_search_albums_pagination() {
+    let db = common::connect().await;
+    let searcher = common::open_searcher("search-albums");
+    for title in ["Crescendo", "Overture", "Interlude"] {
+        common::insert_album(&db, &searcher, title, "Lumina").await;
+    }
+    common::insert_album(&db, &searcher, "Nocturne", "Aurora").await;
+    let schema = common::build_schema(db, searcher);
+
+    let page = search(&schema, "Lumina", 2, None).await;
+    assert_eq!(page["pageInfo"]["hasPreviousPage"], false);
+    assert_eq!(page["pageInfo"]["hasNextPage"], true);
+    let mut found = titles(&page);
+    assert_eq!(found.len(), 2);
+
+    let cursor = page["pageInfo"]["endCursor"].as_str().unwrap();
+    let page = search(&schema, "Lumina", 2, Some(cursor)).await;
+    assert_eq!(page["pageInfo"]["hasPreviousPage"], true);
+    assert_eq!(page["pageInfo"]["hasNextPage"], false);
+    found.extend(titles(&page));
+
+    let found: HashSet<_> = found.iter().map(String::as_str).collect();
+    assert_eq!(found, HashSet::from(["Crescendo", "Overture", "Interlude"]));
+
+    // search pages larger than the maximum are clamped instead of rejected
+    let page = search(&schema, "Nocturne", 10_000, None).await;
+    assert_eq!(titles(&page), vec!["Nocturne"]);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::time::Duration;
+
+use annim::{auth::AuthToken, graphql::MetadataSchema};
+use async_graphql::{Request, Response};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+async fn mutate(schema: &MetadataSchema, mutation: String) {
+    let token = std::env::var("ANNIM_AUTH_TOKEN").unwrap_or_else(|_| "114514".to_string());
+    let request = Request::new(mutation).data(AuthToken::new(token));
+    let response = schema.execute(request).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+}
+
+async fn next_event(stream: &mut (impl Stream<Item = Response> + Unpin)) -> Value {
+    let response = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("event should arrive")
+        .unwrap();
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    response.data.into_json().unwrap()["metadataChanged"].take()
+}
+
+#[tokio::test]
+async fn test_subscribe_tag() {
+    let db = common::connect().await;
+    let searcher = common::open_searcher("subscribe-tag");
+    let tagged = common::insert_album(&db, &searcher, "Crescendo", "Lumina").await;
+    let other = common::insert_album(&db, &searcher, "Nocturne", "Aurora").await;
+    let tag = common::insert_tag(&db, "Lumina").await;
+    let another_tag = common::insert_tag(&db, "Aurora").await;
+    let schema = common::build_schema(db, searcher);
+
+    let mut stream = Box::pin(schema.execute_stream(format!(
+        r#"subscription {{
+            metadataChanged(tagId: "{}") {{
+                ... on AlbumEvent {{ kind album {{ title }} }}
+            }}
+        }}"#,
+        tag.id
+    )));
+    // the subscription starts when the stream is polled for the first time
+    assert!(futures::poll!(stream.next()).is_pending());
+
+    // changes of albums without the tag are not sent
+    mutate(
+        &schema,
+        format!(
+            r#"mutation {{ updateAlbumInfo(input: {{ id: "{}", title: "Nocturne II" }}) {{ id }} }}"#,
+            other.id
+        ),
+    )
+    .await;
+
+    // the tag is added
+    mutate(
+        &schema,
+        format!(
+            r#"mutation {{ updateMetadataTags(input: {{ album: "{}" }}, tags: ["{}"]) {{ id }} }}"#,
+            tagged.id, tag.id
+        ),
+    )
+    .await;
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["kind"], "TAGS_UPDATED");
+    assert_eq!(event["album"]["title"], "Crescendo");
+
+    // changes of the album with the tag are sent
+    mutate(
+        &schema,
+        format!(
+            r#"mutation {{ updateAlbumInfo(input: {{ id: "{}", title: "Crescendo II" }}) {{ id }} }}"#,
+            tagged.id
+        ),
+    )
+    .await;
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["kind"], "UPDATED");
+    assert_eq!(event["album"]["title"], "Crescendo II");
+
+    // the tag is replaced, which is sent as the album had the tag before
+    mutate(
+        &schema,
+        format!(
+            r#"mutation {{ updateMetadataTags(input: {{ album: "{}" }}, tags: ["{}"]) {{ id }} }}"#,
+            tagged.id, another_tag.id
+        ),
+    )
+    .await;
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["kind"], "TAGS_UPDATED");
+
+    // the album does not have the tag any more
+    mutate(
+        &schema,
+        format!(
+            r#"mutation {{ updateAlbumInfo(input: {{ id: "{}", title: "Crescendo III" }}) {{ id }} }}"#,
+            tagged.id
+        ),
+    )
+    .await;
+    let next = tokio::time::timeout(Duration::from_millis(200), stream.next()).await;
+    assert!(next.is_err(), "no event should be sent");
+}